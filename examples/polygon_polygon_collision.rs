@@ -11,7 +11,7 @@ use sfml::window::{Key, VideoMode, event, window_style};
 use box2d::world::World;
 use box2d::body::BodyDef;
 use box2d::body::BodyType;
-use box2d::math::Vec2;
+use box2d::math::{Vec2, Scalar};
 
 use time::Duration;
 use time::get_time;
@@ -20,12 +20,11 @@ use time::get_time;
 // Using floating point numbers, shapes work best between 0.1 and 10 meters.
 // It is HIGHLY recommended to have a meter-to-pixel scale to stay within these bounds.
 // Here we will use 1 meter == 100 pixels.
-const meters_to_pixels: f32 = 100.0;
+const meters_to_pixels: Scalar = 100.0;
 
 fn main() {
     let step = 1.0 / 60.0;
     let mut current_time: f64 = 0.0;
-    let mut accumulator: f64 = 0.0;
 
     // Create the window of the application
     let mut window = RenderWindow::new(VideoMode::new_init(800, 600, 32),
@@ -61,11 +60,7 @@ fn main() {
         let frame_time = if !paused { (new_time - current_time).min(0.2) } else { 0.0 };
         current_time = new_time;
 
-        accumulator = accumulator + frame_time;
-        while accumulator >= step && !paused {
-            world.step(step as f32);
-            accumulator -= step;
-        }
+        world.advance(frame_time as Scalar, step);
 
         // Clear the window
         window.clear(&Color::new_rgb(0, 200, 200));
@@ -75,33 +70,34 @@ fn main() {
                 box2d::shape::shape::Shape::CircleShape{center, radius} => {
                     let mut circle = CircleShape::new().expect("Error, cannot create ball.");
                     // Units in Box2D should be converted from Meters to Pixels
-                    let position = (world.bodies[i].position + center).multiply(meters_to_pixels);
-                    let radius = radius * meters_to_pixels;
+                    let position = world.bodies[i].world_point(center).multiply(meters_to_pixels);
+                    // SFML draws in f32, whichever precision the engine is built with
+                    let radius = (radius * meters_to_pixels) as f32;
                     circle.set_radius(radius-1.0);
                     circle.set_outline_thickness(1.0);
                     circle.set_outline_color(&Color::new_rgb(255, 0, 0));
                     circle.set_fill_color(&Color::transparent());
-                    circle.set_position(&Vector2f::new(position.x, position.y));
+                    circle.set_position(&Vector2f::new(position.x as f32, position.y as f32));
                     circle.set_origin(&Vector2f::new(radius, radius));
                     window.draw(&circle);
                 },
 
                 box2d::shape::shape::Shape::LineShape{point1, point2} => {
                     // Units in Box2D should be converted from Meters to Pixels
-                    let point1_global = (world.bodies[i].position + point1).multiply(meters_to_pixels);
-                    let point2_global = (world.bodies[i].position + point2).multiply(meters_to_pixels);
+                    let point1_global = world.bodies[i].world_point(point1).multiply(meters_to_pixels);
+                    let point2_global = world.bodies[i].world_point(point2).multiply(meters_to_pixels);
                     
                     // Latest SFML uses new type, VertexArray, to draw primitive types
                     let mut points = VertexArray::new().unwrap();
                     points.set_primitive_type(Lines);
                     points.append(&Vertex::new_with_pos_color(&Vector2f {
-                                                                    x: point1_global.x,
-                                                                    y: point1_global.y
+                                                                    x: point1_global.x as f32,
+                                                                    y: point1_global.y as f32
                                                                 },
                                                                 &Color::blue()));
                     points.append(&Vertex::new_with_pos_color(&Vector2f {
-                                                                    x: point2_global.x,
-                                                                    y: point2_global.y
+                                                                    x: point2_global.x as f32,
+                                                                    y: point2_global.y as f32
                                                                 }, &Color::blue()));
                     window.draw(&points);
                 },
@@ -111,10 +107,10 @@ fn main() {
                     global_points.set_primitive_type(LinesStrip);
                     for p in points.iter() {
                         // Units in Box2D should be converted from Meters to Pixels
-                        let mut global_point = (world.bodies[i].position + *p).multiply(meters_to_pixels);
+                        let mut global_point = world.bodies[i].world_point(*p).multiply(meters_to_pixels);
                         global_points.append(&Vertex::new_with_pos_color(&Vector2f {
-                                                                                x: global_point.x,
-                                                                                y: global_point.y
+                                                                                x: global_point.x as f32,
+                                                                                y: global_point.y as f32
                                                                             },
                                                                             &Color::blue()));
                     }
@@ -126,22 +122,23 @@ fn main() {
                     global_points.set_primitive_type(LinesStrip);
                     for p in points.iter() {
                         // Units in Box2D should be converted from Meters to Pixels
-                        let global_point = (world.bodies[i].position + *p).multiply(meters_to_pixels);
+                        let global_point = world.bodies[i].world_point(*p).multiply(meters_to_pixels);
                         global_points.append(&Vertex::new_with_pos_color(&Vector2f {
-                                                                            x: global_point.x,
-                                                                            y: global_point.y
+                                                                            x: global_point.x as f32,
+                                                                            y: global_point.y as f32
                                                                         },
                                                                         &Color::red()));
                     }
                     // Close off polygon by adding first point to end
-                    let global_point = (world.bodies[i].position + points[0]).multiply(meters_to_pixels);
+                    let global_point = world.bodies[i].world_point(points[0]).multiply(meters_to_pixels);
                     global_points.append(&Vertex::new_with_pos_color(&Vector2f {
-                                                                            x: global_point.x,
-                                                                            y: global_point.y
+                                                                            x: global_point.x as f32,
+                                                                            y: global_point.y as f32
                                                                         },
                                                                         &Color::red()));
                     window.draw(&global_points);
-                }
+                },
+                _ => {}
             }
         }
         window.display();
@@ -150,46 +147,27 @@ fn main() {
 
 fn setup_box2d() -> World {
     // Safe to use the box2d shapes here, be aware, though, SFML has similar shape names.
-    use box2d::shape::shape::Shape::*;
+    use box2d::shape::shape::Shape;
 
     // Earth gravity, 9.8m/s
     let mut world = World::new(Vec2::new(0.0, 9.8));
 
     // Units in Box2D are 1.0 == 1 meter.
-    let polygon_shape = PolygonShape {
-                            points: vec! [
-                                Vec2::new(-0.75, -0.75),
-                                Vec2::new(-0.75, 0.75),
-                                Vec2::new(0.75, 0.75),
-                                Vec2::new(0.75, -0.75)
-                        ]};
-    let polygon_body_def = BodyDef {
-                            shape: polygon_shape,
-                            body_type: BodyType::StaticBody,
-                            position: Vec2::new(4.0, 4.0),
-                            velocity: Vec2::new(0.0, 0.0),
-                            restitution: 1.0,
-                            mass: 0.0,
-                            gravity_scale: 1.0
-                        };
+    let polygon_shape = Shape::box_shape(0.75, 0.75);
+    let polygon_body_def = BodyDef::builder(polygon_shape)
+                            .body_type(BodyType::StaticBody)
+                            .position(Vec2::new(4.0, 4.0))
+                            .restitution(1.0)
+                            .build();
     world.add_body(polygon_body_def);
 
-    let polygon_shape2 = PolygonShape {
-                            points: vec! [
-                                Vec2::new(-0.75, -0.75),
-                                Vec2::new(-0.75, 0.75),
-                                Vec2::new(0.75, 0.75),
-                                Vec2::new(0.75, -0.75)
-                        ]};
-    let polygon_body_def2 = BodyDef {
-                            shape: polygon_shape2,
-                            body_type: BodyType::DynamicBody,
-                            position: Vec2::new(3.5, 1.0),
-                            velocity: Vec2::new(0.0, 0.0),
-                            restitution: 1.0,
-                            mass: 1.0,
-                            gravity_scale: 1.0
-                        };
+    let polygon_shape2 = Shape::box_shape(0.75, 0.75);
+    let polygon_body_def2 = BodyDef::builder(polygon_shape2)
+                            .body_type(BodyType::DynamicBody)
+                            .position(Vec2::new(3.5, 1.0))
+                            .restitution(1.0)
+                            .mass(1.0)
+                            .build();
     world.add_body(polygon_body_def2);
 
     return world;
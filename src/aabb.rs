@@ -0,0 +1,46 @@
+use super::math::Vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AABB {
+    pub min: Vec2,
+    pub max: Vec2
+}
+
+impl AABB {
+    pub fn new(min: Vec2, max: Vec2) -> AABB {
+        return AABB{ min: min, max: max };
+    }
+
+    // The smallest box containing every point. There must be at least one point.
+    pub fn from_points(points: &[Vec2], offset: Vec2) -> AABB {
+        let mut min = points[0] + offset;
+        let mut max = min;
+        for point in points.iter() {
+            let p = *point + offset;
+            min = Vec2::new(min.x.min(p.x), min.y.min(p.y));
+            max = Vec2::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        return AABB::new(min, max);
+    }
+
+    // Touching boxes count as overlapping.
+    pub fn overlaps(&self, other: &AABB) -> bool {
+        return self.min.x <= other.max.x && other.min.x <= self.max.x &&
+            self.min.y <= other.max.y && other.min.y <= self.max.y;
+    }
+
+    // The smallest box containing both boxes.
+    pub fn combine(&self, other: &AABB) -> AABB {
+        return AABB::new(Vec2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+                         Vec2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)));
+    }
+
+    pub fn center(&self) -> Vec2 {
+        return (self.min + self.max).multiply(0.5);
+    }
+
+    pub fn extents(&self) -> Vec2 {
+        return (self.max - self.min).multiply(0.5);
+    }
+}
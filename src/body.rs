@@ -1,52 +1,926 @@
-use super::math::Vec2;
+use std::fmt;
+use std::error::Error;
+use super::math::{Vec2, Rot, Transform, Scalar, consts};
 use super::shape::shape::Shape;
+use super::aabb::AABB;
+use super::fixture::{Fixture, FixtureDef};
 
-#[derive(Clone)]
+// A body moving slower than these for TIME_TO_SLEEP seconds is put to sleep, unless its
+// world is given other thresholds.
+pub const LINEAR_SLEEP_TOLERANCE: Scalar = 0.01;
+pub const ANGULAR_SLEEP_TOLERANCE: Scalar = 2.0 / 180.0 * consts::PI;
+pub const TIME_TO_SLEEP: Scalar = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BodyType {
 	StaticBody,
 	KinematicBody,
 	DynamicBody
 }
 
+// Why World::try_add_body turned a BodyDef down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyDefError {
+	// The position, velocity, angle, angular velocity, mass or density is NaN or infinite
+	NonFinite,
+	// A dynamic body without a mass of its own and with no shape or fixture that has area
+	ZeroMass,
+}
+
+impl fmt::Display for BodyDefError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BodyDefError::NonFinite => {
+				return write!(f, "the body's position, velocity, angle, angular velocity, mass or density is not finite");
+			},
+			BodyDefError::ZeroMass => {
+				return write!(f, "a dynamic body needs a mass, set one or give it a shape with area");
+			}
+		}
+	}
+}
+
+impl Error for BodyDefError {}
+
+// Identifies a body for as long as it stays in its world. Unlike an index into
+// World::bodies it is unaffected by other bodies being removed, and a handle to a removed
+// body is never handed out again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BodyHandle {
+	pub index: u32,
+	pub generation: u32
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BodyDef {
 	pub shape: Shape,
 	pub body_type: BodyType,
 	pub position: Vec2,
 	pub velocity: Vec2,
-	pub restitution: f32,
-	pub mass: f32,
-	pub gravity_scale: f32
+	pub angle: Scalar,
+	pub angular_velocity: Scalar,
+	pub restitution: Scalar,
+	pub friction: Scalar,
+	// Leave at 0.0 on a dynamic body to compute it from the density and shape
+	pub mass: Scalar,
+	pub density: Scalar,
+	pub gravity_scale: Scalar,
+	// Used instead of the world gravity times gravity_scale when set
+	pub gravity_override: Option<Vec2>,
+	pub linear_damping: Scalar,
+	pub angular_damping: Scalar,
+	pub category_bits: u16,
+	pub mask_bits: u16,
+	// Bodies sharing a positive group always collide and those sharing a negative group
+	// never do, whatever their categories. Zero is no group.
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub group_index: i16,
+	pub allow_sleep: bool,
+	pub fixed_rotation: bool,
+	// Sensors report contacts but never push or get pushed
+	pub is_sensor: bool,
+	// Fast moving or spinning bodies swept against static geometry each step so they
+	// cannot tunnel
+	pub bullet: bool,
+	// Left alone by the engine, for mapping bodies back to game entities
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub user_data: u64,
+	// Shapes beyond the body's own one, each with its own material and filter
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub fixtures: Vec<FixtureDef>,
+	// Fastest the body may move, for an arcade feel, or None for no limit
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub max_linear_speed: Option<Scalar>
+}
+
+impl Default for BodyDef {
+	// A static body with a zero radius circle at the origin.
+	fn default() -> BodyDef {
+		return BodyDef{ shape: Shape::CircleShape{center: Vec2::new_zero(), radius: 0.0}, body_type: BodyType::StaticBody,
+			position: Vec2::new_zero(), velocity: Vec2::new_zero(), angle: 0.0, angular_velocity: 0.0,
+			restitution: 0.0, friction: 0.3, mass: 0.0, density: 1.0, gravity_scale: 1.0, gravity_override: None, linear_damping: 0.0, angular_damping: 0.0,
+			category_bits: 0x0001, mask_bits: 0xFFFF, group_index: 0, allow_sleep: true, fixed_rotation: false,
+			is_sensor: false, bullet: false, user_data: 0, fixtures: Vec::new(), max_linear_speed: None };
+	}
+}
+
+impl BodyDef {
+	pub fn builder(shape: Shape) -> BodyDefBuilder {
+		return BodyDefBuilder{ body_def: BodyDef{ shape: shape, ..Default::default() } };
+	}
+
+	// Catches definitions that would fill the world with NaNs once stepped.
+	pub fn validate(&self) -> Result<(), BodyDefError> {
+		let values = [self.position.x, self.position.y, self.velocity.x, self.velocity.y,
+			self.angle, self.angular_velocity, self.mass, self.density];
+		if values.iter().any(|value| !value.is_finite()) {
+			return Err(BodyDefError::NonFinite);
+		}
+		let shape_mass = self.shape.compute_mass(self.density).mass +
+			self.fixtures.iter().map(|fixture| fixture.shape.compute_mass(fixture.density).mass).sum::<Scalar>();
+		if self.body_type == BodyType::DynamicBody && self.mass <= 0.0 && (shape_mass.is_nan() || shape_mass <= 0.0) {
+			return Err(BodyDefError::ZeroMass);
+		}
+		return Ok(());
+	}
+}
+
+// Builds a BodyDef, leaving every field that is not set at its default.
+pub struct BodyDefBuilder {
+	body_def: BodyDef
+}
+
+impl BodyDefBuilder {
+	pub fn body_type(mut self, body_type: BodyType) -> BodyDefBuilder {
+		self.body_def.body_type = body_type;
+		return self;
+	}
+
+	pub fn position(mut self, position: Vec2) -> BodyDefBuilder {
+		self.body_def.position = position;
+		return self;
+	}
+
+	pub fn velocity(mut self, velocity: Vec2) -> BodyDefBuilder {
+		self.body_def.velocity = velocity;
+		return self;
+	}
+
+	pub fn angle(mut self, angle: Scalar) -> BodyDefBuilder {
+		self.body_def.angle = angle;
+		return self;
+	}
+
+	pub fn angular_velocity(mut self, angular_velocity: Scalar) -> BodyDefBuilder {
+		self.body_def.angular_velocity = angular_velocity;
+		return self;
+	}
+
+	pub fn restitution(mut self, restitution: Scalar) -> BodyDefBuilder {
+		self.body_def.restitution = restitution;
+		return self;
+	}
+
+	pub fn friction(mut self, friction: Scalar) -> BodyDefBuilder {
+		self.body_def.friction = friction;
+		return self;
+	}
+
+	pub fn mass(mut self, mass: Scalar) -> BodyDefBuilder {
+		self.body_def.mass = mass;
+		return self;
+	}
+
+	pub fn density(mut self, density: Scalar) -> BodyDefBuilder {
+		self.body_def.density = density;
+		return self;
+	}
+
+	pub fn gravity_scale(mut self, gravity_scale: Scalar) -> BodyDefBuilder {
+		self.body_def.gravity_scale = gravity_scale;
+		return self;
+	}
+
+	pub fn gravity_override(mut self, gravity: Vec2) -> BodyDefBuilder {
+		self.body_def.gravity_override = Some(gravity);
+		return self;
+	}
+
+	pub fn linear_damping(mut self, linear_damping: Scalar) -> BodyDefBuilder {
+		self.body_def.linear_damping = linear_damping;
+		return self;
+	}
+
+	pub fn angular_damping(mut self, angular_damping: Scalar) -> BodyDefBuilder {
+		self.body_def.angular_damping = angular_damping;
+		return self;
+	}
+
+	pub fn category_bits(mut self, category_bits: u16) -> BodyDefBuilder {
+		self.body_def.category_bits = category_bits;
+		return self;
+	}
+
+	pub fn mask_bits(mut self, mask_bits: u16) -> BodyDefBuilder {
+		self.body_def.mask_bits = mask_bits;
+		return self;
+	}
+
+	pub fn group_index(mut self, group_index: i16) -> BodyDefBuilder {
+		self.body_def.group_index = group_index;
+		return self;
+	}
+
+	pub fn allow_sleep(mut self, allow_sleep: bool) -> BodyDefBuilder {
+		self.body_def.allow_sleep = allow_sleep;
+		return self;
+	}
+
+	pub fn fixed_rotation(mut self, fixed_rotation: bool) -> BodyDefBuilder {
+		self.body_def.fixed_rotation = fixed_rotation;
+		return self;
+	}
+
+	pub fn is_sensor(mut self, is_sensor: bool) -> BodyDefBuilder {
+		self.body_def.is_sensor = is_sensor;
+		return self;
+	}
+
+	pub fn bullet(mut self, bullet: bool) -> BodyDefBuilder {
+		self.body_def.bullet = bullet;
+		return self;
+	}
+
+	pub fn user_data(mut self, user_data: u64) -> BodyDefBuilder {
+		self.body_def.user_data = user_data;
+		return self;
+	}
+
+	pub fn max_linear_speed(mut self, max_linear_speed: Scalar) -> BodyDefBuilder {
+		self.body_def.max_linear_speed = Some(max_linear_speed);
+		return self;
+	}
+
+	// Adds a fixture after the body's own shape and any added before.
+	pub fn fixture(mut self, fixture: FixtureDef) -> BodyDefBuilder {
+		self.body_def.fixtures.push(fixture);
+		return self;
+	}
+
+	pub fn build(self) -> BodyDef {
+		return self.body_def;
+	}
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Body {
 	pub id: usize,
+	pub handle: BodyHandle,
 	pub shape: Shape,
 	pub body_type: BodyType,
 	pub position: Vec2,
 	pub velocity: Vec2,
-	pub restitution: f32,
-	pub mass: f32,
-	pub gravity_scale: f32
+	pub angle: Scalar,
+	pub angular_velocity: Scalar,
+	pub restitution: Scalar,
+	pub friction: Scalar,
+	pub mass: Scalar,
+	pub density: Scalar,
+	// Rotational inertia about the body position, which is what the body rotates around
+	pub inertia: Scalar,
+	pub gravity_scale: Scalar,
+	// Used instead of the world gravity times gravity_scale when set
+	pub gravity_override: Option<Vec2>,
+	pub linear_damping: Scalar,
+	pub angular_damping: Scalar,
+	pub category_bits: u16,
+	pub mask_bits: u16,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub group_index: i16,
+	pub allow_sleep: bool,
+	pub fixed_rotation: bool,
+	pub is_sensor: bool,
+	pub bullet: bool,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub user_data: u64,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub fixtures: Vec<Fixture>,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub max_linear_speed: Option<Scalar>,
+	pub force: Vec2,
+	pub torque: Scalar,
+	// Where the body was before the last step, for drawing it between steps
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub previous_position: Vec2,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub previous_angle: Scalar,
+	// A cache of the bounds of the shape and fixtures, rebuilt by update_aabb rather than saved
+	#[cfg_attr(feature = "serde", serde(skip))]
+	aabb: AABB,
+	awake: bool,
+	sleep_time: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_enabled"))]
+	enabled: bool,
+	// Bumped by set_transform, set_shape and set_enabled
+	#[cfg_attr(feature = "serde", serde(skip))]
+	revision: u32
+}
+
+#[cfg(feature = "serde")]
+fn default_enabled() -> bool {
+	return true;
 }
 
 impl Body {
-	pub fn new(id: usize, body_def: BodyDef) -> Body {
-		return Body{ id: id, shape: body_def.shape, body_type: body_def.body_type, position: body_def.position,
-			velocity: body_def.velocity, restitution: body_def.restitution, mass: body_def.mass, gravity_scale: body_def.gravity_scale};
+	pub fn new(id: usize, handle: BodyHandle, body_def: BodyDef) -> Body {
+		let fixtures: Vec<Fixture> = body_def.fixtures.into_iter().map(Fixture::new).collect();
+		let (mass, inertia) = mass_properties(&body_def.shape, body_def.density, &fixtures, body_def.mass, body_def.body_type);
+
+		let mut body = Body{ id: id, handle: handle, shape: body_def.shape, body_type: body_def.body_type, position: body_def.position,
+			velocity: body_def.velocity, angle: body_def.angle, angular_velocity: body_def.angular_velocity,
+			restitution: body_def.restitution, friction: body_def.friction, mass: mass, density: body_def.density, inertia: inertia,
+			gravity_scale: body_def.gravity_scale, gravity_override: body_def.gravity_override,
+			linear_damping: body_def.linear_damping, angular_damping: body_def.angular_damping,
+			category_bits: body_def.category_bits, mask_bits: body_def.mask_bits,
+			group_index: body_def.group_index, allow_sleep: body_def.allow_sleep,
+			fixed_rotation: body_def.fixed_rotation, is_sensor: body_def.is_sensor, bullet: body_def.bullet,
+			user_data: body_def.user_data, fixtures: fixtures, max_linear_speed: body_def.max_linear_speed, force: Vec2::new_zero(), torque: 0.0,
+			previous_position: body_def.position, previous_angle: body_def.angle, aabb: AABB::new(Vec2::new_zero(), Vec2::new_zero()),
+			awake: true, sleep_time: 0.0, enabled: true, revision: 0};
+		body.update_aabb();
+		return body;
 	}
-	pub fn integrate(&mut self, time_step: f32, gravity: Vec2) {
-		if self.mass <= 0.0 {
+
+	// The bounding box as of the last call to update_aabb.
+	pub fn aabb(&self) -> AABB {
+		return self.aabb;
+	}
+
+	pub fn update_aabb(&mut self) {
+		let transform = self.transform();
+		let mut aabb = self.shape.compute_transformed_aabb(transform);
+		for fixture in self.fixtures.iter() {
+			aabb = aabb.combine(&fixture.shape.compute_transformed_aabb(transform));
+		}
+		self.aabb = aabb;
+	}
+
+	// The body's own shape followed by the shape of each fixture.
+	pub fn shapes<'a>(&'a self) -> impl Iterator<Item = &'a Shape> + 'a {
+		return ::std::iter::once(&self.shape).chain(self.fixtures.iter().map(|fixture| &fixture.shape));
+	}
+
+	// A copy of the body standing in for one of its fixtures, None being the body's own
+	// shape, with the fixture's shape, material and filter and bounds to match. The
+	// world collides these one by one.
+	pub fn fixture_body(&self, fixture: Option<usize>) -> Body {
+		let mut body = Body{ fixtures: Vec::new(), ..self.clone() };
+		if let Some(index) = fixture {
+			let fixture = &self.fixtures[index];
+			body.shape = fixture.shape.clone();
+			body.density = fixture.density;
+			body.friction = fixture.friction;
+			body.restitution = fixture.restitution;
+			body.category_bits = fixture.category_bits;
+			body.mask_bits = fixture.mask_bits;
+			body.group_index = fixture.group_index;
+			body.is_sensor = fixture.is_sensor;
+		}
+		body.update_aabb();
+		return body;
+	}
+
+	// Maps points from the body's local space into world space.
+	pub fn transform(&self) -> Transform {
+		return Transform::new(self.position, self.angle);
+	}
+
+	// The position and angle a fraction alpha of the way from before the last step to now.
+	// Drawing with the alpha World::advance returns hides the steps falling unevenly
+	// between frames.
+	pub fn interpolated_transform(&self, alpha: Scalar) -> (Vec2, Scalar) {
+		let position = self.previous_position + (self.position - self.previous_position).multiply(alpha);
+		let angle = self.previous_angle + (self.angle - self.previous_angle) * alpha;
+		return (position, angle);
+	}
+
+	// A point given relative to the body, in world coordinates.
+	pub fn world_point(&self, local: Vec2) -> Vec2 {
+		return self.transform().apply(local);
+	}
+
+	// A world point relative to the body's position and angle.
+	pub fn local_point(&self, world: Vec2) -> Vec2 {
+		return self.transform().apply_inverse(world);
+	}
+
+	// A direction given relative to the body, in world coordinates. Only the rotation
+	// applies.
+	pub fn world_vector(&self, local: Vec2) -> Vec2 {
+		return Rot::new(self.angle).rotate(local);
+	}
+
+	pub fn local_vector(&self, world: Vec2) -> Vec2 {
+		return Rot::new(self.angle).inv_rotate(world);
+	}
+
+	pub fn position(&self) -> Vec2 {
+		return self.position;
+	}
+
+	pub fn angle(&self) -> Scalar {
+		return self.angle;
+	}
+
+	// Moves the body directly, without any physical response, and wakes it. Static bodies
+	// have to be moved this way, as stepping doesn't refresh their bounds and the world
+	// keeps the copies it collides them as until the body's revision changes.
+	pub fn set_transform(&mut self, position: Vec2, angle: Scalar) {
+		self.position = position;
+		self.angle = angle;
+		self.revision = self.revision.wrapping_add(1);
+		self.update_aabb();
+		if !self.awake {
+			self.set_awake(true);
+		}
+	}
+
+	// Swaps the shape, working out the mass from the density again as set_mass(0.0) does,
+	// and wakes the body. Like set_transform, this is how a static body has to be changed.
+	pub fn set_shape(&mut self, shape: Shape) {
+		self.shape = shape;
+		let (mass, inertia) = mass_properties(&self.shape, self.density, &self.fixtures, 0.0, self.body_type);
+		self.mass = mass;
+		self.inertia = inertia;
+		self.revision = self.revision.wrapping_add(1);
+		self.update_aabb();
+		if !self.awake {
+			self.set_awake(true);
+		}
+	}
+
+	// Changes whenever set_transform, set_shape or set_enabled is called, which is when
+	// the world builds its copies of a static body again.
+	pub fn revision(&self) -> u32 {
+		return self.revision;
+	}
+
+	pub fn linear_velocity(&self) -> Vec2 {
+		return self.velocity;
+	}
+
+	// Gives the body a new mass, with its inertia scaled to match, and wakes it. As in a
+	// BodyDef, zero or less goes back to the mass of the shape at the body's density.
+	pub fn set_mass(&mut self, mass: Scalar) {
+		let (mass, inertia) = mass_properties(&self.shape, self.density, &self.fixtures, mass, self.body_type);
+		self.mass = mass;
+		self.inertia = inertia;
+		if !self.awake {
+			self.set_awake(true);
+		}
+	}
+
+	pub fn set_gravity_scale(&mut self, gravity_scale: Scalar) {
+		self.gravity_scale = gravity_scale;
+		if !self.awake {
+			self.set_awake(true);
+		}
+	}
+
+	// Static bodies cannot be given a velocity.
+	pub fn set_linear_velocity(&mut self, velocity: Vec2) {
+		if self.body_type == BodyType::StaticBody {
 			return;
 		}
-		let inv_mass = 1.0 / self.mass;
-		if !inv_mass.is_nan() && !inv_mass.is_infinite() && inv_mass > 0.0 && time_step > 0.0 {
-			//Add gravity to the object;
-			let force_accum = gravity.multiply(self.gravity_scale);
+		if velocity.length_squared() > 0.0 && !self.awake {
+			self.set_awake(true);
+		}
+		self.velocity = velocity;
+	}
+
+	// False once a NaN or infinity has got into the body's motion.
+	pub fn is_finite(&self) -> bool {
+		return self.position.x.is_finite() && self.position.y.is_finite() && self.angle.is_finite()
+			&& self.velocity.x.is_finite() && self.velocity.y.is_finite() && self.angular_velocity.is_finite();
+	}
+
+	pub fn is_awake(&self) -> bool {
+		return self.awake;
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		return self.enabled;
+	}
+
+	// A disabled body stays in the world with its position and velocity, but isn't moved,
+	// collides with nothing and has its joints switched off until it is enabled again.
+	pub fn set_enabled(&mut self, enabled: bool) {
+		if enabled && !self.enabled {
+			self.set_awake(true);
+		}
+		if enabled != self.enabled {
+			self.revision = self.revision.wrapping_add(1);
+		}
+		self.enabled = enabled;
+	}
+
+	// A sleeping body is not integrated and has no velocity.
+	pub fn set_awake(&mut self, awake: bool) {
+		self.sleep_time = 0.0;
+		if !awake {
+			self.velocity = Vec2::new_zero();
+			self.angular_velocity = 0.0;
+			self.force = Vec2::new_zero();
+			self.torque = 0.0;
+		}
+		self.awake = awake;
+	}
+
+	// How long the body has been slow enough to sleep.
+	pub fn sleep_time(&self) -> Scalar {
+		return self.sleep_time;
+	}
+
+	pub fn update_sleep_time(&mut self, time_step: Scalar) {
+		self.update_sleep_time_below(time_step, LINEAR_SLEEP_TOLERANCE, ANGULAR_SLEEP_TOLERANCE);
+	}
+
+	// Like update_sleep_time, counting the body as still below the given speeds.
+	pub fn update_sleep_time_below(&mut self, time_step: Scalar, linear_tolerance: Scalar, angular_tolerance: Scalar) {
+		if !self.allow_sleep || self.body_type != BodyType::DynamicBody ||
+			self.velocity.length_squared() > linear_tolerance * linear_tolerance ||
+			self.angular_velocity.abs() > angular_tolerance {
+			self.sleep_time = 0.0;
+		} else {
+			self.sleep_time += time_step;
+		}
+	}
+
+	// A shared group decides first, as in Box2D. Otherwise both bodies have to accept the
+	// other's category for the pair to collide. Disabled bodies never collide.
+	pub fn should_collide(&self, other: &Body) -> bool {
+		if !self.enabled || !other.enabled {
+			return false;
+		}
+		if self.group_index != 0 && self.group_index == other.group_index {
+			return self.group_index > 0;
+		}
+		return (self.category_bits & other.mask_bits) != 0 && (other.category_bits & self.mask_bits) != 0;
+	}
+
+	// Zero for bodies that cannot be moved by forces or impulses.
+	// Kinematic bodies count as infinitely heavy, so contacts never push them.
+	pub fn inv_mass(&self) -> Scalar {
+		match self.body_type {
+			BodyType::StaticBody | BodyType::KinematicBody => return 0.0,
+			_ => {}
+		}
+		if self.mass <= 0.0 {
+			return 0.0;
+		}
+		return 1.0 / self.mass;
+	}
+
+	// Zero for bodies that cannot be rotated by torques or impulses.
+	pub fn inv_inertia(&self) -> Scalar {
+		if self.fixed_rotation || self.inertia <= 0.0 || self.inv_mass() == 0.0 {
+			return 0.0;
+		}
+		return 1.0 / self.inertia;
+	}
 
-			let total_accel = force_accum.multiply(inv_mass);
-			self.velocity = self.velocity + total_accel.multiply(time_step);
-			self.position = self.position + self.velocity.multiply(time_step);
+	// Accumulates a force, in newtons, to be applied over the next step.
+	pub fn apply_force(&mut self, force: Vec2) {
+		if self.inv_mass() > 0.0 {
+			if !self.awake {
+				self.set_awake(true);
+			}
+			self.force += force;
 		}
 	}
+
+	// A force applied at a world point away from the body position also adds a torque.
+	pub fn apply_force_at_point(&mut self, force: Vec2, point: Vec2) {
+		if self.inv_mass() > 0.0 {
+			self.apply_force(force);
+			self.torque += (point - self.position).cross(force);
+		}
+	}
+
+	// Changes the velocity immediately.
+	pub fn apply_impulse(&mut self, impulse: Vec2) {
+		let inv_mass = self.inv_mass();
+		if inv_mass > 0.0 {
+			if !self.awake {
+				self.set_awake(true);
+			}
+			self.velocity += impulse.multiply(inv_mass);
+		}
+	}
+
+	// Accumulates a torque, in newton metres, to be applied over the next step.
+	pub fn apply_torque(&mut self, torque: Scalar) {
+		if self.inv_inertia() > 0.0 {
+			if !self.awake {
+				self.set_awake(true);
+			}
+			self.torque += torque;
+		}
+	}
+
+	// Changes the angular velocity immediately.
+	pub fn apply_angular_impulse(&mut self, impulse: Scalar) {
+		let inv_inertia = self.inv_inertia();
+		if inv_inertia > 0.0 {
+			if !self.awake {
+				self.set_awake(true);
+			}
+			self.angular_velocity += impulse * inv_inertia;
+		}
+	}
+
+	pub fn integrate(&mut self, time_step: Scalar, gravity: Vec2) {
+		self.integrate_limited(time_step, gravity, Scalar::INFINITY, Scalar::INFINITY);
+	}
+
+	// Integrates like integrate, but never moves the body further than max_translation or
+	// turns it further than max_rotation. The velocity is cut down to match, as in Box2D.
+	// A dynamic body going faster than its max_linear_speed is slowed to it first.
+	pub fn integrate_limited(&mut self, time_step: Scalar, gravity: Vec2, max_translation: Scalar, max_rotation: Scalar) {
+		let inv_mass = self.inv_mass();
+		if self.fixed_rotation {
+			self.angular_velocity = 0.0;
+		}
+		if self.body_type == BodyType::KinematicBody && time_step > 0.0 {
+			//Kinematic bodies follow their velocity and nothing else
+			self.limit_motion(time_step, max_translation, max_rotation);
+			self.position += self.velocity.multiply(time_step);
+			self.angle += self.angular_velocity * time_step;
+		} else if inv_mass > 0.0 && time_step > 0.0 && self.awake {
+			//Gravity is an acceleration, forces are scaled by the mass
+			let body_gravity = match self.gravity_override {
+				Some(gravity_override) => gravity_override,
+				None => gravity.multiply(self.gravity_scale)
+			};
+			let total_accel = body_gravity + self.force.multiply(inv_mass);
+			self.velocity += total_accel.multiply(time_step);
+			self.angular_velocity += self.torque * self.inv_inertia() * time_step;
+
+			//Damping as in Box2D, v *= 1 / (1 + dt * c), which stays stable for large steps
+			self.velocity = self.velocity.multiply(1.0 / (1.0 + time_step * self.linear_damping));
+			self.angular_velocity *= 1.0 / (1.0 + time_step * self.angular_damping);
+
+			self.limit_speed();
+			self.limit_motion(time_step, max_translation, max_rotation);
+			self.position += self.velocity.multiply(time_step);
+			self.angle += self.angular_velocity * time_step;
+		}
+		self.force = Vec2::new_zero();
+		self.torque = 0.0;
+	}
+
+	// Slows the body down to its max_linear_speed, keeping its direction. The world does
+	// this again after solving contacts and joints, so pushes can't carry it past the cap.
+	pub fn limit_speed(&mut self) {
+		if let Some(max_linear_speed) = self.max_linear_speed {
+			let speed = self.velocity.length();
+			if speed > max_linear_speed {
+				self.velocity = self.velocity.multiply(max_linear_speed.max(0.0) / speed);
+			}
+		}
+	}
+
+	fn limit_motion(&mut self, time_step: Scalar, max_translation: Scalar, max_rotation: Scalar) {
+		let translation = self.velocity.length() * time_step;
+		if translation > max_translation {
+			self.velocity = self.velocity.multiply(max_translation / translation);
+		}
+		let rotation = (self.angular_velocity * time_step).abs();
+		if rotation > max_rotation {
+			self.angular_velocity *= max_rotation / rotation;
+		}
+	}
+}
+
+// The mass and inertia of a body with the shape and fixtures, each at its own density. An
+// explicit mass keeps their inertia, scaled to match.
+fn mass_properties(shape: &Shape, density: Scalar, fixtures: &[Fixture], mass: Scalar, body_type: BodyType) -> (Scalar, Scalar) {
+	let mut mass_data = shape.compute_mass(density);
+	for fixture in fixtures.iter() {
+		let fixture_mass = fixture.shape.compute_mass(fixture.density);
+		mass_data.mass += fixture_mass.mass;
+		mass_data.inertia += fixture_mass.inertia;
+	}
+	let mut mass = mass;
+	let mut inertia = mass_data.inertia;
+	if body_type == BodyType::DynamicBody {
+		if mass <= 0.0 {
+			mass = mass_data.mass;
+		} else if mass_data.mass > 0.0 {
+			inertia *= mass / mass_data.mass;
+		}
+	}
+	return (mass, inertia);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn body(def: BodyDef) -> Body {
+		return Body::new(0, BodyHandle{ index: 0, generation: 0 }, def);
+	}
+
+	fn dynamic_box(mass: Scalar) -> Body {
+		return body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody).mass(mass).build());
+	}
+
+	#[test]
+	fn impulse_changes_velocity_by_impulse_over_mass() {
+		let mut dynamic = dynamic_box(2.0);
+		dynamic.apply_impulse(Vec2::new(4.0, -1.0));
+		assert!(dynamic.velocity.approx_eq(Vec2::new(2.0, -0.5), 1.0e-6));
+	}
+
+	#[test]
+	fn force_is_integrated_once_and_cleared() {
+		let mut dynamic = dynamic_box(2.0);
+		dynamic.apply_force(Vec2::new(4.0, 0.0));
+		dynamic.integrate(0.5, Vec2::new_zero());
+		assert!(dynamic.velocity.approx_eq(Vec2::new(1.0, 0.0), 1.0e-6));
+		assert_eq!(dynamic.force, Vec2::new_zero());
+		dynamic.integrate(0.5, Vec2::new_zero());
+		assert!(dynamic.velocity.approx_eq(Vec2::new(1.0, 0.0), 1.0e-6));
+	}
+
+	#[test]
+	fn force_off_center_adds_torque() {
+		let mut dynamic = dynamic_box(2.0);
+		dynamic.apply_force_at_point(Vec2::new(0.0, 1.0), Vec2::new(1.0, 0.0));
+		assert_eq!(dynamic.torque, 1.0);
+	}
+
+	#[test]
+	fn static_bodies_ignore_forces_and_impulses() {
+		let mut ground = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).build());
+		ground.apply_force(Vec2::new(1.0, 0.0));
+		ground.apply_impulse(Vec2::new(1.0, 0.0));
+		ground.integrate(1.0, Vec2::new(0.0, 9.8));
+		assert_eq!(ground.velocity, Vec2::new_zero());
+		assert_eq!(ground.position, Vec2::new_zero());
+	}
+
+	#[test]
+	fn damping_slows_a_body_without_reversing_it() {
+		let mut damped = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.velocity(Vec2::new(10.0, 0.0)).angular_velocity(4.0).linear_damping(1.0).angular_damping(1.0).build());
+		let mut speed = damped.velocity.x;
+		let mut spin = damped.angular_velocity;
+		for _ in 0..20 {
+			// A large step as well, which naive damping would overshoot
+			damped.integrate(1.5, Vec2::new_zero());
+			assert!(damped.velocity.x > 0.0 && damped.velocity.x < speed);
+			assert!(damped.angular_velocity > 0.0 && damped.angular_velocity < spin);
+			speed = damped.velocity.x;
+			spin = damped.angular_velocity;
+		}
+	}
+
+	#[test]
+	fn mass_comes_from_density_unless_given() {
+		let derived = body(BodyDef::builder(Shape::box_shape(1.0, 1.0)).body_type(BodyType::DynamicBody).density(0.5).build());
+		assert!((derived.mass - 2.0).abs() < 1.0e-5);
+		let given = body(BodyDef::builder(Shape::box_shape(1.0, 1.0)).body_type(BodyType::DynamicBody).density(0.5).mass(6.0).build());
+		assert_eq!(given.mass, 6.0);
+		// The inertia is scaled to the given mass
+		assert!((given.inertia - 3.0 * derived.inertia).abs() < 1.0e-4);
+	}
+
+	#[test]
+	fn builder_with_only_a_shape_makes_a_valid_static_body() {
+		let def = BodyDef::builder(Shape::box_shape(1.0, 2.0)).build();
+		assert_eq!(def.body_type, BodyType::StaticBody);
+		assert_eq!(def.position, Vec2::new_zero());
+		assert_eq!(def.friction, BodyDef::default().friction);
+		assert_eq!(def.category_bits, 0x0001);
+		assert_eq!(def.mask_bits, 0xFFFF);
+		assert!(def.validate().is_ok());
+
+		let def = BodyDef::builder(Shape::box_shape(1.0, 2.0)).position(Vec2::new(1.0, 2.0)).restitution(0.5).gravity_scale(0.0).build();
+		assert_eq!(def.position, Vec2::new(1.0, 2.0));
+		assert_eq!(def.restitution, 0.5);
+		assert_eq!(def.gravity_scale, 0.0);
+	}
+
+	#[test]
+	fn set_transform_moves_the_body_and_its_bounds() {
+		let mut dynamic = dynamic_box(1.0);
+		dynamic.set_awake(false);
+		dynamic.set_transform(Vec2::new(3.0, -2.0), 0.5);
+		assert_eq!(dynamic.position(), Vec2::new(3.0, -2.0));
+		assert_eq!(dynamic.angle(), 0.5);
+		assert!(dynamic.aabb().center().approx_eq(Vec2::new(3.0, -2.0), 1.0e-5));
+		assert!(dynamic.is_awake());
+	}
+
+	#[test]
+	fn set_linear_velocity_wakes_and_skips_static_bodies() {
+		let mut dynamic = dynamic_box(1.0);
+		dynamic.set_awake(false);
+		dynamic.set_linear_velocity(Vec2::new(1.0, 0.0));
+		assert_eq!(dynamic.linear_velocity(), Vec2::new(1.0, 0.0));
+		assert!(dynamic.is_awake());
+
+		let mut ground = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).build());
+		ground.set_linear_velocity(Vec2::new(1.0, 0.0));
+		assert_eq!(ground.linear_velocity(), Vec2::new_zero());
+	}
+
+	#[test]
+	fn torque_and_angular_impulse_spin_by_inverse_inertia() {
+		let mut dynamic = dynamic_box(1.0);
+		let inertia = dynamic.inertia;
+		dynamic.apply_torque(2.0);
+		dynamic.integrate(0.1, Vec2::new_zero());
+		assert!((dynamic.angular_velocity - 0.2 / inertia).abs() < 1.0e-5);
+		assert_eq!(dynamic.torque, 0.0);
+		dynamic.apply_angular_impulse(1.0);
+		assert!((dynamic.angular_velocity - 1.2 / inertia).abs() < 1.0e-4);
+
+		let mut fixed = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).build());
+		fixed.apply_torque(5.0);
+		fixed.apply_angular_impulse(5.0);
+		fixed.integrate(0.1, Vec2::new_zero());
+		assert_eq!(fixed.angular_velocity, 0.0);
+	}
+
+	#[test]
+	fn local_and_world_conversions_are_inverse() {
+		let rotated = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.position(Vec2::new(3.0, -2.0)).angle(0.7).build());
+		let point = Vec2::new(0.3, 1.2);
+		assert!(rotated.local_point(rotated.world_point(point)).approx_eq(point, 1.0e-5));
+		assert!(rotated.local_vector(rotated.world_vector(point)).approx_eq(point, 1.0e-5));
+		assert!(rotated.world_point(Vec2::new_zero()).approx_eq(Vec2::new(3.0, -2.0), 1.0e-6));
+		let angle: Scalar = 0.7;
+		assert!(rotated.world_vector(Vec2::new(1.0, 0.0)).approx_eq(Vec2::new(angle.cos(), angle.sin()), 1.0e-6));
+	}
+
+	#[test]
+	fn doubling_the_mass_halves_the_acceleration() {
+		let mut light = dynamic_box(1.0);
+		let mut heavy = dynamic_box(1.0);
+		let inertia = heavy.inertia;
+		heavy.set_mass(2.0);
+		assert!((heavy.inertia - 2.0 * inertia).abs() < 1.0e-6);
+		light.apply_force(Vec2::new(1.0, 0.0));
+		heavy.apply_force(Vec2::new(1.0, 0.0));
+		light.integrate(0.1, Vec2::new_zero());
+		heavy.integrate(0.1, Vec2::new_zero());
+		assert!((light.velocity.x - 2.0 * heavy.velocity.x).abs() < 1.0e-6);
+
+		// A mass of zero falls back to the mass of the shape
+		heavy.set_mass(0.0);
+		assert!((heavy.mass - 1.0).abs() < 1.0e-6);
+	}
+
+	#[test]
+	fn zero_gravity_scale_floats() {
+		let mut floating = dynamic_box(1.0);
+		floating.set_gravity_scale(0.0);
+		floating.integrate(0.1, Vec2::new(0.0, 9.8));
+		assert_eq!(floating.velocity, Vec2::new_zero());
+	}
+
+	#[test]
+	fn limited_integration_caps_each_step() {
+		let mut spinning = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.angular_velocity(1.0e6).velocity(Vec2::new(1.0e5, 0.0)).build());
+		for _ in 0..5 {
+			let (position, angle) = (spinning.position, spinning.angle);
+			spinning.integrate_limited(1.0 / 60.0, Vec2::new_zero(), 2.0, 0.1);
+			assert!((spinning.angle - angle).abs() <= 0.1 + 1.0e-5);
+			assert!((spinning.position - position).length() <= 2.0 + 1.0e-4);
+		}
+		// The velocities are cut down to what was moved
+		assert!((spinning.angular_velocity - 6.0).abs() < 1.0e-3);
+		assert!((spinning.velocity.x - 120.0).abs() < 1.0e-2);
+	}
+
+	#[test]
+	fn non_finite_angular_velocity_is_named_in_the_error() {
+		let spinning = BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.angular_velocity(Scalar::INFINITY).build();
+		let error = spinning.validate().unwrap_err();
+		assert_eq!(error, BodyDefError::NonFinite);
+		assert!(error.to_string().contains("angular velocity"));
+	}
+
+	#[test]
+	fn angle_and_angular_velocity_carry_over_and_integrate() {
+		let mut spinning = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.angle(0.5).angular_velocity(2.0).build());
+		assert_eq!((spinning.angle, spinning.angular_velocity), (0.5, 2.0));
+		spinning.integrate(0.25, Vec2::new_zero());
+		assert!((spinning.angle - 1.0).abs() < 1.0e-6);
+		assert_eq!(spinning.position, Vec2::new_zero());
+		// Points on the body turn with it
+		let corner = spinning.world_point(Vec2::new(0.5, 0.0));
+		assert!(corner.approx_eq(Vec2::new(0.5 * (1.0 as Scalar).cos(), 0.5 * (1.0 as Scalar).sin()), 1.0e-6));
+	}
+
+	#[test]
+	fn fixed_rotation_moves_but_never_turns() {
+		let mut player = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+			.mass(1.0).angular_velocity(3.0).fixed_rotation(true).build());
+		assert_eq!(player.inv_inertia(), 0.0);
+		player.apply_force_at_point(Vec2::new(0.0, 4.0), Vec2::new(0.5, 0.0));
+		player.apply_angular_impulse(1.0);
+		player.integrate(0.5, Vec2::new_zero());
+		assert!(player.velocity.approx_eq(Vec2::new(0.0, 2.0), 1.0e-6));
+		assert_eq!((player.angle, player.angular_velocity), (0.0, 0.0));
+	}
 }
@@ -1,5 +1,19 @@
 use super::body::Body;
+use super::math::Scalar;
 
+// Finds the pairs of bodies that might be colliding. Each pair is returned once, as
+// (lower index, higher index).
 pub trait BroadPhase {
-    fn run(&self, bodies: &Vec<Body>) -> Vec<(Body, Body)>;
+    fn run(&self, bodies: &Vec<Body>) -> Vec<(usize, usize)>;
+}
+
+// The broad phases World can be switched between.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BroadphaseKind {
+     // Every pair is tested
+     BruteForce,
+     // Sorted along the x axis and swept, the default
+     SortAndSweep,
+     // Bucketed into a grid of square cells
+     SpatialHash{cell_size: Scalar},
 }
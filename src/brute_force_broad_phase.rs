@@ -0,0 +1,26 @@
+use super::body::Body;
+use super::broad_phase::BroadPhase;
+
+// Tests every pair of bodies, which is as fast as anything for a handful of them.
+pub struct BruteForceBroadPhase;
+
+impl BroadPhase for BruteForceBroadPhase {
+    fn run(&self, bodies: &Vec<Body>) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for a in 0..bodies.len() {
+            let aabb_a = bodies[a].aabb();
+            for (b, body_b) in bodies.iter().enumerate().skip(a + 1) {
+                if aabb_a.overlaps(&body_b.aabb()) {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        return pairs;
+    }
+}
+
+impl BruteForceBroadPhase {
+    pub fn new() -> BruteForceBroadPhase {
+        return BruteForceBroadPhase
+    }
+}
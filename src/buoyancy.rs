@@ -0,0 +1,126 @@
+use super::math::{Vec2, Transform, Scalar};
+use super::aabb::AABB;
+use super::shape::shape::{Shape, signed_area};
+use super::shape::ellipse::ellipse_points;
+use super::shape::polygon::convex_hull;
+
+// A box of still fluid. Dynamic bodies overlapping it are pushed up against gravity by
+// the weight of the fluid they displace and slowed by drag, both acting at the middle of
+// the part that is under the surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BuoyancyRegion {
+    pub aabb: AABB,
+    // Mass per unit area, so bodies of a lower density float
+    pub density: Scalar,
+    // Drag force per unit of submerged area and unit of speed
+    pub linear_drag: Scalar
+}
+
+impl BuoyancyRegion {
+    pub fn new(aabb: AABB, density: Scalar, linear_drag: Scalar) -> BuoyancyRegion {
+        return BuoyancyRegion{ aabb: aabb, density: density, linear_drag: linear_drag };
+    }
+
+    // The area of the shape, on a body with the given transform, that lies inside the
+    // region and the centroid of that part in world coordinates. Round shapes are clipped
+    // as polygons and their area scaled back up to the true one.
+    pub fn submerged(&self, shape: &Shape, transform: Transform) -> (Scalar, Vec2) {
+        let outline = match *shape {
+            Shape::PolygonShape{ref points} => points.clone(),
+            Shape::CircleShape{center, radius} => ellipse_points(center, radius, radius),
+            Shape::EllipseShape{center, radius_x, radius_y} => ellipse_points(center, radius_x, radius_y),
+            Shape::CapsuleShape{center1, center2, radius} => {
+                let mut points = ellipse_points(center1, radius, radius);
+                points.extend(ellipse_points(center2, radius, radius));
+                convex_hull(&points)
+            },
+            _ => return (0.0, transform.position)
+        };
+        let mut world: Vec<Vec2> = outline.iter().map(|&point| transform.apply(point)).collect();
+        let outline_area = signed_area(&world);
+        if outline_area == 0.0 {
+            return (0.0, transform.position);
+        }
+        if outline_area < 0.0 {
+            world.reverse();
+        }
+
+        let clipped = clip_to_aabb(world, self.aabb);
+        let (area, centroid) = polygon_area_centroid(&clipped);
+        if area <= 0.0 {
+            return (0.0, transform.position);
+        }
+        return (area * shape.area() / (0.5 * outline_area.abs()), centroid);
+    }
+}
+
+// Sutherland-Hodgman clipping of a counter clockwise polygon against each side of the box.
+fn clip_to_aabb(polygon: Vec<Vec2>, aabb: AABB) -> Vec<Vec2> {
+    let sides = [(Vec2::new(1.0, 0.0), aabb.min.x), (Vec2::new(-1.0, 0.0), -aabb.max.x),
+        (Vec2::new(0.0, 1.0), aabb.min.y), (Vec2::new(0.0, -1.0), -aabb.max.y)];
+    let mut clipped = polygon;
+    for &(normal, offset) in sides.iter() {
+        if clipped.is_empty() {
+            break;
+        }
+        // Points with point.dot(normal) >= offset are inside this side
+        let input = clipped;
+        clipped = Vec::with_capacity(input.len() + 1);
+        for i in 0..input.len() {
+            let current = input[i];
+            let next = input[(i + 1) % input.len()];
+            let current_depth = current.dot(normal) - offset;
+            let next_depth = next.dot(normal) - offset;
+            if current_depth >= 0.0 {
+                clipped.push(current);
+            }
+            if (current_depth >= 0.0) != (next_depth >= 0.0) {
+                let t = current_depth / (current_depth - next_depth);
+                clipped.push(current + (next - current).multiply(t));
+            }
+        }
+    }
+    return clipped;
+}
+
+fn polygon_area_centroid(points: &[Vec2]) -> (Scalar, Vec2) {
+    if points.len() < 3 {
+        return (0.0, Vec2::new_zero());
+    }
+    let mut area = 0.0;
+    let mut sum = Vec2::new_zero();
+    for i in 0..points.len() {
+        let (p, q) = (points[i], points[(i + 1) % points.len()]);
+        let cross = p.cross(q);
+        area += 0.5 * cross;
+        sum += (p + q).multiply(cross / 6.0);
+    }
+    if area <= 0.0 {
+        return (0.0, Vec2::new_zero());
+    }
+    return (area, sum.divide(area));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::math::consts;
+
+    #[test]
+    fn submerged_part_is_clipped_at_the_surface() {
+        // Water below y = 0, as y points down
+        let water = BuoyancyRegion::new(AABB::new(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 10.0)), 1.0, 0.0);
+        let (area, centroid) = water.submerged(&Shape::box_shape(0.5, 0.5), Transform::new(Vec2::new(2.0, 0.25), 0.0));
+        assert!((area - 0.75).abs() < 1.0e-5);
+        assert!(centroid.approx_eq(Vec2::new(2.0, 0.375), 1.0e-5));
+
+        // A half sunk circle gives half its true area, not half its outline's
+        let circle = Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5};
+        let (area, _) = water.submerged(&circle, Transform::new(Vec2::new_zero(), 0.0));
+        assert!((area - 0.125 * consts::PI).abs() < 1.0e-4);
+
+        let (area, _) = water.submerged(&circle, Transform::new(Vec2::new(0.0, -2.0), 0.0));
+        assert_eq!(area, 0.0);
+    }
+}
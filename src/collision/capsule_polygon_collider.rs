@@ -0,0 +1,117 @@
+use super::super::shape::shape::Shape::{CapsuleShape, PolygonShape};
+use super::super::shape::shape::{edge_normal, closest_point_on_segment};
+use super::super::body::Body;
+use super::super::math::{Vec2, EPSILON, Scalar};
+use super::super::manifold::{Manifold, ContactPoint, ContactId, FeatureType};
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+use super::sat::{polygon_axes, least_overlap};
+
+pub struct CapsulePolygonCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for CapsulePolygonCollider {
+    fn new(pair: (Body, Body)) -> CapsulePolygonCollider {
+        return CapsulePolygonCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let capsule_shape = self.pair().0.shape;
+        let polygon_shape = self.pair().1.shape;
+
+        match (capsule_shape, polygon_shape) {
+            (CapsuleShape{center1, center2, radius}, PolygonShape{points}) => {
+                let global_center1 = self.pair().0.position + center1;
+                let global_center2 = self.pair().0.position + center2;
+                let polygon_position = self.pair().1.position;
+                let global_points: Vec<Vec2> = points.iter().map(|p| *p + polygon_position).collect();
+                let segment = vec![global_center1, global_center2];
+
+                let mut axes = polygon_axes(&points);
+                if (center2 - center1).length_squared() >= EPSILON {
+                    axes.push(edge_normal(center1, center2));
+                }
+
+                let (normal, penetration) = match least_overlap(&segment, &global_points, &axes) {
+                    Some((mut normal, overlap)) => {
+                        // The segment itself is inside the polygon, push it out along the
+                        // shallowest axis, away from the polygon
+                        let polygon_center = global_points.iter().fold(Vec2::new_zero(), |sum, p| sum + *p).divide(global_points.len() as Scalar);
+                        if ((global_center1 + global_center2).multiply(0.5) - polygon_center).dot(normal) < 0.0 {
+                            normal = -normal;
+                        }
+                        (normal, overlap + radius)
+                    },
+                    None => {
+                        // Otherwise the nearest pair of points between the segment and the
+                        // polygon boundary decides the contact
+                        let mut best_distance = Scalar::MAX;
+                        let mut best_normal = Vec2::new_zero();
+                        for i in 0..global_points.len() {
+                            let vertex1 = global_points[i];
+                            let vertex2 = global_points[(i + 1) % global_points.len()];
+                            let candidates = [
+                                (global_center1, closest_point_on_segment(vertex1, vertex2, global_center1)),
+                                (global_center2, closest_point_on_segment(vertex1, vertex2, global_center2)),
+                                (closest_point_on_segment(global_center1, global_center2, vertex1), vertex1)
+                            ];
+                            for &(capsule_point, polygon_point) in candidates.iter() {
+                                let distance = (capsule_point - polygon_point).length();
+                                if distance < best_distance {
+                                    best_distance = distance;
+                                    best_normal = capsule_point - polygon_point;
+                                }
+                            }
+                        }
+                        if best_distance >= radius {
+                            return ColliderResult::new_empty_false();
+                        }
+                        best_normal.normalize();
+                        (best_normal, radius - best_distance)
+                    }
+                };
+
+                let manifold = capsule_manifold(global_center1, global_center2, radius, &global_points, normal, penetration);
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test capsule to polygon collision without capsule and polygon!!!");
+            }
+        }
+    }
+}
+
+// Contact points along the capsule's segment, trimmed to where it lies over the polygon.
+// A capsule lying flat gets a point at each end, a tilted one only at the low end.
+fn capsule_manifold(center1: Vec2, center2: Vec2, radius: Scalar, polygon: &[Vec2], normal: Vec2, penetration: Scalar) -> Manifold {
+    let tangent = Vec2::new(-normal.y, normal.x);
+    let min_t = polygon.iter().fold(Scalar::MAX, |min, p| min.min(tangent.dot(*p)));
+    let max_t = polygon.iter().fold(Scalar::MIN, |max, p| max.max(tangent.dot(*p)));
+    let deepest = normal.dot(center1).min(normal.dot(center2));
+
+    let (t1, t2) = (tangent.dot(center1), tangent.dot(center2));
+    let mut points: Vec<ContactPoint> = Vec::new();
+    if (t2 - t1).abs() > EPSILON {
+        let fraction1 = ((t1.max(min_t).min(max_t) - t1) / (t2 - t1)).clamp(0.0, 1.0);
+        let fraction2 = ((t2.max(min_t).min(max_t) - t1) / (t2 - t1)).clamp(0.0, 1.0);
+        let fractions = if (fraction2 - fraction1).abs() > EPSILON { vec![fraction1, fraction2] } else { vec![fraction1] };
+        for (i, &fraction) in fractions.iter().enumerate() {
+            let core_point = center1 + (center2 - center1).multiply(fraction);
+            let depth = penetration - (normal.dot(core_point) - deepest);
+            if depth > 0.0 {
+                points.push(ContactPoint::new(core_point - normal.multiply(radius - 0.5 * depth), depth,
+                    ContactId::new(i as u8, FeatureType::Vertex, 0, FeatureType::Face)));
+            }
+        }
+    }
+    if points.is_empty() {
+        let core_point = if normal.dot(center1) <= normal.dot(center2) { center1 } else { center2 };
+        return Manifold::circle(core_point, radius, normal, penetration);
+    }
+    return Manifold::new(normal, penetration, points);
+}
@@ -0,0 +1,56 @@
+use super::super::shape::shape::Shape::{CircleShape, CapsuleShape};
+use super::super::shape::shape::{edge_normal, closest_point_on_segment};
+use super::super::body::Body;
+use super::super::math::{Vec2, EPSILON};
+use super::super::manifold::Manifold;
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+
+pub struct CircleCapsuleCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for CircleCapsuleCollider {
+    fn new(pair: (Body, Body)) -> CircleCapsuleCollider {
+        return CircleCapsuleCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let circle_shape = self.pair().0.shape;
+        let capsule_shape = self.pair().1.shape;
+
+        match (circle_shape, capsule_shape) {
+            (CircleShape{center, radius}, CapsuleShape{center1, center2, radius: capsule_radius}) => {
+                let global_circle_center = self.pair().0.position + center;
+                let global_center1 = self.pair().1.position + center1;
+                let global_center2 = self.pair().1.position + center2;
+
+                // Against the capsule the circle only sees the nearest point of its segment
+                let closest_point = closest_point_on_segment(global_center1, global_center2, global_circle_center);
+                let mut normal = global_circle_center - closest_point;
+                let distance = normal.normalize();
+                let total_radius = radius + capsule_radius;
+                if distance >= total_radius {
+                    return ColliderResult::new_empty_false();
+                }
+                if distance == 0.0 {
+                    normal = if (center2 - center1).length_squared() < EPSILON {
+                        Vec2::new(0.0, 1.0)
+                    } else {
+                        edge_normal(center1, center2)
+                    };
+                }
+
+                let manifold = Manifold::circle(global_circle_center, radius, normal, total_radius - distance);
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test circle to capsule collision without circle and capsule!!!");
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use super::super::shape::shape::Shape::{CircleShape, ChainLineShape};
 use super::super::body::Body;
-use super::super::math::Vec2;
+use super::super::shape::shape::{edge_normal, closest_point_on_segment};
 use super::super::manifold::Manifold;
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
@@ -25,41 +25,48 @@ impl Collider for CircleChainLineCollider {
         match (circle_shape, chain_line_shape) {
             (CircleShape{center, radius}, ChainLineShape{points}) => {
                 let global_circle_center = self.pair().0.position + center;
-                let mut closest_line_distance = Vec2::new(0.0, 0.0);
+                let chain_position = self.pair().1.position;
+                let mut deepest: Option<Manifold> = None;
 
-                for i in 0..(points.len()-1) {
-                    let global_point1 = points[i] + self.pair().1.position;
-                    let global_point2 = points[i+1] + self.pair().1.position;
+                for i in 0..points.len().saturating_sub(1) {
+                    let global_point1 = points[i] + chain_position;
+                    let global_point2 = points[i+1] + chain_position;
+                    let segment_vector = global_point2 - global_point1;
 
-                    let segment_vector = global_point1 - global_point2;
-                    let mut point_vector = global_circle_center - global_point2;
-
-                    let scalar_projection = point_vector.dot(segment_vector.normal());
-
-                    let mut closest_point = global_point2 + segment_vector.normal().multiply(scalar_projection);
-
-                    if scalar_projection < 0.0 {
-                        closest_point = global_point2;
-                    } else if scalar_projection > segment_vector.length() {
-                        closest_point = global_point1;
+                    // Beyond an end of the segment the circle may be beside the neighbouring
+                    // segment instead, which then makes the contact. Without this the shared
+                    // vertex would push the circle out sideways, as in Box2D's ghost vertices.
+                    let before = segment_vector.dot(global_circle_center - global_point1) <= 0.0;
+                    let after = segment_vector.dot(global_point2 - global_circle_center) <= 0.0;
+                    if before && i > 0 && (global_point1 - (points[i-1] + chain_position)).dot(global_point1 - global_circle_center) > 0.0 {
+                        continue;
+                    }
+                    if after && i + 2 < points.len() && ((points[i+2] + chain_position) - global_point2).dot(global_circle_center - global_point2) > 0.0 {
+                        continue;
                     }
 
-                    let distance_vector = global_circle_center - closest_point;
-
-                    if i == 0 || distance_vector.length() < closest_line_distance.length() {
-                        closest_line_distance = distance_vector;
+                    let closest_point = closest_point_on_segment(global_point1, global_point2, global_circle_center);
+                    let mut normal = global_circle_center - closest_point;
+                    let distance = normal.normalize();
+                    if distance >= radius {
+                        continue;
+                    }
+                    if distance == 0.0 {
+                        normal = edge_normal(global_point1, global_point2);
                     }
-                }
 
-                if closest_line_distance.length() < radius {
-                    let offset = closest_line_distance.normal().multiply((radius - closest_line_distance.length()));
-                    let mut manifold = Manifold{body_a: self.pair().0, body_b: self.pair().1, normal: Vec2::new(0.0, 0.0), penetration: 0.0};
-                    manifold.normal = offset.normal();
-                    manifold.penetration = offset.length();
-                    return ColliderResult::new(Some(manifold), true);
+                    let penetration = radius - distance;
+                    let is_deeper = match deepest {
+                        Some(ref manifold) => penetration > manifold.penetration,
+                        None => true
+                    };
+                    if is_deeper {
+                        deepest = Some(Manifold::circle(global_circle_center, radius, normal, penetration));
+                    }
                 }
 
-                return ColliderResult::new_empty_false();
+                let is_colliding = deepest.is_some();
+                return ColliderResult::new(deepest, is_colliding);
             },
             _ => {
                 panic!("Something happened. Cannot test circle to chain line collision without circle and chain line!!!");
@@ -67,3 +74,27 @@ impl Collider for CircleChainLineCollider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::math::{Vec2, Scalar};
+
+    #[test]
+    fn circle_rolls_through_a_valley_without_snagging() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let ball = world.add_body(BodyDef::builder(CircleShape{center: Vec2::new_zero(), radius: 0.3}).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-3.0, -0.1)).velocity(Vec2::new(2.0, 0.0)).friction(0.5).build());
+        world.add_body(BodyDef::builder(ChainLineShape{points: vec![Vec2::new(-4.0, 0.0), Vec2::new(0.0, 1.0), Vec2::new(4.0, 0.0)]})
+            .friction(0.5).build());
+        let mut slowest: Scalar = 1.0e9;
+        for _ in 0..150 {
+            world.step(1.0 / 60.0);
+            slowest = slowest.min(world.get_body(ball).unwrap().velocity.x);
+        }
+        assert!(world.get_body(ball).unwrap().position.x > 1.0);
+        assert!(slowest > 0.5);
+    }
+}
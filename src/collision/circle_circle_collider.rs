@@ -1,5 +1,5 @@
 use super::super::body::Body;
-use super::super::math::Vec2;
+use super::super::math::{Vec2, Scalar};
 use super::super::manifold::Manifold;
 use super::super::shape::shape::Shape::CircleShape;
 use super::collider_result::ColliderResult;
@@ -37,15 +37,11 @@ impl Collider for CircleCircleCollider {
 
                 let distance = normal.length();
 
-                let mut manifold = Manifold{body_a: self.pair().0, body_b: self.pair().1, normal: Vec2::new(0.0, 0.0), penetration: 0.0};
-
-                if distance != 0.0 {
-                    manifold.penetration = f32::sqrt(total_radius) - distance;
-                    manifold.normal = normal.normal();
+                let manifold = if distance != 0.0 {
+                    Manifold::circle(global_center_a, radius_a, normal.normal(), Scalar::sqrt(total_radius) - distance)
                 } else {
-                    manifold.penetration = radius_a;
-                    manifold.normal = Vec2::new(1.0, 0.0);
-                }
+                    Manifold::circle(global_center_a, radius_a, Vec2::new(1.0, 0.0), radius_a)
+                };
 
                 ColliderResult::new(Some(manifold), true)
             }
@@ -0,0 +1,88 @@
+use super::super::shape::shape::Shape::{CircleShape, EdgeShape};
+use super::super::shape::shape::{edge_normal, closest_point_on_segment};
+use super::super::body::Body;
+use super::super::manifold::Manifold;
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+
+pub struct CircleEdgeCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for CircleEdgeCollider {
+    fn new(pair: (Body, Body)) -> CircleEdgeCollider {
+        return CircleEdgeCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let circle_shape = self.pair().0.shape;
+        let edge_shape = self.pair().1.shape;
+
+        match (circle_shape, edge_shape) {
+            (CircleShape{center, radius}, EdgeShape{vertex1, vertex2, one_sided}) => {
+                let global_circle_center = self.pair().0.position + center;
+                let global_vertex1 = self.pair().1.position + vertex1;
+                let global_vertex2 = self.pair().1.position + vertex2;
+                let edge_normal = edge_normal(vertex1, vertex2);
+
+                // A one sided edge ignores circles whose center is behind it
+                if one_sided && edge_normal.dot(global_circle_center - global_vertex1) < 0.0 {
+                    return ColliderResult::new_empty_false();
+                }
+
+                let closest_point = closest_point_on_segment(global_vertex1, global_vertex2, global_circle_center);
+
+                let mut normal = global_circle_center - closest_point;
+                let distance = normal.normalize();
+                if distance >= radius {
+                    return ColliderResult::new_empty_false();
+                }
+                if distance == 0.0 {
+                    normal = edge_normal;
+                }
+
+                let manifold = Manifold::circle(global_circle_center, radius, normal, radius - distance);
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test circle to edge collision without circle and edge!!!");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::body::{BodyDef, BodyHandle};
+    use super::super::super::math::Vec2;
+    use super::super::super::shape::shape::Shape;
+
+    fn collide(circle_position: Vec2, one_sided: bool) -> ColliderResult {
+        let circle = BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5}).position(circle_position).build();
+        let edge = BodyDef::builder(Shape::EdgeShape{vertex1: Vec2::new(-5.0, 0.0), vertex2: Vec2::new(5.0, 0.0), one_sided: one_sided}).build();
+        let pair = (Body::new(0, BodyHandle{ index: 0, generation: 0 }, circle), Body::new(1, BodyHandle{ index: 1, generation: 0 }, edge));
+        return CircleEdgeCollider::new(pair).colliding();
+    }
+
+    #[test]
+    fn normal_points_from_the_edge_to_the_circle() {
+        let result = collide(Vec2::new(1.0, -0.4), true);
+        assert!(result.is_colliding);
+        let manifold = result.manifold.unwrap();
+        assert!(manifold.normal.approx_eq(Vec2::new(0.0, -1.0), 1.0e-6));
+        assert!((manifold.penetration - 0.1).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn one_sided_edges_ignore_circles_behind_them() {
+        assert!(!collide(Vec2::new(1.0, 0.4), true).is_colliding);
+        let result = collide(Vec2::new(1.0, 0.4), false);
+        assert!(result.is_colliding);
+        assert!(result.manifold.unwrap().normal.approx_eq(Vec2::new(0.0, 1.0), 1.0e-6));
+    }
+}
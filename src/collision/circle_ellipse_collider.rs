@@ -0,0 +1,52 @@
+use super::super::shape::shape::Shape::{CircleShape, EllipseShape};
+use super::super::shape::ellipse::{closest_point_on_ellipse, ellipse_normal, unit_circle_point};
+use super::super::body::Body;
+use super::super::manifold::Manifold;
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+
+// Unlike the other colliders this one takes its bodies as they are, not moved into world
+// space, because the ellipse's axes turn with its body.
+pub struct CircleEllipseCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for CircleEllipseCollider {
+    fn new(pair: (Body, Body)) -> CircleEllipseCollider {
+        return CircleEllipseCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let circle_shape = self.pair().0.shape;
+        let ellipse_shape = self.pair().1.shape;
+
+        match (circle_shape, ellipse_shape) {
+            (CircleShape{center, radius}, EllipseShape{center: ellipse_center, radius_x, radius_y}) => {
+                let global_circle_center = self.pair().0.transform().apply(center);
+                let ellipse_transform = self.pair().1.transform();
+                // The circle's center relative to the ellipse, along the ellipse's axes
+                let local_center = ellipse_transform.apply_inverse(global_circle_center) - ellipse_center;
+
+                // Whether the center is inside is read off the unit circle the ellipse squashes into
+                let inside = unit_circle_point(radius_x, radius_y, local_center).length_squared() < 1.0;
+                let closest_point = closest_point_on_ellipse(radius_x, radius_y, local_center);
+                let distance = (local_center - closest_point).length();
+                let penetration = if inside { radius + distance } else { radius - distance };
+                if penetration <= 0.0 {
+                    return ColliderResult::new_empty_false();
+                }
+
+                let normal = ellipse_transform.rotation.rotate(ellipse_normal(radius_x, radius_y, closest_point));
+                let manifold = Manifold::circle(global_circle_center, radius, normal, penetration);
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test circle to ellipse collision without circle and ellipse!!!");
+            }
+        }
+    }
+}
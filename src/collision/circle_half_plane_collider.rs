@@ -0,0 +1,42 @@
+use super::super::shape::shape::Shape::{CircleShape, HalfPlaneShape};
+use super::super::body::Body;
+use super::super::manifold::Manifold;
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+
+pub struct CircleHalfPlaneCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for CircleHalfPlaneCollider {
+    fn new(pair: (Body, Body)) -> CircleHalfPlaneCollider {
+        return CircleHalfPlaneCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let circle_shape = self.pair().0.shape;
+        let half_plane_shape = self.pair().1.shape;
+
+        match (circle_shape, half_plane_shape) {
+            (CircleShape{center, radius}, HalfPlaneShape{normal, offset}) => {
+                let global_circle_center = self.pair().0.position + center;
+                let plane_offset = offset + normal.dot(self.pair().1.position);
+
+                let separation = normal.dot(global_circle_center) - plane_offset - radius;
+                if separation >= 0.0 {
+                    return ColliderResult::new_empty_false();
+                }
+
+                let manifold = Manifold::circle(global_circle_center, radius, normal, -separation);
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test circle to half plane collision without circle and half plane!!!");
+            }
+        }
+    }
+}
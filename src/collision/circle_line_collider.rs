@@ -1,6 +1,6 @@
 use super::super::shape::shape::Shape::{CircleShape, LineShape};
+use super::super::shape::shape::{edge_normal, closest_point_on_segment};
 use super::super::body::Body;
-use super::super::math::Vec2;
 use super::super::manifold::Manifold;
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
@@ -28,29 +28,20 @@ impl Collider for CircleLineCollider {
                 let global_point1 = self.pair().1.position + point1;
                 let global_point2 = self.pair().1.position + point2;
 
-                let segment_vector = global_point1 - global_point2;
-                let mut point_vector = global_circle_center - global_point2;
-
-                let scalar_projection = point_vector.dot(segment_vector.normal());
-
-                let mut closest_point = global_point2 + (segment_vector.normal().multiply(scalar_projection));
-
-                if scalar_projection < 0.0 {
-                    closest_point = global_point2;
-                } else if scalar_projection > segment_vector.length() {
-                    closest_point = global_point1;
+                // Lines are solid from both sides, so the circle is pushed out whichever side
+                // its center is on
+                let closest_point = closest_point_on_segment(global_point1, global_point2, global_circle_center);
+                let mut normal = global_circle_center - closest_point;
+                let distance = normal.normalize();
+                if distance >= radius {
+                    return ColliderResult::new_empty_false();
                 }
-
-                let distance_vector = global_circle_center - closest_point;
-
-                if distance_vector.length() < radius {
-                    let offset = distance_vector.normal().multiply((radius - distance_vector.length()));
-                    let mut manifold = Manifold{body_a: self.pair().0, body_b: self.pair().1, normal: Vec2::new(0.0, 0.0), penetration: 0.0};
-                    manifold.normal = offset.normal();
-                    manifold.penetration = offset.length();
-                    return ColliderResult::new(Some(manifold), true);
+                if distance == 0.0 {
+                    normal = edge_normal(point1, point2);
                 }
-                return ColliderResult::new_empty_false();
+
+                let manifold = Manifold::circle(global_circle_center, radius, normal, radius - distance);
+                return ColliderResult::new(Some(manifold), true);
             },
             _ => {
                 panic!("Something happened. Cannot test circle to line collision without circle and line!!!");
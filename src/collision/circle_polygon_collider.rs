@@ -1,6 +1,6 @@
 use super::super::shape::shape::Shape::{CircleShape, PolygonShape};
 use super::super::body::Body;
-use super::super::math::Vec2;
+use super::super::math::{Vec2, Scalar};
 use super::super::manifold::Manifold;
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
@@ -33,9 +33,9 @@ impl Collider for CirclePolygonCollider {
                     let global_p2: Vec2 = points[index + 1] + self.pair().1.position;
 
                     let segment_vector = global_p1 - global_p2;
-                    let mut point_vector = global_circle_center - global_p2;
+                    let point_vector = global_circle_center - global_p2;
 
-                    let scalar_projection: f32 = point_vector.dot(segment_vector.normal());
+                    let scalar_projection: Scalar = point_vector.dot(segment_vector.normal());
 
                     let mut closest_point = Vec2::new(0.0, 0.0);
 
@@ -61,9 +61,9 @@ impl Collider for CirclePolygonCollider {
                 let global_p1: Vec2 = points[points.len()-1] + self.pair().1.position;
                 let global_p2: Vec2 = points[0] + self.pair().1.position;
                 let segment_vector = global_p1 - global_p2;
-                let mut point_vector = global_circle_center - global_p2;
+                let point_vector = global_circle_center - global_p2;
 
-                let scalar_projection: f32 = point_vector.dot(segment_vector.normal());
+                let scalar_projection: Scalar = point_vector.dot(segment_vector.normal());
 
                 let mut closest_point = Vec2::new(0.0, 0.0);
 
@@ -81,9 +81,17 @@ impl Collider for CirclePolygonCollider {
                     closest_line_distance = distance_vector;
                 }
 
+                // With the center inside the polygon the nearest edge has to push outwards
+                if polygon_shape_contains(&points, self.pair().1.position, global_circle_center) {
+                    let penetration = radius + closest_line_distance.length();
+                    let normal = if closest_line_distance.length() > 0.0 { -closest_line_distance.normal() } else { Vec2::new(1.0, 0.0) };
+                    let manifold = Manifold::circle(global_circle_center, radius, normal, penetration);
+                    return ColliderResult::new(Some(manifold), true);
+                }
+
                 if closest_line_distance.length() < radius {
                     let offset = closest_line_distance.normal().multiply(radius - closest_line_distance.length());
-                    let manifold = Manifold{body_a: self.pair().0, body_b: self.pair().1, normal: offset.normal(), penetration: offset.length()};
+                    let manifold = Manifold::circle(global_circle_center, radius, offset.normal(), offset.length());
                     return ColliderResult::new(Some(manifold), true);
                 }
 
@@ -95,3 +103,7 @@ impl Collider for CirclePolygonCollider {
         }
     }
 }
+
+fn polygon_shape_contains(points: &[Vec2], position: Vec2, point: Vec2) -> bool {
+    return PolygonShape{points: points.to_vec()}.contains_point(position, point);
+}
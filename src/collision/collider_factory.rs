@@ -1,5 +1,6 @@
-use super::super::shape::shape::Shape::{CircleShape, LineShape, ChainLineShape, PolygonShape};
+use super::super::shape::shape::Shape::{CircleShape, LineShape, ChainLineShape, PolygonShape, EdgeShape, CapsuleShape, EllipseShape, HalfPlaneShape};
 use super::super::body::Body;
+use super::super::math::Vec2;
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
 use super::circle_circle_collider::CircleCircleCollider;
@@ -7,11 +8,32 @@ use super::circle_line_collider::CircleLineCollider;
 use super::circle_polygon_collider::CirclePolygonCollider;
 use super::circle_chain_line_collider::CircleChainLineCollider;
 use super::polygon_chain_line_collider::PolygonChainLineCollider;
+use super::polygon_line_collider::PolygonLineCollider;
 use super::polygon_polygon_collider::PolygonPolygonCollider;
+use super::circle_edge_collider::CircleEdgeCollider;
+use super::polygon_edge_collider::PolygonEdgeCollider;
+use super::circle_capsule_collider::CircleCapsuleCollider;
+use super::capsule_polygon_collider::CapsulePolygonCollider;
+use super::circle_ellipse_collider::CircleEllipseCollider;
+use super::circle_half_plane_collider::CircleHalfPlaneCollider;
+use super::polygon_half_plane_collider::PolygonHalfPlaneCollider;
 
+// Collides the pair, with the manifold normal pointing from the second body to the first.
 pub fn collider_factory(body_pair: (Body, Body)) -> ColliderResult {
-    let a_body = body_pair.0;
-    let b_body = body_pair.1;
+    // An ellipse only keeps its shape in its own body's space, so circles meet it there.
+    // Everything else collides with its support polygon.
+    match (&body_pair.0.shape, &body_pair.1.shape) {
+        (&CircleShape{..}, &EllipseShape{..}) => {
+            return CircleEllipseCollider::new(body_pair).colliding();
+        },
+        (&EllipseShape{..}, &CircleShape{..}) => {
+            return flipped(CircleEllipseCollider::new((body_pair.1, body_pair.0)).colliding());
+        },
+        _ => {}
+    }
+
+    let a_body = in_world_space(body_pair.0);
+    let b_body = in_world_space(body_pair.1);
     let ref a_shape = a_body.shape;
     let ref b_shape = b_body.shape;
 
@@ -23,31 +45,87 @@ pub fn collider_factory(body_pair: (Body, Body)) -> ColliderResult {
             CircleLineCollider::new((a_body.clone(), b_body.clone())).colliding()
         },
         (&LineShape{..}, &CircleShape{..}) => {
-            CircleLineCollider::new((b_body.clone(), a_body.clone())).colliding()
+            flipped(CircleLineCollider::new((b_body.clone(), a_body.clone())).colliding())
         },
         (&CircleShape{..}, &ChainLineShape{..}) => {
             CircleChainLineCollider::new((a_body.clone(), b_body.clone())).colliding()
         },
         (&ChainLineShape{..}, &CircleShape{..}) => {
-            CircleChainLineCollider::new((b_body.clone(), a_body.clone())).colliding()
+            flipped(CircleChainLineCollider::new((b_body.clone(), a_body.clone())).colliding())
         },
         (&CircleShape{..}, &PolygonShape{..}) => {
             CirclePolygonCollider::new((a_body.clone(), b_body.clone())).colliding()
         },
         (&PolygonShape{..}, &CircleShape{..}) => {
-            CirclePolygonCollider::new((b_body.clone(), a_body.clone())).colliding()
+            flipped(CirclePolygonCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&PolygonShape{..}, &LineShape{..}) => {
+            PolygonLineCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&LineShape{..}, &PolygonShape{..}) => {
+            flipped(PolygonLineCollider::new((b_body.clone(), a_body.clone())).colliding())
         },
         (&PolygonShape{..}, &ChainLineShape{..}) => {
             PolygonChainLineCollider::new((a_body.clone(), b_body.clone())).colliding()
         },
         (&ChainLineShape{..}, &PolygonShape{..}) => {
-            PolygonChainLineCollider::new((b_body.clone(), a_body.clone())).colliding()
+            flipped(PolygonChainLineCollider::new((b_body.clone(), a_body.clone())).colliding())
         },
         (&PolygonShape{..}, &PolygonShape{..}) => {
             PolygonPolygonCollider::new((a_body.clone(), b_body.clone())).colliding()
         },
+        (&CircleShape{..}, &EdgeShape{..}) => {
+            CircleEdgeCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&EdgeShape{..}, &CircleShape{..}) => {
+            flipped(CircleEdgeCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&PolygonShape{..}, &EdgeShape{..}) => {
+            PolygonEdgeCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&EdgeShape{..}, &PolygonShape{..}) => {
+            flipped(PolygonEdgeCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&CircleShape{..}, &CapsuleShape{..}) => {
+            CircleCapsuleCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&CapsuleShape{..}, &CircleShape{..}) => {
+            flipped(CircleCapsuleCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&CapsuleShape{..}, &PolygonShape{..}) => {
+            CapsulePolygonCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&PolygonShape{..}, &CapsuleShape{..}) => {
+            flipped(CapsulePolygonCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&CircleShape{..}, &HalfPlaneShape{..}) => {
+            CircleHalfPlaneCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&HalfPlaneShape{..}, &CircleShape{..}) => {
+            flipped(CircleHalfPlaneCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
+        (&PolygonShape{..}, &HalfPlaneShape{..}) | (&CapsuleShape{..}, &HalfPlaneShape{..}) => {
+            PolygonHalfPlaneCollider::new((a_body.clone(), b_body.clone())).colliding()
+        },
+        (&HalfPlaneShape{..}, &PolygonShape{..}) | (&HalfPlaneShape{..}, &CapsuleShape{..}) => {
+            flipped(PolygonHalfPlaneCollider::new((b_body.clone(), a_body.clone())).colliding())
+        },
         _ => {
             ColliderResult::new_empty_false()
         }
     }
 }
+
+// The colliders work with the shape already moved and rotated into place, so they never
+// have to deal with the body's angle.
+fn in_world_space(mut body: Body) -> Body {
+    body.shape = body.shape.transformed(body.transform());
+    body.position = Vec2::new_zero();
+    body.angle = 0.0;
+    return body;
+}
+
+// The colliders put their first shape first, so results for swapped pairs are turned around.
+fn flipped(result: ColliderResult) -> ColliderResult {
+    return ColliderResult::new(result.manifold.map(|manifold| manifold.flipped()), result.is_colliding);
+}
@@ -8,3 +8,11 @@ pub mod circle_polygon_collider;
 pub mod polygon_chain_line_collider;
 pub mod polygon_line_collider;
 pub mod polygon_polygon_collider;
+pub mod circle_edge_collider;
+pub mod polygon_edge_collider;
+pub mod circle_capsule_collider;
+pub mod capsule_polygon_collider;
+pub mod circle_ellipse_collider;
+pub mod circle_half_plane_collider;
+pub mod polygon_half_plane_collider;
+pub mod sat;
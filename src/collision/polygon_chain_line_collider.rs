@@ -1,7 +1,16 @@
 use super::super::shape::shape::Shape::{PolygonShape, ChainLineShape};
+use super::super::shape::shape::edge_normal;
 use super::super::body::Body;
+use super::super::math::{Vec2, Transform, Scalar};
+use super::super::manifold::{Manifold, ContactPoint, FeatureType};
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
+use super::polygon_polygon_collider::{collide_polygons, collide_with_face};
+
+// How far past a neighbouring segment's normal a contact normal may lean and still be kept
+const SIN_TOLERANCE: Scalar = 0.1;
+// Contact points from neighbouring segments closer than this are the same point
+const WELD_DISTANCE: Scalar = 0.005;
 
 pub struct PolygonChainLineCollider {
     pair: (Body, Body)
@@ -21,8 +30,26 @@ impl Collider for PolygonChainLineCollider {
         let chain_line_shape = self.pair().1.shape;
 
         match (polygon_shape, chain_line_shape) {
-            (PolygonShape{..}, ChainLineShape{..}) => {
-                return ColliderResult::new_empty_false();
+            (PolygonShape{points: polygon_points}, ChainLineShape{points}) => {
+                if polygon_points.len() < 2 {
+                    return ColliderResult::new_empty_false();
+                }
+                let polygon_transform = self.pair().0.transform();
+                let polygon: Vec<Vec2> = polygon_points.iter().map(|p| polygon_transform.apply(*p)).collect();
+                let center = polygon.iter().fold(Vec2::new_zero(), |sum, p| sum + *p).divide(polygon.len() as Scalar);
+                let chain_position = self.pair().1.position;
+                let chain: Vec<Vec2> = points.iter().map(|p| *p + chain_position).collect();
+
+                let mut manifolds: Vec<Manifold> = Vec::new();
+                for i in 0..chain.len().saturating_sub(1) {
+                    if let Some(manifold) = collide_chain_segment(&polygon, center, &chain, i) {
+                        manifolds.push(manifold);
+                    }
+                }
+
+                let manifold = merge_manifolds(manifolds);
+                let is_colliding = manifold.is_some();
+                return ColliderResult::new(manifold, is_colliding);
             },
             _ => {
                 panic!("Something happened. Cannot test polygon to chain line collision without polygon and chain line!!!");
@@ -30,3 +57,126 @@ impl Collider for PolygonChainLineCollider {
         }
     }
 }
+
+// Collides the polygon with segment i of the chain, using the segments on either side as
+// ghost vertices the way Box2D does. A contact normal that would push the polygon into a
+// neighbouring segment is left to that segment, and where the chain bends up towards the
+// polygon the segment's own normal is used. This keeps polygons from catching on the
+// vertices between segments.
+fn collide_chain_segment(polygon: &[Vec2], center: Vec2, chain: &[Vec2], i: usize) -> Option<Manifold> {
+    let previous = if i > 0 { Some(chain[i - 1]) } else { None };
+    let next = if i + 2 < chain.len() { Some(chain[i + 2]) } else { None };
+
+    // Walk the chain in the direction that puts the polygon on the side edge_normal points to
+    let reversed = edge_normal(chain[i], chain[i + 1]).dot(center - chain[i]) < 0.0;
+    let (vertex0, vertex1, vertex2, vertex3) = if reversed {
+        (next, chain[i + 1], chain[i], previous)
+    } else {
+        (previous, chain[i], chain[i + 1], next)
+    };
+    let normal1 = edge_normal(vertex1, vertex2);
+
+    let manifold = match collide_polygons(polygon, Transform::identity(), &[vertex1, vertex2], Transform::identity()) {
+        Some(manifold) => in_chain(manifold, i, reversed),
+        None => return None
+    };
+
+    let mut edge1 = vertex2 - vertex1;
+    edge1.normalize();
+    let (convex, admitted) = if manifold.normal.dot(edge1) <= 0.0 {
+        match vertex0 {
+            Some(vertex0) => {
+                let mut edge0 = vertex1 - vertex0;
+                edge0.normalize();
+                (edge0.cross(edge1) >= 0.0, manifold.normal.cross(edge_normal(vertex0, vertex1)) <= SIN_TOLERANCE)
+            },
+            None => return Some(manifold)
+        }
+    } else {
+        match vertex3 {
+            Some(vertex3) => {
+                let mut edge2 = vertex3 - vertex2;
+                edge2.normalize();
+                (edge1.cross(edge2) >= 0.0, edge_normal(vertex2, vertex3).cross(manifold.normal) <= SIN_TOLERANCE)
+            },
+            None => return Some(manifold)
+        }
+    };
+
+    if !convex {
+        return collide_with_face(polygon, vertex1, vertex2, normal1).map(|mut manifold| {
+            for point in manifold.points.iter_mut() {
+                point.id = point.id.swapped();
+            }
+            in_chain(manifold, i, reversed)
+        });
+    }
+    if !admitted {
+        return None;
+    }
+    return Some(manifold);
+}
+
+// Renames the segment's features in the ids after the chain's vertices and edges, so
+// points from different segments don't share ids.
+fn in_chain(mut manifold: Manifold, segment: usize, reversed: bool) -> Manifold {
+    for point in manifold.points.iter_mut() {
+        let local = point.id.index_b as usize;
+        let index = match point.id.type_b {
+            FeatureType::Vertex => if reversed { segment + 1 - local.min(1) } else { segment + local },
+            FeatureType::Face => segment
+        };
+        point.id.index_b = index as u8;
+    }
+    return manifold;
+}
+
+// A pair gets a single manifold, so the deepest contact wins. Contacts from other segments
+// with the same normal, like those of a box lying across two flat segments, join it.
+fn merge_manifolds(manifolds: Vec<Manifold>) -> Option<Manifold> {
+    let deepest = match manifolds.iter().fold(None, |best: Option<&Manifold>, m| match best {
+        Some(best) if best.penetration >= m.penetration => Some(best),
+        _ => Some(m)
+    }) {
+        Some(deepest) => deepest.clone(),
+        None => return None
+    };
+
+    let mut points: Vec<ContactPoint> = Vec::new();
+    for manifold in manifolds.iter().filter(|m| m.normal.dot(deepest.normal) > 1.0 - 1.0e-4) {
+        for point in manifold.points.iter() {
+            if !points.iter().any(|p| (p.position - point.position).length_squared() < WELD_DISTANCE * WELD_DISTANCE) {
+                points.push(*point);
+            }
+        }
+    }
+    return Some(Manifold::new(deepest.normal, deepest.penetration, points));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    #[test]
+    fn box_slides_over_a_junction_without_catching() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let sliding = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-2.0, -0.5)).velocity(Vec2::new(3.0, 0.0)).friction(0.0).build());
+        world.add_body(BodyDef::builder(ChainLineShape{points: vec![Vec2::new(-4.0, 0.0), Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0)]})
+            .friction(0.0).build());
+        let mut slowest: Scalar = 1.0e9;
+        let mut fastest_rise: Scalar = 0.0;
+        for _ in 0..90 {
+            world.step(1.0 / 60.0);
+            let body = world.get_body(sliding).unwrap();
+            slowest = slowest.min(body.velocity.x);
+            fastest_rise = fastest_rise.max(-body.velocity.y);
+        }
+        assert!(world.get_body(sliding).unwrap().position.x > 2.0);
+        assert!(slowest > 2.9);
+        assert!(fastest_rise < 0.1);
+    }
+}
@@ -0,0 +1,50 @@
+use super::super::shape::shape::Shape::{PolygonShape, EdgeShape};
+use super::super::shape::shape::edge_normal;
+use super::super::body::Body;
+use super::super::math::{Vec2, Scalar};
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+use super::polygon_polygon_collider::collide_polygons;
+
+pub struct PolygonEdgeCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for PolygonEdgeCollider {
+    fn new(pair: (Body, Body)) -> PolygonEdgeCollider {
+        return PolygonEdgeCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let polygon_shape = self.pair().0.shape;
+        let edge_shape = self.pair().1.shape;
+
+        match (polygon_shape, edge_shape) {
+            (PolygonShape{points}, EdgeShape{vertex1, vertex2, one_sided}) => {
+                let manifold = match collide_polygons(&points, self.pair().0.transform(), &[vertex1, vertex2], self.pair().1.transform()) {
+                    Some(manifold) => manifold,
+                    None => return ColliderResult::new_empty_false()
+                };
+
+                if one_sided {
+                    // Only push out through the front, and only polygons that are in front
+                    let edge_normal = edge_normal(vertex1, vertex2);
+                    let polygon_position = self.pair().0.position;
+                    let center = points.iter().fold(Vec2::new_zero(), |sum, p| sum + *p + polygon_position).divide(points.len() as Scalar);
+                    if edge_normal.dot(center - (self.pair().1.position + vertex1)) < 0.0 || manifold.normal.dot(edge_normal) <= 0.0 {
+                        return ColliderResult::new_empty_false();
+                    }
+                }
+
+                return ColliderResult::new(Some(manifold), true);
+            },
+            _ => {
+                panic!("Something happened. Cannot test polygon to edge collision without polygon and edge!!!");
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::super::shape::shape::Shape::{PolygonShape, CapsuleShape, HalfPlaneShape};
+use super::super::body::Body;
+use super::super::math::{Vec2, Scalar};
+use super::super::manifold::{Manifold, ContactPoint, ContactId, FeatureType};
+use super::collider::Collider;
+use super::collider_result::ColliderResult;
+
+// Also takes capsules, which touch the surface with the circles at their ends.
+pub struct PolygonHalfPlaneCollider {
+    pair: (Body, Body)
+}
+
+impl Collider for PolygonHalfPlaneCollider {
+    fn new(pair: (Body, Body)) -> PolygonHalfPlaneCollider {
+        return PolygonHalfPlaneCollider{ pair: pair }
+    }
+
+    fn pair(&self) -> (Body, Body) {
+        return self.pair.clone();
+    }
+
+    fn colliding(&self) -> ColliderResult {
+        let shape = self.pair().0.shape;
+        let half_plane_shape = self.pair().1.shape;
+        let position = self.pair().0.position;
+
+        let (vertices, radius): (Vec<Vec2>, Scalar) = match shape {
+            PolygonShape{points} => (points.iter().map(|p| *p + position).collect(), 0.0),
+            CapsuleShape{center1, center2, radius} => (vec![center1 + position, center2 + position], radius),
+            _ => {
+                panic!("Something happened. Cannot test polygon to half plane collision without polygon or capsule!!!");
+            }
+        };
+        match half_plane_shape {
+            HalfPlaneShape{normal, offset} => {
+                let plane_offset = offset + normal.dot(self.pair().1.position);
+                let manifold = collide_half_plane(&vertices, radius, normal, plane_offset);
+                let is_colliding = manifold.is_some();
+                return ColliderResult::new(manifold, is_colliding);
+            },
+            _ => {
+                panic!("Something happened. Cannot test polygon to half plane collision without half plane!!!");
+            }
+        }
+    }
+}
+
+// The two deepest vertices below the surface, grown by the radius, become the contact points.
+fn collide_half_plane(vertices: &[Vec2], radius: Scalar, normal: Vec2, offset: Scalar) -> Option<Manifold> {
+    let mut points: Vec<ContactPoint> = Vec::new();
+    for (i, vertex) in vertices.iter().enumerate() {
+        let separation = normal.dot(*vertex) - offset - radius;
+        if separation < 0.0 {
+            let position = *vertex - normal.multiply(radius + 0.5 * separation);
+            let id = ContactId::new(i as u8, FeatureType::Vertex, 0, FeatureType::Face);
+            points.push(ContactPoint::new(position, -separation, id));
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    points.sort_by(|a, b| b.penetration.partial_cmp(&a.penetration).unwrap_or(::std::cmp::Ordering::Equal));
+    points.truncate(2);
+    let penetration = points[0].penetration;
+    return Some(Manifold::new(normal, penetration, points));
+}
@@ -2,6 +2,7 @@ use super::super::shape::shape::Shape::{PolygonShape, LineShape};
 use super::super::body::Body;
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
+use super::polygon_polygon_collider::collide_polygons;
 
 pub struct PolygonLineCollider {
     pair: (Body, Body)
@@ -21,11 +22,18 @@ impl Collider for PolygonLineCollider {
         let line_shape = self.pair().1.shape;
 
         match (polygon_shape, line_shape) {
-            (PolygonShape{..}, LineShape{..}) => {
-                return ColliderResult::new_empty_false();
+            (PolygonShape{points}, LineShape{point1, point2}) => {
+                // A line is solid from both sides, so it collides like a two point polygon
+                if points.len() < 2 {
+                    return ColliderResult::new_empty_false();
+                }
+                match collide_polygons(&points, self.pair().0.transform(), &[point1, point2], self.pair().1.transform()) {
+                    Some(manifold) => return ColliderResult::new(Some(manifold), true),
+                    None => return ColliderResult::new_empty_false()
+                }
             },
             _ => {
-                panic!("Something happened. Cannot test polygon to chain line collision without polygon and chain line!!!");
+                panic!("Something happened. Cannot test polygon to line collision without polygon and line!!!");
             }
         }
     }
@@ -1,10 +1,13 @@
 use super::super::shape::shape::Shape::PolygonShape;
 use super::super::body::Body;
-use super::super::math::Vec2;
-use super::super::manifold::Manifold;
+use super::super::math::{Vec2, Transform, Scalar};
+use super::super::manifold::{Manifold, ContactPoint, ContactId, FeatureType};
 use super::collider::Collider;
 use super::collider_result::ColliderResult;
 
+// How much better the second polygon's axis must be before its face is used as the reference
+const REFERENCE_TOLERANCE: Scalar = 0.0005;
+
 pub struct PolygonPolygonCollider {
     pair: (Body, Body)
 }
@@ -24,86 +27,9 @@ impl Collider for PolygonPolygonCollider {
 
         match (polygon_a_shape, polygon_b_shape) {
             (PolygonShape{points: points_a}, PolygonShape{points: points_b}) => {
-
-                let mut axes_one: Vec<Vec2> = Vec::new();
-                let mut axes_two: Vec<Vec2> = Vec::new();
-
-                for (p1, p2) in get_lines(points_a.clone()) {
-                    let edge: Vec2 = p2 - p1;
-                    let normal: Vec2 = Vec2::new((-1.0 * edge.y), edge.x);
-                    axes_one.push(normal.normal());
-                }
-
-                for (p1, p2) in get_lines(points_b.clone()) {
-                    let edge: Vec2 = p2 - p1;
-                    let normal: Vec2 = Vec2::new((-1.0 * edge.y), edge.x);
-                    axes_two.push(normal.normal());
-                }
-
-                let mut best_overlap: f32 = 9999999.0;
-        		let mut mtv: Vec2 = Vec2::new(0.0,0.0);
-
-                for axis in axes_one {
-        			let min1: f32 = get_min(&points_a, axis, self.pair().0.position);
-        			let max1: f32 = get_max(&points_a, axis, self.pair().0.position);
-        			let min2: f32 = get_min(&points_b, axis, self.pair().1.position);
-        			let max2: f32 = get_max(&points_b, axis, self.pair().1.position);
-        			if min2 > min1 && min2 < max1 {
-        				let mut overlap: f32 = min2 - min1;
-        				if max1 - min1 < overlap {
-        					overlap = max1 - min2;
-        				}
-        				if overlap < best_overlap {
-        					best_overlap = overlap;
-        					mtv = axis;
-        				}
-        			} else if max2 > min1 && max2 < max1 {
-        				let mut overlap: f32 = max2 - min1;
-        				if max1 - min2 < overlap {
-        					overlap = max1 - min2;
-        				}
-        				if overlap < best_overlap  {
-        					best_overlap = overlap;
-        					mtv = axis;
-        				}
-        			} else {
-        				return ColliderResult::new_empty_false();
-        			}
-        		}
-
-                for axis in axes_two {
-        			let min1: f32 = get_min(&points_a, axis, self.pair().0.position);
-        			let max1: f32 = get_max(&points_a, axis, self.pair().0.position);
-        			let min2: f32 = get_min(&points_b, axis, self.pair().1.position);
-        			let max2: f32 = get_max(&points_b, axis, self.pair().1.position);
-
-        			if min2 > min1 && min2 < max1 {
-        			    let mut overlap: f32 = min2 - min1;
-        				if max1 - min1 < overlap {
-        					overlap = max1 - min2;
-        				}
-        				if overlap < best_overlap {
-        					best_overlap = overlap;
-        					mtv = axis;
-        				}
-        			} else if max2 > min1 && max2 < max1 {
-        				let mut overlap: f32 = max2 - min1;
-        				if max1 - min2 < overlap {
-        					overlap = max1 - min2;
-        				}
-        				if overlap < best_overlap {
-        					best_overlap = overlap;
-        					mtv = axis;
-        				}
-        			}
-        			else {
-        				return ColliderResult::new_empty_false();
-        			}
-        		}
-
-                let manifold = Manifold{body_a: self.pair().0, body_b: self.pair().1, normal: mtv, penetration:best_overlap};
-                return ColliderResult::new(Some(manifold), true);
-
+                let manifold = collide_polygons(&points_a, self.pair().0.transform(), &points_b, self.pair().1.transform());
+                let is_colliding = manifold.is_some();
+                return ColliderResult::new(manifold, is_colliding);
             },
             _ => {
                 panic!("Something happened. Cannot test polygon to polygon collision without two polygons!!!");
@@ -112,39 +38,203 @@ impl Collider for PolygonPolygonCollider {
     }
 }
 
-fn get_min(points: &Vec<Vec2>, axis: Vec2, position: Vec2) -> f32 {
-    let mut min: f32 = (points[0] + position).dot(axis);
-    for point in points.iter() {
-        let mut new_point = point.clone() + position;
-        if new_point.dot(axis) < min {
-            min = new_point.dot(axis);
+// Collides two convex polygons given in their local space, as Box2D does. The axis of
+// least penetration picks a reference face on one polygon, and the most opposed face of the
+// other polygon is clipped against its sides to give up to two contact points. A polygon
+// of two points works as a two sided segment. The normal points from b to a.
+pub fn collide_polygons(points_a: &[Vec2], xf_a: Transform, points_b: &[Vec2], xf_b: Transform) -> Option<Manifold> {
+    if points_a.len() < 2 || points_b.len() < 2 {
+        return None;
+    }
+    let world_a: Vec<Vec2> = points_a.iter().map(|p| xf_a.apply(*p)).collect();
+    let world_b: Vec<Vec2> = points_b.iter().map(|p| xf_b.apply(*p)).collect();
+    let normals_a = outward_normals(&world_a);
+    let normals_b = outward_normals(&world_b);
+
+    let (edge_a, separation_a) = max_separation(&world_a, &normals_a, &world_b);
+    if separation_a > 0.0 {
+        return None;
+    }
+    let (edge_b, separation_b) = max_separation(&world_b, &normals_b, &world_a);
+    if separation_b > 0.0 {
+        return None;
+    }
+
+    // The reference polygon owns the face the other one is pushed out of
+    let (reference, reference_normals, reference_edge, incident, incident_normals, reference_is_b) =
+        if separation_b > separation_a + REFERENCE_TOLERANCE {
+            (&world_b, &normals_b, edge_b, &world_a, &normals_a, true)
+        } else {
+            (&world_a, &normals_a, edge_a, &world_b, &normals_b, false)
+        };
+
+    let normal = reference_normals[reference_edge];
+    let v11 = reference[reference_edge];
+    let v12 = reference[(reference_edge + 1) % reference.len()];
+    let mut manifold = face_manifold(incident, incident_normals, reference_edge, v11, v12, normal)?;
+    // face_manifold names the reference polygon's feature first in each id. Swap them when
+    // the reference is b, so polygon a's feature always comes first, and turn the normal
+    // around when it is a, so it always points from b to a.
+    if reference_is_b {
+        for point in manifold.points.iter_mut() {
+            point.id = point.id.swapped();
         }
+    } else {
+        manifold.normal = -manifold.normal;
+    }
+    return Some(manifold);
+}
+
+// Pushes the incident polygon out through the reference face from v1 to v2 with the given
+// outward normal. The face of the incident polygon most opposed to the normal is clipped to
+// the sides of the reference face, and what is left behind it gives the contact points. The
+// normal of the manifold is the reference normal, and the ids name the segment's features
+// first, as edge 0 from vertex 0 to 1.
+pub fn collide_with_face(incident: &[Vec2], v1: Vec2, v2: Vec2, normal: Vec2) -> Option<Manifold> {
+    if incident.len() < 2 {
+        return None;
+    }
+    return face_manifold(incident, &outward_normals(incident), 0, v1, v2, normal);
+}
+
+fn face_manifold(incident: &[Vec2], incident_normals: &[Vec2], reference_edge: usize, v1: Vec2, v2: Vec2, normal: Vec2) -> Option<Manifold> {
+    let incident_edge = (0..incident.len())
+        .fold(0, |best, i| if incident_normals[i].dot(normal) < incident_normals[best].dot(normal) { i } else { best });
+    let incident_next = (incident_edge + 1) % incident.len();
+    let incident_points = [
+        (incident[incident_edge], ContactId::new(reference_edge as u8, FeatureType::Face, incident_edge as u8, FeatureType::Vertex)),
+        (incident[incident_next], ContactId::new(reference_edge as u8, FeatureType::Face, incident_next as u8, FeatureType::Vertex))
+    ];
+
+    let mut tangent = v2 - v1;
+    tangent.normalize();
+
+    // Keep the part of the incident face that lies beside the reference face. The
+    // reference face runs from vertex reference_edge to the next one.
+    let clipped = clip_segment(&incident_points, -tangent, -tangent.dot(v1), reference_edge as u8);
+    if clipped.len() < 2 {
+        return None;
+    }
+    let clipped = clip_segment(&clipped, tangent, tangent.dot(v2), (reference_edge as u8).wrapping_add(1));
+    if clipped.len() < 2 {
+        return None;
+    }
+
+    let front_offset = normal.dot(v1);
+    let mut points: Vec<ContactPoint> = Vec::new();
+    for &(point, id) in clipped.iter() {
+        let separation = normal.dot(point) - front_offset;
+        if separation <= 0.0 {
+            points.push(ContactPoint::new(point - normal.multiply(0.5 * separation), -separation, id));
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    let penetration = points.iter().fold(0.0, |deepest: Scalar, p| deepest.max(p.penetration));
+    return Some(Manifold::new(normal, penetration, points));
+}
+
+// The unit outward normal of each edge, whatever the winding. Edge i runs from point i to
+// point i + 1.
+fn outward_normals(points: &[Vec2]) -> Vec<Vec2> {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        area += points[i].cross(points[(i + 1) % points.len()]);
     }
-    return min;
+    let winding = if area < 0.0 { -1.0 } else { 1.0 };
+    return (0..points.len()).map(|i| {
+        let edge = points[(i + 1) % points.len()] - points[i];
+        let mut normal = Vec2::new(edge.y, -edge.x).multiply(winding);
+        normal.normalize();
+        normal
+    }).collect();
 }
 
-fn get_max(points: &Vec<Vec2>, axis: Vec2, position: Vec2) -> f32 {
-    let mut max: f32 = (points[0] + position).dot(axis);
-    for point in points.iter() {
-        let mut new_point = point.clone() + position;
-        if new_point.dot(axis) > max {
-            max = new_point.dot(axis);
+// The edge of the first polygon the second polygon is furthest outside of, and how far.
+fn max_separation(points_1: &[Vec2], normals_1: &[Vec2], points_2: &[Vec2]) -> (usize, Scalar) {
+    let mut best_edge = 0;
+    let mut best_separation = Scalar::MIN;
+    for i in 0..points_1.len() {
+        let separation = points_2.iter()
+            .map(|p| normals_1[i].dot(*p - points_1[i]))
+            .fold(Scalar::MAX, |min, s| min.min(s));
+        if separation > best_separation {
+            best_edge = i;
+            best_separation = separation;
         }
     }
-    return max;
+    return (best_edge, best_separation);
+}
+
+// The part of the segment where normal . p <= offset. A point made by the cut is named
+// after the reference vertex at the side plane and the incident face.
+fn clip_segment(points: &[(Vec2, ContactId)], normal: Vec2, offset: Scalar, vertex_index: u8) -> Vec<(Vec2, ContactId)> {
+    let distance0 = normal.dot(points[0].0) - offset;
+    let distance1 = normal.dot(points[1].0) - offset;
+    let mut clipped: Vec<(Vec2, ContactId)> = Vec::new();
+    if distance0 <= 0.0 {
+        clipped.push(points[0]);
+    }
+    if distance1 <= 0.0 {
+        clipped.push(points[1]);
+    }
+    if distance0 * distance1 < 0.0 {
+        let interp = distance0 / (distance0 - distance1);
+        let id = ContactId::new(vertex_index, FeatureType::Vertex, points[0].1.index_b, FeatureType::Face);
+        clipped.push((points[0].0 + (points[1].0 - points[0].0).multiply(interp), id));
+    }
+    return clipped;
 }
 
-fn get_lines(points: Vec<Vec2>) -> Vec<(Vec2, Vec2)> {
-    let mut lines: Vec<(Vec2, Vec2)> = Vec::new();
-    for index in 0..(points.len() - 1) {
-	    let p1: Vec2 = points[index];
-	    let p2: Vec2 = points[index + 1];
-	    lines.push( (p1, p2) );
-	    if index == points.len() - 2 {
-		    let point1: Vec2 = points[index + 1];
-		    let point2: Vec2 = points[0];
-		    lines.push( (point1,point2) );
-	    }
-     }
-     return lines;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::math::consts;
+
+    fn square() -> Vec<Vec2> {
+        return vec![Vec2::new(-0.5, -0.5), Vec2::new(0.5, -0.5), Vec2::new(0.5, 0.5), Vec2::new(-0.5, 0.5)];
+    }
+
+    fn collide(xf_a: Transform, xf_b: Transform) -> Option<Manifold> {
+        return collide_polygons(&square(), xf_a, &square(), xf_b);
+    }
+
+    #[test]
+    fn overlapping_faces_give_two_points() {
+        // b overlaps the bottom of a by 0.1
+        let manifold = collide(Transform::new(Vec2::new_zero(), 0.0), Transform::new(Vec2::new(0.0, 0.9), 0.0)).unwrap();
+        assert!(manifold.normal.approx_eq(Vec2::new(0.0, -1.0), 1.0e-5));
+        assert!((manifold.penetration - 0.1).abs() < 1.0e-4);
+        assert_eq!(manifold.points.len(), 2);
+        for point in manifold.points.iter() {
+            assert!((point.penetration - 0.1).abs() < 1.0e-4);
+            assert_eq!(point.id.type_a, FeatureType::Face);
+            assert_eq!(point.id.index_a, 2);
+            assert_eq!(point.id.type_b, FeatureType::Vertex);
+        }
+    }
+
+    #[test]
+    fn corner_on_a_face_gives_one_point_naming_a_first() {
+        // The corner of a, turned 45 degrees, dips 0.1 into the top face of b
+        let half_diagonal = (0.5 as Scalar).sqrt();
+        let xf_b = Transform::new(Vec2::new(0.0, half_diagonal + 0.4), 0.0);
+        let manifold = collide(Transform::new(Vec2::new_zero(), consts::PI / 4.0), xf_b).unwrap();
+        assert!(manifold.normal.approx_eq(Vec2::new(0.0, -1.0), 1.0e-5));
+        assert!((manifold.penetration - 0.1).abs() < 1.0e-4);
+        assert_eq!(manifold.points.len(), 1);
+        let id = manifold.points[0].id;
+        assert_eq!(id.type_a, FeatureType::Vertex);
+        assert_eq!(id.type_b, FeatureType::Face);
+        assert_eq!(id.index_b, 0);
+    }
+
+    #[test]
+    fn separated_squares_do_not_collide() {
+        assert!(collide(Transform::new(Vec2::new_zero(), 0.0), Transform::new(Vec2::new(0.0, 1.1), 0.0)).is_none());
+        assert!(collide(Transform::new(Vec2::new_zero(), 0.0), Transform::new(Vec2::new(1.05, 0.0), 0.3)).is_some());
+        assert!(collide(Transform::new(Vec2::new_zero(), 0.0), Transform::new(Vec2::new(1.5, 0.0), 0.3)).is_none());
+    }
 }
@@ -0,0 +1,52 @@
+use super::super::math::{Vec2, Scalar};
+
+// The outward facing normals of every edge of a convex polygon, whatever its winding.
+pub fn polygon_axes(points: &[Vec2]) -> Vec<Vec2> {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        area += points[i].cross(points[(i + 1) % points.len()]);
+    }
+    let winding = if area < 0.0 { -1.0 } else { 1.0 };
+
+    let mut axes: Vec<Vec2> = Vec::new();
+    for i in 0..points.len() {
+        let edge = points[(i + 1) % points.len()] - points[i];
+        let mut axis = Vec2::new(edge.y, -edge.x).multiply(winding);
+        if axis.normalize() > 0.0 {
+            axes.push(axis);
+        }
+    }
+    return axes;
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (Scalar, Scalar) {
+    let mut min = points[0].dot(axis);
+    let mut max = min;
+    for point in points.iter() {
+        let projection = point.dot(axis);
+        min = min.min(projection);
+        max = max.max(projection);
+    }
+    return (min, max);
+}
+
+// Projects both convex point sets onto every axis and returns the axis needing the least
+// push to separate them along with that distance, or None if some axis separates them.
+// Works when one projection contains the other and for segments, which project to a
+// single point on their own normal.
+pub fn least_overlap(points_a: &[Vec2], points_b: &[Vec2], axes: &[Vec2]) -> Option<(Vec2, Scalar)> {
+    let mut best: Option<(Vec2, Scalar)> = None;
+    for &axis in axes.iter() {
+        let (min_a, max_a) = project(points_a, axis);
+        let (min_b, max_b) = project(points_b, axis);
+        let overlap = (max_a - min_b).min(max_b - min_a);
+        if overlap <= 0.0 {
+            return None;
+        }
+        match best {
+            Some((_, best_overlap)) if best_overlap <= overlap => {},
+            _ => best = Some((axis, overlap))
+        }
+    }
+    return best;
+}
@@ -1,5 +1,5 @@
-use super::manifold::Manifold;
+use super::manifold::Contact;
 
 pub trait CollisionResolution {
-    fn resolve_collisions(&mut self, manifold: &Vec<Manifold>);
+    fn resolve_collisions(&mut self, contacts: &Vec<Contact>);
 }
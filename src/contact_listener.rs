@@ -0,0 +1,31 @@
+use super::math::Scalar;
+use super::manifold::Manifold;
+
+// What the solver pushed a contact with over the last step, one entry per manifold
+// point in the same order. Dividing by the time step gives the force, and the normal
+// impulse of a hard landing is what to base damage on.
+#[derive(Clone, Debug)]
+pub struct ContactImpulse {
+    pub normal_impulses: Vec<Scalar>,
+    pub tangent_impulses: Vec<Scalar>
+}
+
+// Receives notifications from World::step as pairs of bodies start and stop touching.
+// Bodies are identified by their index in World::bodies, lower index first.
+pub trait ContactListener {
+    fn begin_contact(&mut self, _a: usize, _b: usize) {}
+    fn end_contact(&mut self, _a: usize, _b: usize) {}
+
+    // Called on every step for each touching pair of shapes before the solver runs, with a
+    // and b in the manifold's order, so the normal points from b to a. Returning false
+    // leaves the contact out of this step's solve and the bodies pass through each other,
+    // while still counting as touching. Deciding once when a pair begins touching and
+    // keeping to it until it ends makes a one way platform.
+    fn pre_solve(&mut self, _a: usize, _b: usize, _manifold: &Manifold) -> bool {
+        return true;
+    }
+
+    // Called after the solver for each contact it solved, with a and b as in pre_solve.
+    // Sensors, contacts between sleeping bodies and those pre_solve turned down are left out.
+    fn post_solve(&mut self, _a: usize, _b: usize, _impulse: &ContactImpulse) {}
+}
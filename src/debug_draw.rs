@@ -0,0 +1,132 @@
+use super::math::{Vec2, Transform, Scalar, consts};
+use super::body::{Body, BodyType};
+use super::shape::shape::Shape;
+use super::shape::polygon::tessellate_circle;
+
+// Straight pieces each circle and capsule end is drawn with
+const CIRCLE_SEGMENTS: usize = 24;
+// How far a half plane's surface is drawn either side of its nearest point to the origin
+const HALF_PLANE_DRAW_EXTENT: Scalar = 1000.0;
+
+// What a renderer needs to draw a body, without looking inside its shape.
+#[derive(Clone, Debug)]
+pub struct DebugShape {
+    // The body's index in World::bodies
+    pub body: usize,
+    // The outline in world coordinates
+    pub polyline: Vec<Vec2>,
+    // Whether the last point joins back up with the first
+    pub closed: bool,
+    pub awake: bool,
+    pub body_type: BodyType,
+    // Where the shape touched others in the last step, in world coordinates
+    pub contact_points: Vec<Vec2>
+}
+
+impl DebugShape {
+    pub fn new(body: &Body, contact_points: Vec<Vec2>) -> DebugShape {
+        return DebugShape::with_shape(body, &body.shape, contact_points);
+    }
+
+    // One of the body's fixture shapes instead of its own shape.
+    pub fn with_shape(body: &Body, shape: &Shape, contact_points: Vec<Vec2>) -> DebugShape {
+        let (polyline, closed) = outline(shape, body.transform());
+        return DebugShape{
+            body: body.id,
+            polyline: polyline,
+            closed: closed,
+            awake: body.is_awake(),
+            body_type: body.body_type,
+            contact_points: contact_points
+        };
+    }
+}
+
+// The outline of the shape in world coordinates with its body at the transform, and
+// whether it is closed.
+fn outline(shape: &Shape, transform: Transform) -> (Vec<Vec2>, bool) {
+    let map_points = |points: &Vec<Vec2>| points.iter().map(|p| transform.apply(*p)).collect();
+    match *shape {
+        Shape::CircleShape{center, radius} => {
+            return (tessellate_circle(transform.apply(center), radius, CIRCLE_SEGMENTS), true);
+        },
+        Shape::CapsuleShape{center1, center2, radius} => {
+            let (center1, center2) = (transform.apply(center1), transform.apply(center2));
+            let axis = center2 - center1;
+            let angle = axis.y.atan2(axis.x);
+            let half_turn = consts::PI;
+            let mut points = arc(center2, radius, angle - 0.5 * half_turn, half_turn, CIRCLE_SEGMENTS / 2);
+            points.extend(arc(center1, radius, angle + 0.5 * half_turn, half_turn, CIRCLE_SEGMENTS / 2));
+            return (points, true);
+        },
+        Shape::LineShape{point1, point2} | Shape::EdgeShape{vertex1: point1, vertex2: point2, ..} => {
+            return (vec![transform.apply(point1), transform.apply(point2)], false);
+        },
+        Shape::ChainLineShape{ref points} => {
+            return (map_points(points), false);
+        },
+        Shape::PolygonShape{ref points} => {
+            return (map_points(points), true);
+        },
+        Shape::EllipseShape{center, radius_x, radius_y} => {
+            let points = (0..CIRCLE_SEGMENTS).map(|i| {
+                let angle = 2.0 * consts::PI * i as Scalar / CIRCLE_SEGMENTS as Scalar;
+                transform.apply(center + Vec2::new(radius_x * angle.cos(), radius_y * angle.sin()))
+            }).collect();
+            return (points, true);
+        },
+        Shape::HalfPlaneShape{normal, offset} => {
+            let normal = transform.rotation.rotate(normal);
+            let surface = normal.multiply(offset + normal.dot(transform.position));
+            let along = Vec2::new(-normal.y, normal.x).multiply(HALF_PLANE_DRAW_EXTENT);
+            return (vec![surface - along, surface + along], false);
+        }
+    }
+}
+
+// Points along a circle from the start angle, turning `sweep` counter clockwise and
+// ending on the end point. Full circles come from tessellate_circle.
+fn arc(center: Vec2, radius: Scalar, start: Scalar, sweep: Scalar, segments: usize) -> Vec<Vec2> {
+    return (0..segments + 1).map(|i| {
+        let angle = start + sweep * i as Scalar / segments as Scalar;
+        center + Vec2::new(radius * angle.cos(), radius * angle.sin())
+    }).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::body::{BodyDef, BodyHandle};
+
+    fn body(def: BodyDef) -> Body {
+        return Body::new(0, BodyHandle{ index: 0, generation: 0 }, def);
+    }
+
+    #[test]
+    fn turned_box_is_drawn_at_its_world_corners() {
+        let turned = body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(2.0, 3.0)).angle(consts::FRAC_PI_2).build());
+        let drawn = DebugShape::new(&turned, Vec::new());
+        assert!(drawn.closed);
+        let corners = [Vec2::new(2.5, 2.5), Vec2::new(2.5, 3.5), Vec2::new(1.5, 3.5), Vec2::new(1.5, 2.5)];
+        assert_eq!(drawn.polyline.len(), corners.len());
+        for (point, corner) in drawn.polyline.iter().zip(corners.iter()) {
+            assert!(point.approx_eq(*corner, 1.0e-5));
+        }
+    }
+
+    #[test]
+    fn round_shapes_are_drawn_on_their_outline() {
+        let circle = body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0}).position(Vec2::new(0.0, 10.0)).build());
+        let drawn = DebugShape::new(&circle, Vec::new());
+        assert_eq!(drawn.polyline.len(), CIRCLE_SEGMENTS);
+        for point in drawn.polyline.iter() {
+            assert!(((*point - Vec2::new(0.0, 10.0)).length() - 1.0).abs() < 1.0e-4);
+        }
+
+        let capsule = body(BodyDef::builder(Shape::CapsuleShape{center1: Vec2::new(-1.0, 0.0), center2: Vec2::new(1.0, 0.0), radius: 0.5}).build());
+        for point in DebugShape::new(&capsule, Vec::new()).polyline.iter() {
+            let nearest = Vec2::new(point.x.clamp(-1.0, 1.0), 0.0);
+            assert!(((*point - nearest).length() - 0.5).abs() < 1.0e-4);
+        }
+    }
+}
@@ -1,19 +1,32 @@
+use std::cmp::Ordering;
 use super::body::Body;
 use super::broad_phase::BroadPhase;
 
 pub struct DefaultBroadPhase;
 
 impl BroadPhase for DefaultBroadPhase {
-    fn run(&self, bodies: &Vec<Body>) -> Vec<(Body, Body)> {
-        // The worst implementation possible!!!
-        let mut pairs: Vec<(Body, Body)> = Vec::new();
-        for (i, body1) in bodies.iter().enumerate() {
-            for (j, body2) in bodies.iter().enumerate() {
-                if i != j {
-                    pairs.push( ((*body1).clone(), (*body2).clone()) );
+    fn run(&self, bodies: &Vec<Body>) -> Vec<(usize, usize)> {
+        // Sort and sweep along the x axis. Only bodies whose x intervals overlap are
+        // compared on both axes.
+        let mut order: Vec<usize> = (0..bodies.len()).collect();
+        order.sort_by(|&a, &b| {
+            bodies[a].aabb().min.x.partial_cmp(&bodies[b].aabb().min.x).unwrap_or(Ordering::Equal)
+        });
+
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for (i, &a) in order.iter().enumerate() {
+            let aabb_a = bodies[a].aabb();
+            for &b in order[i + 1..].iter() {
+                let aabb_b = bodies[b].aabb();
+                if aabb_b.min.x > aabb_a.max.x {
+                    break;
+                }
+                if aabb_a.overlaps(&aabb_b) {
+                    pairs.push((a.min(b), a.max(b)));
                 }
             }
         }
+        pairs.sort();
         return pairs;
     }
 }
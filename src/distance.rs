@@ -0,0 +1,267 @@
+use super::math::{Vec2, Transform, EPSILON, Scalar};
+use super::shape::shape::Shape;
+use super::shape::ellipse::ellipse_points;
+
+const MAX_ITERATIONS: usize = 20;
+// How far a half plane reaches when GJK stands a box in for it
+const HALF_PLANE_EXTENT: Scalar = 1000.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DistanceOutput {
+    // Closest point on each shape, in world coordinates
+    pub point_a: Vec2,
+    pub point_b: Vec2,
+    // Zero when the shapes overlap
+    pub distance: Scalar
+}
+
+// A shape as GJK sees it, a convex set of points grown by a radius. Chains are treated as
+// the convex hull of their points, ellipses as the polygon of their support points and half
+// planes as a large box behind their surface.
+struct DistanceProxy {
+    vertices: Vec<Vec2>,
+    radius: Scalar
+}
+
+impl DistanceProxy {
+    fn new(shape: &Shape) -> DistanceProxy {
+        match *shape {
+            Shape::CircleShape{center, radius} => {
+                return DistanceProxy{ vertices: vec![center], radius: radius };
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                return DistanceProxy{ vertices: vec![center1, center2], radius: radius };
+            },
+            Shape::LineShape{point1, point2} | Shape::EdgeShape{vertex1: point1, vertex2: point2, ..} => {
+                return DistanceProxy{ vertices: vec![point1, point2], radius: 0.0 };
+            },
+            Shape::ChainLineShape{ref points} | Shape::PolygonShape{ref points} => {
+                let vertices = if points.is_empty() { vec![Vec2::new_zero()] } else { points.clone() };
+                return DistanceProxy{ vertices: vertices, radius: 0.0 };
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                return DistanceProxy{ vertices: ellipse_points(center, radius_x, radius_y), radius: 0.0 };
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                let surface = normal.multiply(offset);
+                let along = Vec2::new(-normal.y, normal.x).multiply(HALF_PLANE_EXTENT);
+                let behind = normal.multiply(-HALF_PLANE_EXTENT);
+                let vertices = vec![surface - along, surface + along, surface + along + behind, surface - along + behind];
+                return DistanceProxy{ vertices: vertices, radius: 0.0 };
+            }
+        }
+    }
+
+    // The index of the vertex furthest along the local direction.
+    fn support(&self, direction: Vec2) -> usize {
+        let mut best_index = 0;
+        let mut best_value = self.vertices[0].dot(direction);
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            let value = vertex.dot(direction);
+            if value > best_value {
+                best_index = i;
+                best_value = value;
+            }
+        }
+        return best_index;
+    }
+}
+
+// A point of the Minkowski difference B - A along with where it came from.
+#[derive(Clone, Copy)]
+struct SimplexVertex {
+    w_a: Vec2,
+    w_b: Vec2,
+    w: Vec2,
+    // Barycentric weight of this vertex in the closest point
+    a: Scalar,
+    index_a: usize,
+    index_b: usize
+}
+
+impl SimplexVertex {
+    fn new(proxy_a: &DistanceProxy, xf_a: Transform, index_a: usize, proxy_b: &DistanceProxy, xf_b: Transform, index_b: usize) -> SimplexVertex {
+        let w_a = xf_a.apply(proxy_a.vertices[index_a]);
+        let w_b = xf_b.apply(proxy_b.vertices[index_b]);
+        return SimplexVertex{ w_a: w_a, w_b: w_b, w: w_b - w_a, a: 1.0, index_a: index_a, index_b: index_b };
+    }
+}
+
+// The closest points between two convex shapes, found with GJK as in Box2D.
+pub fn distance(shape_a: &Shape, xf_a: Transform, shape_b: &Shape, xf_b: Transform) -> DistanceOutput {
+    let proxy_a = DistanceProxy::new(shape_a);
+    let proxy_b = DistanceProxy::new(shape_b);
+
+    let mut simplex: Vec<SimplexVertex> = vec![SimplexVertex::new(&proxy_a, xf_a, 0, &proxy_b, xf_b, 0)];
+    for _ in 0..MAX_ITERATIONS {
+        let previous: Vec<(usize, usize)> = simplex.iter().map(|v| (v.index_a, v.index_b)).collect();
+
+        match simplex.len() {
+            2 => solve2(&mut simplex),
+            3 => solve3(&mut simplex),
+            _ => {}
+        }
+        // The origin is inside the triangle, so the shapes overlap
+        if simplex.len() == 3 {
+            break;
+        }
+
+        let direction = search_direction(&simplex);
+        if direction.length_squared() < EPSILON * EPSILON {
+            break;
+        }
+
+        let index_a = proxy_a.support(xf_a.rotation.inv_rotate(-direction));
+        let index_b = proxy_b.support(xf_b.rotation.inv_rotate(direction));
+        // Coming back to a vertex already in the simplex means no progress can be made
+        if previous.contains(&(index_a, index_b)) {
+            break;
+        }
+        simplex.push(SimplexVertex::new(&proxy_a, xf_a, index_a, &proxy_b, xf_b, index_b));
+    }
+
+    let mut point_a = Vec2::new_zero();
+    let mut point_b = Vec2::new_zero();
+    for vertex in simplex.iter() {
+        point_a += vertex.w_a.multiply(vertex.a);
+        point_b += vertex.w_b.multiply(vertex.a);
+    }
+    if simplex.len() == 3 {
+        point_b = point_a;
+    }
+    let mut distance = (point_b - point_a).length();
+
+    // Grow the core shapes by their radii
+    let total_radius = proxy_a.radius + proxy_b.radius;
+    if distance > total_radius && distance > EPSILON {
+        let mut normal = point_b - point_a;
+        normal.normalize();
+        distance -= total_radius;
+        point_a += normal.multiply(proxy_a.radius);
+        point_b -= normal.multiply(proxy_b.radius);
+    } else {
+        let middle = (point_a + point_b).multiply(0.5);
+        point_a = middle;
+        point_b = middle;
+        distance = 0.0;
+    }
+
+    return DistanceOutput{ point_a: point_a, point_b: point_b, distance: distance };
+}
+
+// Towards the origin from the closest feature of the simplex.
+fn search_direction(simplex: &[SimplexVertex]) -> Vec2 {
+    if simplex.len() == 1 {
+        return -simplex[0].w;
+    }
+    let e12 = simplex[1].w - simplex[0].w;
+    if e12.cross(-simplex[0].w) > 0.0 {
+        // The origin is left of the edge
+        return Vec2::scalar_cross(1.0, e12);
+    }
+    return e12.cross_scalar(1.0);
+}
+
+// Reduces a segment to the part closest to the origin.
+fn solve2(simplex: &mut Vec<SimplexVertex>) {
+    let w1 = simplex[0].w;
+    let w2 = simplex[1].w;
+    let e12 = w2 - w1;
+
+    let d12_2 = -w1.dot(e12);
+    if d12_2 <= 0.0 {
+        simplex[0].a = 1.0;
+        simplex.truncate(1);
+        return;
+    }
+    let d12_1 = w2.dot(e12);
+    if d12_1 <= 0.0 {
+        simplex[1].a = 1.0;
+        simplex.remove(0);
+        return;
+    }
+
+    let inv_d12 = 1.0 / (d12_1 + d12_2);
+    simplex[0].a = d12_1 * inv_d12;
+    simplex[1].a = d12_2 * inv_d12;
+}
+
+// Reduces a triangle to the vertex, edge or whole triangle closest to the origin.
+fn solve3(simplex: &mut Vec<SimplexVertex>) {
+    let w1 = simplex[0].w;
+    let w2 = simplex[1].w;
+    let w3 = simplex[2].w;
+
+    let e12 = w2 - w1;
+    let d12_1 = w2.dot(e12);
+    let d12_2 = -w1.dot(e12);
+
+    let e13 = w3 - w1;
+    let d13_1 = w3.dot(e13);
+    let d13_2 = -w1.dot(e13);
+
+    let e23 = w3 - w2;
+    let d23_1 = w3.dot(e23);
+    let d23_2 = -w2.dot(e23);
+
+    let n123 = e12.cross(e13);
+    let d123_1 = n123 * w2.cross(w3);
+    let d123_2 = n123 * w3.cross(w1);
+    let d123_3 = n123 * w1.cross(w2);
+
+    let (v1, v2, v3) = (simplex[0], simplex[1], simplex[2]);
+    if d12_2 <= 0.0 && d13_2 <= 0.0 {
+        *simplex = vec![SimplexVertex{ a: 1.0, ..v1 }];
+    } else if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+        let inv_d12 = 1.0 / (d12_1 + d12_2);
+        *simplex = vec![SimplexVertex{ a: d12_1 * inv_d12, ..v1 }, SimplexVertex{ a: d12_2 * inv_d12, ..v2 }];
+    } else if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+        let inv_d13 = 1.0 / (d13_1 + d13_2);
+        *simplex = vec![SimplexVertex{ a: d13_1 * inv_d13, ..v1 }, SimplexVertex{ a: d13_2 * inv_d13, ..v3 }];
+    } else if d12_1 <= 0.0 && d23_2 <= 0.0 {
+        *simplex = vec![SimplexVertex{ a: 1.0, ..v2 }];
+    } else if d13_1 <= 0.0 && d23_1 <= 0.0 {
+        *simplex = vec![SimplexVertex{ a: 1.0, ..v3 }];
+    } else if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+        let inv_d23 = 1.0 / (d23_1 + d23_2);
+        *simplex = vec![SimplexVertex{ a: d23_2 * inv_d23, ..v3 }, SimplexVertex{ a: d23_1 * inv_d23, ..v2 }];
+    } else {
+        let inv_d123 = 1.0 / (d123_1 + d123_2 + d123_3);
+        simplex[0].a = d123_1 * inv_d123;
+        simplex[1].a = d123_2 * inv_d123;
+        simplex[2].a = d123_3 * inv_d123;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::math::consts;
+
+    #[test]
+    fn separated_circles_are_the_gap_between_their_surfaces_apart() {
+        let circle = Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5};
+        let output = distance(&circle, Transform::identity(), &circle, Transform::new(Vec2::new(3.0, 4.0), 0.0));
+        assert!((output.distance - 4.0).abs() < 1.0e-4);
+        assert!(output.point_a.approx_eq(Vec2::new(0.3, 0.4), 1.0e-4));
+        assert!(output.point_b.approx_eq(Vec2::new(2.7, 3.6), 1.0e-4));
+    }
+
+    #[test]
+    fn separated_squares_measure_between_facing_sides() {
+        let square = Shape::box_shape(0.5, 0.5);
+        let output = distance(&square, Transform::identity(), &square, Transform::new(Vec2::new(3.0, 0.3), 0.0));
+        assert!((output.distance - 2.0).abs() < 1.0e-4);
+
+        // Turned 45 degrees, the corner of the second square points at the first
+        let output = distance(&square, Transform::identity(), &square, Transform::new(Vec2::new(3.0, 3.0), consts::FRAC_PI_4));
+        let root_2 = consts::SQRT_2;
+        assert!((output.distance - (3.0 * root_2 - 0.5 * root_2 - 0.5)).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn overlapping_shapes_are_zero_apart() {
+        let square = Shape::box_shape(0.5, 0.5);
+        assert_eq!(distance(&square, Transform::identity(), &square, Transform::new(Vec2::new(0.5, 0.3), 0.3)).distance, 0.0);
+    }
+}
@@ -0,0 +1,50 @@
+use super::math::Scalar;
+use super::shape::shape::Shape;
+
+// An extra shape for a body, with its own material and collision filter. The body's own
+// shape, density, friction and filter act as its first fixture, so a body only needs
+// fixtures beyond that one shape.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FixtureDef {
+    // In body local coordinates, like the body's own shape
+    pub shape: Shape,
+    pub density: Scalar,
+    pub friction: Scalar,
+    pub restitution: Scalar,
+    pub category_bits: u16,
+    pub mask_bits: u16,
+    pub group_index: i16,
+    // Sensors report contacts but never push or get pushed
+    pub is_sensor: bool
+}
+
+impl FixtureDef {
+    // A solid fixture with the same defaults as a BodyDef.
+    pub fn new(shape: Shape) -> FixtureDef {
+        return FixtureDef{ shape: shape, density: 1.0, friction: 0.3, restitution: 0.0,
+            category_bits: 0x0001, mask_bits: 0xFFFF, group_index: 0, is_sensor: false };
+    }
+}
+
+// A fixture attached to a body, made from a FixtureDef.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fixture {
+    pub shape: Shape,
+    pub density: Scalar,
+    pub friction: Scalar,
+    pub restitution: Scalar,
+    pub category_bits: u16,
+    pub mask_bits: u16,
+    pub group_index: i16,
+    pub is_sensor: bool
+}
+
+impl Fixture {
+    pub fn new(fixture_def: FixtureDef) -> Fixture {
+        return Fixture{ shape: fixture_def.shape, density: fixture_def.density, friction: fixture_def.friction,
+            restitution: fixture_def.restitution, category_bits: fixture_def.category_bits,
+            mask_bits: fixture_def.mask_bits, group_index: fixture_def.group_index, is_sensor: fixture_def.is_sensor };
+    }
+}
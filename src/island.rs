@@ -0,0 +1,67 @@
+// Splits bodies into islands, the groups of bodies joined to each other by contacts or
+// joints, with a union find over body indices. Static and kinematic bodies never join
+// islands together, since nothing one island does can move them.
+pub struct Islands {
+    parents: Vec<usize>
+}
+
+impl Islands {
+    // Every body starts on an island of its own.
+    pub fn new(body_count: usize) -> Islands {
+        return Islands{ parents: (0..body_count).collect() };
+    }
+
+    // The body that stands for the island the body is on, always its lowest index.
+    pub fn find(&mut self, body: usize) -> usize {
+        let mut current = body;
+        while self.parents[current] != current {
+            // Halve the path on the way up, so later finds are quicker
+            let grandparent = self.parents[self.parents[current]];
+            self.parents[current] = grandparent;
+            current = grandparent;
+        }
+        return current;
+    }
+
+    pub fn join(&mut self, a: usize, b: usize) {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a < root_b {
+            self.parents[root_b] = root_a;
+        } else if root_b < root_a {
+            self.parents[root_a] = root_b;
+        }
+    }
+
+    // The bodies on each island, each island in index order and the islands ordered by
+    // their lowest body.
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut slots: Vec<Option<usize>> = vec![None; self.parents.len()];
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for body in 0..self.parents.len() {
+            let root = self.find(body);
+            match slots[root] {
+                Some(slot) => groups[slot].push(body),
+                None => {
+                    slots[root] = Some(groups.len());
+                    groups.push(vec![body]);
+                }
+            }
+        }
+        return groups;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joined_bodies_share_an_island_named_by_the_lowest() {
+        let mut islands = Islands::new(5);
+        islands.join(3, 1);
+        islands.join(4, 3);
+        assert_eq!(islands.find(4), 1);
+        assert_eq!(islands.groups(), vec![vec![0], vec![1, 3, 4], vec![2]]);
+    }
+}
@@ -0,0 +1,170 @@
+use super::super::math::{Vec2, Rot, Scalar};
+use super::super::body::{Body, BodyHandle};
+use super::joint::{apply_impulse, apply_position_impulse};
+
+// The most the position pass moves a rigid distance joint in one iteration
+const MAX_CORRECTION: Scalar = 0.2;
+
+// Keeps two anchor points a set distance apart, either rigidly like a rod or softly like
+// a spring.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DistanceJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // The anchor relative to each body's position, in the body's unrotated frame
+    pub local_anchor_a: Vec2,
+    pub local_anchor_b: Vec2,
+    pub length: Scalar,
+    // Spring stiffness in N/m, zero for a rigid rod
+    pub stiffness: Scalar,
+    // Spring damping in N*s/m, only used with a stiffness
+    pub damping: Scalar,
+    pub collide_connected: bool,
+    // Solver state for the current step
+    impulse: Scalar,
+    u: Vec2,
+    r_a: Vec2,
+    r_b: Vec2,
+    mass: Scalar,
+    soft_mass: Scalar,
+    gamma: Scalar,
+    bias: Scalar
+}
+
+impl DistanceJoint {
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, local_anchor_a: Vec2, local_anchor_b: Vec2,
+               length: Scalar, stiffness: Scalar, damping: Scalar) -> DistanceJoint {
+        return DistanceJoint{
+            body_a: body_a,
+            body_b: body_b,
+            local_anchor_a: local_anchor_a,
+            local_anchor_b: local_anchor_b,
+            length: length,
+            stiffness: stiffness,
+            damping: damping,
+            collide_connected: false,
+            impulse: 0.0,
+            u: Vec2::new_zero(),
+            r_a: Vec2::new_zero(),
+            r_b: Vec2::new_zero(),
+            mass: 0.0,
+            soft_mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0
+        }
+    }
+
+    // The pull or push along the rod on body b over the last step.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return self.u.multiply(self.impulse * inv_dt);
+    }
+
+    // A rod pushes along itself only, so there is never a torque.
+    pub fn reaction_torque(&self, _inv_dt: Scalar) -> Scalar {
+        return 0.0;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        self.r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        self.r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        self.u = bodies[b].position + self.r_b - bodies[a].position - self.r_a;
+        let current_length = self.u.normalize();
+
+        let inv_mass = axis_inv_mass(&bodies[a], &bodies[b], self.r_a, self.r_b, self.u);
+        self.mass = if inv_mass != 0.0 { 1.0 / inv_mass } else { 0.0 };
+
+        if self.stiffness > 0.0 {
+            // Soft constraint as in Box2D, the spring is folded into the effective mass
+            let c = current_length - self.length;
+            let h = time_step;
+            self.gamma = h * (self.damping + h * self.stiffness);
+            self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+            self.bias = c * h * self.stiffness * self.gamma;
+            let soft_inv_mass = inv_mass + self.gamma;
+            self.soft_mass = if soft_inv_mass != 0.0 { 1.0 / soft_inv_mass } else { 0.0 };
+        } else {
+            self.gamma = 0.0;
+            self.bias = 0.0;
+            self.soft_mass = self.mass;
+        }
+        self.impulse = 0.0;
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let velocity_a = bodies[a].velocity + Vec2::scalar_cross(bodies[a].angular_velocity, self.r_a);
+        let velocity_b = bodies[b].velocity + Vec2::scalar_cross(bodies[b].angular_velocity, self.r_b);
+        let cdot = self.u.dot(velocity_b - velocity_a);
+
+        let impulse = -self.soft_mass * (cdot + self.bias + self.gamma * self.impulse);
+        self.impulse += impulse;
+        apply_impulse(bodies, a, b, self.r_a, self.r_b, self.u.multiply(impulse));
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        // A spring is allowed to stretch
+        if self.stiffness > 0.0 {
+            return;
+        }
+        let r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let mut u = bodies[b].position + r_b - bodies[a].position - r_a;
+        let current_length = u.normalize();
+        let c = (current_length - self.length).clamp(-MAX_CORRECTION, MAX_CORRECTION);
+
+        let inv_mass = axis_inv_mass(&bodies[a], &bodies[b], r_a, r_b, u);
+        if inv_mass == 0.0 {
+            return;
+        }
+        apply_position_impulse(bodies, a, b, r_a, r_b, u.multiply(-c / inv_mass));
+    }
+}
+
+// The inverse effective mass for pushing the anchors apart along `axis`.
+fn axis_inv_mass(body_a: &Body, body_b: &Body, r_a: Vec2, r_b: Vec2, axis: Vec2) -> Scalar {
+    let cr_a = r_a.cross(axis);
+    let cr_b = r_b.cross(axis);
+    return body_a.inv_mass() + body_a.inv_inertia() * cr_a * cr_a +
+        body_b.inv_mass() + body_b.inv_inertia() * cr_b * cr_b;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    // The shortest and longest the joint gets after settling in, with a ball hung from a
+    // static anchor at the origin.
+    fn swing(stiffness: Scalar) -> (Scalar, Scalar) {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let anchor = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(1.5, 0.5)).build());
+        world.add_joint(DistanceJoint::new(anchor, ball, Vec2::new_zero(), Vec2::new_zero(), 2.0, stiffness, 0.5));
+        let (mut shortest, mut longest): (Scalar, Scalar) = (Scalar::INFINITY, 0.0);
+        for i in 0..600 {
+            world.step(1.0 / 60.0);
+            let length = world.get_body(ball).unwrap().position.length();
+            if i > 30 {
+                shortest = shortest.min(length);
+                longest = longest.max(length);
+            }
+        }
+        return (shortest, longest);
+    }
+
+    #[test]
+    fn rigid_joint_holds_its_length() {
+        let (shortest, longest) = swing(0.0);
+        assert!((shortest - 2.0).abs() < 0.01);
+        assert!((longest - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn soft_joint_stretches() {
+        let (shortest, longest) = swing(20.0);
+        assert!(longest - shortest > 0.05);
+    }
+}
@@ -0,0 +1,302 @@
+use super::super::math::{Vec2, Rot, Scalar};
+use super::super::body::{Body, BodyHandle};
+use super::joint::{Joint, JointHandle};
+
+// How each of the coupled joints measures its motion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum Coupling {
+     // The angle of the joint's body b relative to its body a
+     Revolute,
+     // How far the joint's body b has slid along body a's axis
+     Prismatic,
+}
+
+// One coupled joint, copied when the gear is added to the world. The driven body is the
+// joint's body b and the base it moves against is the joint's body a.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct GearSide {
+    coupling: Coupling,
+    base: BodyHandle,
+    local_anchor_base: Vec2,
+    local_anchor_driven: Vec2,
+    local_axis_base: Vec2,
+    reference_angle: Scalar
+}
+
+// The gear's view of one side at a time: the jacobian of the coordinate for the driven
+// body and the base.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct SideJacobian {
+    linear: Vec2,
+    angular_driven: Scalar,
+    angular_base: Scalar
+}
+
+// Couples two revolute or prismatic joints so that ratio * coordinate_1 - coordinate_2
+// stays the same, where a coordinate is a joint's angle or translation. Body b then moves
+// ratio times as fast as body a, or the other way round for a negative ratio, the way
+// meshed gears turn.
+//
+// Both joints should have a static body as their body a. World::add_joint reads the
+// joints when the gear is added, which sets body_a to joint_1's body b and body_b to
+// joint_2's body b, so the handles have to refer to joints already in the world.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GearJoint {
+    pub joint_1: JointHandle,
+    pub joint_2: JointHandle,
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    pub ratio: Scalar,
+    // ratio * coordinate_1 - coordinate_2, taken from where the bodies are on the first
+    // step when left as None
+    pub constant: Option<Scalar>,
+    pub collide_connected: bool,
+    sides: Option<(GearSide, GearSide)>,
+    // Solver state, kept from one step to the next to warm start the solver
+    impulse: Scalar,
+    time_step: Scalar,
+    base_a: usize,
+    base_b: usize,
+    jacobian_a: SideJacobian,
+    jacobian_b: SideJacobian,
+    mass: Scalar
+}
+
+impl GearJoint {
+    pub fn new(joint_1: JointHandle, joint_2: JointHandle, ratio: Scalar) -> GearJoint {
+        return GearJoint{
+            joint_1: joint_1,
+            joint_2: joint_2,
+            body_a: BodyHandle{ index: 0, generation: 0 },
+            body_b: BodyHandle{ index: 0, generation: 0 },
+            ratio: ratio,
+            constant: None,
+            collide_connected: false,
+            sides: None,
+            impulse: 0.0,
+            time_step: 0.0,
+            base_a: 0,
+            base_b: 0,
+            jacobian_a: SideJacobian::default(),
+            jacobian_b: SideJacobian::default(),
+            mass: 0.0
+        }
+    }
+
+    // Copies what the gear needs from the two joints. Returns false unless both are
+    // revolute or prismatic joints.
+    pub fn bind(&mut self, joint_1: &Joint, joint_2: &Joint) -> bool {
+        match (gear_side(joint_1), gear_side(joint_2)) {
+            (Some((driven_a, side_a)), Some((driven_b, side_b))) => {
+                self.body_a = driven_a;
+                self.body_b = driven_b;
+                self.sides = Some((side_a, side_b));
+                return true;
+            },
+            _ => return false
+        }
+    }
+
+    // The force the gear put on body b over the last step, along joint_2's axis when it is
+    // prismatic and zero otherwise.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return self.jacobian_b.linear.multiply(self.impulse * inv_dt);
+    }
+
+    // The torque the gear put on body b over the last step.
+    pub fn reaction_torque(&self, inv_dt: Scalar) -> Scalar {
+        return self.jacobian_b.angular_driven * self.impulse * inv_dt;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        let (side_a, side_b) = match self.sides {
+            Some((ref side_a, ref side_b)) => (side_a.clone(), side_b.clone()),
+            None => {
+                self.mass = 0.0;
+                return;
+            }
+        };
+        let (base_a, base_b) = match (find_body(bodies, side_a.base), find_body(bodies, side_b.base)) {
+            (Some(base_a), Some(base_b)) => (base_a, base_b),
+            _ => {
+                self.sides = None;
+                self.mass = 0.0;
+                return;
+            }
+        };
+        self.base_a = base_a;
+        self.base_b = base_b;
+        self.jacobian_a = jacobian(&side_a, &bodies[a], &bodies[base_a], self.ratio);
+        self.jacobian_b = jacobian(&side_b, &bodies[b], &bodies[base_b], -1.0);
+        self.mass = gear_mass(bodies, a, base_a, self.jacobian_a, b, base_b, self.jacobian_b);
+        if self.constant.is_none() {
+            self.constant = Some(self.ratio * coordinate(&side_a, &bodies[a], &bodies[base_a]) - coordinate(&side_b, &bodies[b], &bodies[base_b]));
+        }
+
+        // Start from last step's impulse, scaled to the new step
+        let ratio = if self.time_step > 0.0 { time_step / self.time_step } else { 0.0 };
+        self.time_step = time_step;
+        self.impulse *= ratio;
+        let impulse = self.impulse;
+        self.apply(bodies, a, b, impulse);
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        if self.mass == 0.0 {
+            return;
+        }
+        let cdot = side_velocity(self.jacobian_a, &bodies[a], &bodies[self.base_a]) +
+            side_velocity(self.jacobian_b, &bodies[b], &bodies[self.base_b]);
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        self.apply(bodies, a, b, impulse);
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let (side_a, side_b) = match self.sides {
+            Some((ref side_a, ref side_b)) => (side_a.clone(), side_b.clone()),
+            None => return
+        };
+        if self.mass == 0.0 {
+            return;
+        }
+        let (base_a, base_b) = (self.base_a, self.base_b);
+        let jacobian_a = jacobian(&side_a, &bodies[a], &bodies[base_a], self.ratio);
+        let jacobian_b = jacobian(&side_b, &bodies[b], &bodies[base_b], -1.0);
+        let c = self.ratio * coordinate(&side_a, &bodies[a], &bodies[base_a]) - coordinate(&side_b, &bodies[b], &bodies[base_b]) - self.constant.unwrap_or(0.0);
+        let impulse = -gear_mass(bodies, a, base_a, jacobian_a, b, base_b, jacobian_b) * c;
+
+        for &(driven, base, jacobian) in [(a, base_a, jacobian_a), (b, base_b, jacobian_b)].iter() {
+            let (m_driven, i_driven) = (bodies[driven].inv_mass(), bodies[driven].inv_inertia());
+            bodies[driven].position += jacobian.linear.multiply(m_driven * impulse);
+            bodies[driven].angle += i_driven * impulse * jacobian.angular_driven;
+            let (m_base, i_base) = (bodies[base].inv_mass(), bodies[base].inv_inertia());
+            bodies[base].position -= jacobian.linear.multiply(m_base * impulse);
+            bodies[base].angle -= i_base * impulse * jacobian.angular_base;
+        }
+    }
+
+    // Pushes each driven body along its coordinate and its base the other way.
+    fn apply(&self, bodies: &mut [Body], a: usize, b: usize, impulse: Scalar) {
+        for &(driven, base, jacobian) in [(a, self.base_a, self.jacobian_a), (b, self.base_b, self.jacobian_b)].iter() {
+            let (m_driven, i_driven) = (bodies[driven].inv_mass(), bodies[driven].inv_inertia());
+            bodies[driven].velocity += jacobian.linear.multiply(m_driven * impulse);
+            bodies[driven].angular_velocity += i_driven * impulse * jacobian.angular_driven;
+            let (m_base, i_base) = (bodies[base].inv_mass(), bodies[base].inv_inertia());
+            bodies[base].velocity -= jacobian.linear.multiply(m_base * impulse);
+            bodies[base].angular_velocity -= i_base * impulse * jacobian.angular_base;
+        }
+    }
+}
+
+// The driven body and the rest of what the gear needs from a joint it couples.
+fn gear_side(joint: &Joint) -> Option<(BodyHandle, GearSide)> {
+    match *joint {
+        Joint::Revolute(ref joint) => {
+            return Some((joint.body_b, GearSide{ coupling: Coupling::Revolute, base: joint.body_a,
+                local_anchor_base: joint.local_anchor_a, local_anchor_driven: joint.local_anchor_b,
+                local_axis_base: Vec2::new_zero(), reference_angle: joint.reference_angle }));
+        },
+        Joint::Prismatic(ref joint) => {
+            return Some((joint.body_b, GearSide{ coupling: Coupling::Prismatic, base: joint.body_a,
+                local_anchor_base: joint.local_anchor_a, local_anchor_driven: joint.local_anchor_b,
+                local_axis_base: joint.local_axis_a, reference_angle: joint.reference_angle }));
+        },
+        _ => return None
+    }
+}
+
+fn find_body(bodies: &[Body], handle: BodyHandle) -> Option<usize> {
+    return bodies.iter().position(|body| body.handle == handle);
+}
+
+// The joint's angle or translation.
+fn coordinate(side: &GearSide, driven: &Body, base: &Body) -> Scalar {
+    match side.coupling {
+        Coupling::Revolute => return driven.angle - base.angle - side.reference_angle,
+        Coupling::Prismatic => {
+            let anchor_base = base.world_point(side.local_anchor_base);
+            let anchor_driven = driven.world_point(side.local_anchor_driven);
+            return (anchor_driven - anchor_base).dot(Rot::new(base.angle).rotate(side.local_axis_base));
+        }
+    }
+}
+
+// How the coordinate changes with each body's motion, scaled by `scale`. As in Box2D the
+// turning of a prismatic joint's axis is left out.
+fn jacobian(side: &GearSide, driven: &Body, base: &Body, scale: Scalar) -> SideJacobian {
+    match side.coupling {
+        Coupling::Revolute => {
+            return SideJacobian{ linear: Vec2::new_zero(), angular_driven: scale, angular_base: scale };
+        },
+        Coupling::Prismatic => {
+            let axis = Rot::new(base.angle).rotate(side.local_axis_base);
+            let r_base = Rot::new(base.angle).rotate(side.local_anchor_base);
+            let r_driven = Rot::new(driven.angle).rotate(side.local_anchor_driven);
+            return SideJacobian{ linear: axis.multiply(scale), angular_driven: scale * r_driven.cross(axis), angular_base: scale * r_base.cross(axis) };
+        }
+    }
+}
+
+fn side_velocity(jacobian: SideJacobian, driven: &Body, base: &Body) -> Scalar {
+    return jacobian.linear.dot(driven.velocity - base.velocity) +
+        jacobian.angular_driven * driven.angular_velocity - jacobian.angular_base * base.angular_velocity;
+}
+
+fn side_inv_mass(jacobian: SideJacobian, driven: &Body, base: &Body) -> Scalar {
+    return jacobian.linear.length_squared() * (driven.inv_mass() + base.inv_mass()) +
+        driven.inv_inertia() * jacobian.angular_driven * jacobian.angular_driven +
+        base.inv_inertia() * jacobian.angular_base * jacobian.angular_base;
+}
+
+fn gear_mass(bodies: &[Body], a: usize, base_a: usize, jacobian_a: SideJacobian, b: usize, base_b: usize, jacobian_b: SideJacobian) -> Scalar {
+    let inv_mass = side_inv_mass(jacobian_a, &bodies[a], &bodies[base_a]) + side_inv_mass(jacobian_b, &bodies[b], &bodies[base_b]);
+    return if inv_mass > 0.0 { 1.0 / inv_mass } else { 0.0 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+    use super::super::revolute_joint::RevoluteJoint;
+    use super::super::prismatic_joint::PrismaticJoint;
+
+    #[test]
+    fn gears_couple_wheels_and_a_rack_by_their_ratios() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let ground = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).position(Vec2::new(0.0, 10.0)).build());
+        let wheel = |world: &mut World, x: Scalar, radius: Scalar| {
+            return world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: radius})
+                .body_type(BodyType::DynamicBody).position(Vec2::new(x, 0.0)).build());
+        };
+        let big = wheel(&mut world, 0.0, 1.0);
+        let small = wheel(&mut world, 5.0, 0.5);
+        let rack = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(10.0, 0.0)).build());
+
+        let big_axle = RevoluteJoint::new(world.get_body(ground).unwrap(), world.get_body(big).unwrap(), Vec2::new(0.0, 0.0));
+        let mut small_axle = RevoluteJoint::new(world.get_body(ground).unwrap(), world.get_body(small).unwrap(), Vec2::new(5.0, 0.0));
+        // The small wheel drives the rest
+        small_axle.set_motor(3.0, 1000.0);
+        let slide = PrismaticJoint::new(world.get_body(ground).unwrap(), world.get_body(rack).unwrap(), Vec2::new(10.0, 0.0), Vec2::new(1.0, 0.0));
+        let (big_axle, small_axle, slide) = (world.add_joint(big_axle), world.add_joint(small_axle), world.add_joint(slide));
+        world.add_joint(GearJoint::new(big_axle, small_axle, 2.0));
+        world.add_joint(GearJoint::new(big_axle, slide, -1.0));
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+
+        let (big, small, rack) = (world.get_body(big).unwrap(), world.get_body(small).unwrap(), world.get_body(rack).unwrap());
+        assert!(small.angular_velocity.abs() > 0.5);
+        assert!((small.angular_velocity - 2.0 * big.angular_velocity).abs() < 1.0e-3);
+        assert!((small.angle - 2.0 * big.angle).abs() < 1.0e-3);
+        assert!((rack.velocity.x + big.angular_velocity).abs() < 1.0e-3);
+        assert!((rack.position.x - 10.0 + big.angle).abs() < 1.0e-3);
+    }
+}
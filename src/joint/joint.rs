@@ -0,0 +1,246 @@
+use super::super::body::{Body, BodyHandle};
+use super::super::math::{Vec2, Scalar};
+use super::revolute_joint::RevoluteJoint;
+use super::distance_joint::DistanceJoint;
+use super::prismatic_joint::PrismaticJoint;
+use super::weld_joint::WeldJoint;
+use super::pulley_joint::PulleyJoint;
+use super::mouse_joint::MouseJoint;
+use super::gear_joint::GearJoint;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JointHandle {
+    pub index: u32,
+    pub generation: u32
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Joint {
+     Revolute(RevoluteJoint),
+     Distance(DistanceJoint),
+     Prismatic(PrismaticJoint),
+     Weld(WeldJoint),
+     Pulley(PulleyJoint),
+     Mouse(MouseJoint),
+     Gear(GearJoint),
+}
+
+impl Joint {
+    pub fn body_a(&self) -> BodyHandle {
+        match *self {
+            Joint::Revolute(ref joint) => return joint.body_a,
+            Joint::Distance(ref joint) => return joint.body_a,
+            Joint::Prismatic(ref joint) => return joint.body_a,
+            Joint::Weld(ref joint) => return joint.body_a,
+            Joint::Pulley(ref joint) => return joint.body_a,
+            Joint::Mouse(ref joint) => return joint.body_a,
+            Joint::Gear(ref joint) => return joint.body_a
+        }
+    }
+
+    pub fn body_b(&self) -> BodyHandle {
+        match *self {
+            Joint::Revolute(ref joint) => return joint.body_b,
+            Joint::Distance(ref joint) => return joint.body_b,
+            Joint::Prismatic(ref joint) => return joint.body_b,
+            Joint::Weld(ref joint) => return joint.body_b,
+            Joint::Pulley(ref joint) => return joint.body_b,
+            Joint::Mouse(ref joint) => return joint.body_b,
+            Joint::Gear(ref joint) => return joint.body_b
+        }
+    }
+
+    // Whether the two joined bodies still collide with each other.
+    pub fn collide_connected(&self) -> bool {
+        match *self {
+            Joint::Revolute(ref joint) => return joint.collide_connected,
+            Joint::Distance(ref joint) => return joint.collide_connected,
+            Joint::Prismatic(ref joint) => return joint.collide_connected,
+            Joint::Weld(ref joint) => return joint.collide_connected,
+            Joint::Pulley(ref joint) => return joint.collide_connected,
+            Joint::Mouse(ref joint) => return joint.collide_connected,
+            Joint::Gear(ref joint) => return joint.collide_connected
+        }
+    }
+
+    // The force the joint put on body b in the last step, which is the impulse it applied
+    // times inv_dt, one over the time step. Comparing it against a limit is how to break
+    // a joint that is pulled too hard.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        match *self {
+            Joint::Revolute(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Distance(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Prismatic(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Weld(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Pulley(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Mouse(ref joint) => return joint.reaction_force(inv_dt),
+            Joint::Gear(ref joint) => return joint.reaction_force(inv_dt)
+        }
+    }
+
+    // The torque the joint put on body b in the last step, like reaction_force.
+    pub fn reaction_torque(&self, inv_dt: Scalar) -> Scalar {
+        match *self {
+            Joint::Revolute(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Distance(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Prismatic(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Weld(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Pulley(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Mouse(ref joint) => return joint.reaction_torque(inv_dt),
+            Joint::Gear(ref joint) => return joint.reaction_torque(inv_dt)
+        }
+    }
+
+    // Called once per step before the solver iterations, with the indices of the bodies
+    // in World::bodies.
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        match *self {
+            Joint::Revolute(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Distance(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Prismatic(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Weld(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Pulley(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Mouse(ref mut joint) => joint.prepare(bodies, a, b, time_step),
+            Joint::Gear(ref mut joint) => joint.prepare(bodies, a, b, time_step)
+        }
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        match *self {
+            Joint::Revolute(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Distance(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Prismatic(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Weld(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Pulley(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Mouse(ref mut joint) => joint.solve_velocity(bodies, a, b),
+            Joint::Gear(ref mut joint) => joint.solve_velocity(bodies, a, b)
+        }
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        match *self {
+            Joint::Revolute(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Distance(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Prismatic(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Weld(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Pulley(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Mouse(ref mut joint) => joint.solve_position(bodies, a, b),
+            Joint::Gear(ref mut joint) => joint.solve_position(bodies, a, b)
+        }
+    }
+}
+
+impl From<RevoluteJoint> for Joint {
+    fn from(joint: RevoluteJoint) -> Joint {
+        return Joint::Revolute(joint);
+    }
+}
+
+impl From<DistanceJoint> for Joint {
+    fn from(joint: DistanceJoint) -> Joint {
+        return Joint::Distance(joint);
+    }
+}
+
+impl From<PrismaticJoint> for Joint {
+    fn from(joint: PrismaticJoint) -> Joint {
+        return Joint::Prismatic(joint);
+    }
+}
+
+impl From<WeldJoint> for Joint {
+    fn from(joint: WeldJoint) -> Joint {
+        return Joint::Weld(joint);
+    }
+}
+
+impl From<PulleyJoint> for Joint {
+    fn from(joint: PulleyJoint) -> Joint {
+        return Joint::Pulley(joint);
+    }
+}
+
+impl From<MouseJoint> for Joint {
+    fn from(joint: MouseJoint) -> Joint {
+        return Joint::Mouse(joint);
+    }
+}
+
+impl From<GearJoint> for Joint {
+    fn from(joint: GearJoint) -> Joint {
+        return Joint::Gear(joint);
+    }
+}
+
+// Applies an impulse at the anchors, pulling a back and pushing b forward.
+pub fn apply_impulse(bodies: &mut [Body], a: usize, b: usize, r_a: Vec2, r_b: Vec2, impulse: Vec2) {
+    let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+    let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+    bodies[a].velocity -= impulse.multiply(m_a);
+    bodies[a].angular_velocity -= i_a * r_a.cross(impulse);
+    bodies[b].velocity += impulse.multiply(m_b);
+    bodies[b].angular_velocity += i_b * r_b.cross(impulse);
+}
+
+// Turns b forward and a back.
+pub fn apply_angular_impulse(bodies: &mut [Body], a: usize, b: usize, impulse: Scalar) {
+    let (i_a, i_b) = (bodies[a].inv_inertia(), bodies[b].inv_inertia());
+    bodies[a].angular_velocity -= i_a * impulse;
+    bodies[b].angular_velocity += i_b * impulse;
+}
+
+// The same as apply_impulse, but moving the bodies directly.
+pub fn apply_position_impulse(bodies: &mut [Body], a: usize, b: usize, r_a: Vec2, r_b: Vec2, impulse: Vec2) {
+    let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+    let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+    bodies[a].position -= impulse.multiply(m_a);
+    bodies[a].angle -= i_a * r_a.cross(impulse);
+    bodies[b].position += impulse.multiply(m_b);
+    bodies[b].angle += i_b * r_b.cross(impulse);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    // The reaction force of a rope of length 1 holding up a weight of the given mass
+    fn hanging_force(mass: Scalar) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let anchor = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let weight = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, 1.0)).mass(mass).allow_sleep(false).build());
+        world.add_joint(DistanceJoint::new(anchor, weight, Vec2::new_zero(), Vec2::new_zero(), 1.0, 0.0, 0.0));
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let force = world.joints().next().unwrap().1.reaction_force(60.0).length();
+
+        // The joints can be changed in place and removed by their handles
+        for (_, joint) in world.joints_mut() {
+            if let Joint::Distance(ref mut rope) = *joint {
+                rope.length = 2.0;
+            }
+        }
+        let handles: Vec<JointHandle> = world.joints().map(|(handle, _)| handle).collect();
+        match world.get_joint(handles[0]) {
+            Some(&Joint::Distance(ref rope)) => assert_eq!(rope.length, 2.0),
+            _ => panic!("expected the distance joint")
+        }
+        assert!(world.remove_joint(handles[0]));
+        assert_eq!(world.joints().count(), 0);
+        return force;
+    }
+
+    #[test]
+    fn heavier_load_gives_a_larger_reaction_force() {
+        let light = hanging_force(1.0);
+        let heavy = hanging_force(10.0);
+        assert!((light - 9.8).abs() < 0.05 * 9.8);
+        assert!((heavy - 98.0).abs() < 0.05 * 98.0);
+    }
+}
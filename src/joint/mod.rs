@@ -0,0 +1,8 @@
+pub mod joint;
+pub mod revolute_joint;
+pub mod distance_joint;
+pub mod prismatic_joint;
+pub mod weld_joint;
+pub mod pulley_joint;
+pub mod mouse_joint;
+pub mod gear_joint;
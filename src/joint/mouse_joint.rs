@@ -0,0 +1,161 @@
+use super::super::math::{Vec2, Rot, Mat22, Scalar, consts};
+use super::super::body::{Body, BodyHandle};
+
+// How hard a new mouse joint pulls, as a spring of this frequency in hertz and damping ratio
+const DEFAULT_FREQUENCY: Scalar = 5.0;
+const DEFAULT_DAMPING_RATIO: Scalar = 0.7;
+
+// Drags a point of a single body towards a target with a soft spring, as the Box2D testbed
+// does with the mouse. The pull never exceeds max_force, so the body can be held back by
+// what it runs into. Both body_a and body_b are the dragged body.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MouseJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // World space point the anchor is pulled towards
+    pub target: Vec2,
+    // The anchor relative to the body's position, in the body's unrotated frame
+    pub local_anchor_b: Vec2,
+    pub max_force: Scalar,
+    // Spring stiffness in N/m
+    pub stiffness: Scalar,
+    // Spring damping in N*s/m
+    pub damping: Scalar,
+    pub collide_connected: bool,
+    // Solver state, kept from one step to the next to warm start the solver
+    impulse: Vec2,
+    time_step: Scalar,
+    r_b: Vec2,
+    mass: Mat22,
+    c: Vec2,
+    gamma: Scalar
+}
+
+impl MouseJoint {
+    // Grabs the body at the world space target, with a spring stiff enough for its mass.
+    pub fn new(body: &Body, target: Vec2, max_force: Scalar) -> MouseJoint {
+        let omega = 2.0 * consts::PI * DEFAULT_FREQUENCY;
+        return MouseJoint{
+            body_a: body.handle,
+            body_b: body.handle,
+            target: target,
+            local_anchor_b: Rot::new(body.angle).inv_rotate(target - body.position),
+            max_force: max_force,
+            stiffness: body.mass * omega * omega,
+            damping: 2.0 * body.mass * DEFAULT_DAMPING_RATIO * omega,
+            collide_connected: false,
+            impulse: Vec2::new_zero(),
+            time_step: 0.0,
+            r_b: Vec2::new_zero(),
+            mass: Mat22::new_zero(),
+            c: Vec2::new_zero(),
+            gamma: 0.0
+        }
+    }
+
+    // Moves the target, such as to follow the mouse each frame.
+    pub fn set_target(&mut self, target: Vec2) {
+        self.target = target;
+    }
+
+    // The pull towards the target on the body over the last step.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return self.impulse.multiply(inv_dt);
+    }
+
+    // The pull acts at the anchor, so there is no torque of its own.
+    pub fn reaction_torque(&self, _inv_dt: Scalar) -> Scalar {
+        return 0.0;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], _a: usize, b: usize, time_step: Scalar) {
+        // A body being dragged is never left asleep
+        bodies[b].set_awake(true);
+        self.r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+
+        // Soft constraint as in Box2D, the spring is folded into the effective mass
+        let h = time_step;
+        self.gamma = h * (self.damping + h * self.stiffness);
+        self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+        let beta = h * self.stiffness * self.gamma;
+
+        let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+        let r_b = self.r_b;
+        let k11 = m_b + i_b * r_b.y * r_b.y + self.gamma;
+        let k12 = -i_b * r_b.x * r_b.y;
+        let k22 = m_b + i_b * r_b.x * r_b.x + self.gamma;
+        self.mass = Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22));
+        self.c = (bodies[b].position + r_b - self.target).multiply(beta);
+
+        // Start from last step's impulse, scaled to the new step
+        let ratio = if self.time_step > 0.0 { time_step / self.time_step } else { 0.0 };
+        self.time_step = time_step;
+        self.impulse = self.impulse.multiply(ratio);
+        let impulse = self.impulse;
+        apply(&mut bodies[b], r_b, impulse);
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], _a: usize, b: usize) {
+        let cdot = bodies[b].velocity + Vec2::scalar_cross(bodies[b].angular_velocity, self.r_b);
+        let impulse = self.mass.solve(-(cdot + self.c + self.impulse.multiply(self.gamma)));
+
+        // No more than the max force over the step, however far away the target is
+        let old_impulse = self.impulse;
+        self.impulse += impulse;
+        let max_impulse = self.time_step * self.max_force;
+        if self.impulse.length_squared() > max_impulse * max_impulse {
+            self.impulse.normalize();
+            self.impulse = self.impulse.multiply(max_impulse);
+        }
+        let impulse = self.impulse - old_impulse;
+        apply(&mut bodies[b], self.r_b, impulse);
+    }
+
+    // The spring does all its work on the velocities.
+    pub fn solve_position(&mut self, _bodies: &mut [Body], _a: usize, _b: usize) {
+    }
+}
+
+fn apply(body: &mut Body, r: Vec2, impulse: Vec2) {
+    let (m, i) = (body.inv_mass(), body.inv_inertia());
+    body.velocity += impulse.multiply(m);
+    body.angular_velocity += i * r.cross(impulse);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+    use super::super::joint::Joint;
+
+    #[test]
+    fn drags_toward_the_target_within_its_max_force() {
+        let mut world = World::new(Vec2::new_zero());
+        let dragged = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody).mass(2.0).build());
+        let joint = MouseJoint::new(world.get_body(dragged).unwrap(), Vec2::new_zero(), 10.0);
+        let handle = world.add_joint(joint);
+        if let Some(&mut Joint::Mouse(ref mut mouse)) = world.get_joint_mut(handle) {
+            mouse.set_target(Vec2::new(5.0, 0.0));
+        }
+
+        // A force of 10 on a mass of 2 changes the speed by at most 5 per second
+        let max_speed_change = 5.0 / 60.0;
+        let mut last_speed = 0.0;
+        let mut last_x = 0.0;
+        for i in 0..240 {
+            world.step(1.0 / 60.0);
+            let body = world.get_body(dragged).unwrap();
+            assert!((body.velocity.length() - last_speed).abs() <= max_speed_change * 1.01 + 1.0e-4);
+            last_speed = body.velocity.length();
+            // It moves from the second step, once the first has given it a velocity
+            if i > 0 && i < 60 {
+                assert!(body.position.x > last_x);
+            }
+            last_x = body.position.x;
+        }
+        assert!((last_x - 5.0).abs() < 0.5);
+    }
+}
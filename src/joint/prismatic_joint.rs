@@ -0,0 +1,292 @@
+use super::super::math::{Vec2, Rot, Mat22, Scalar};
+use super::super::body::{Body, BodyHandle};
+
+const LINEAR_SLOP: Scalar = 0.005;
+
+// Lets body b slide along an axis fixed in body a, without turning relative to it.
+// Translation can be limited to a range and driven by a motor.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PrismaticJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // The anchor relative to each body's position, in the body's unrotated frame
+    pub local_anchor_a: Vec2,
+    pub local_anchor_b: Vec2,
+    // Unit slide direction in body a's unrotated frame
+    pub local_axis_a: Vec2,
+    // Angle of body b relative to body a that the joint holds
+    pub reference_angle: Scalar,
+    pub enable_limit: bool,
+    pub lower_translation: Scalar,
+    pub upper_translation: Scalar,
+    pub enable_motor: bool,
+    pub motor_speed: Scalar,
+    pub max_motor_force: Scalar,
+    pub collide_connected: bool,
+    // Solver state for the current step
+    impulse: Vec2,
+    motor_impulse: Scalar,
+    lower_impulse: Scalar,
+    upper_impulse: Scalar,
+    time_step: Scalar,
+    axis: Vec2,
+    perp: Vec2,
+    a1: Scalar,
+    a2: Scalar,
+    s1: Scalar,
+    s2: Scalar,
+    translation: Scalar,
+    axial_mass: Scalar,
+    k: Mat22
+}
+
+impl PrismaticJoint {
+    // Joins the bodies at a world space anchor, sliding along a world space axis, using
+    // their current positions.
+    pub fn new(body_a: &Body, body_b: &Body, anchor: Vec2, axis: Vec2) -> PrismaticJoint {
+        let mut axis = axis;
+        axis.normalize();
+        let rotation_a = Rot::new(body_a.angle);
+        return PrismaticJoint{
+            body_a: body_a.handle,
+            body_b: body_b.handle,
+            local_anchor_a: rotation_a.inv_rotate(anchor - body_a.position),
+            local_anchor_b: Rot::new(body_b.angle).inv_rotate(anchor - body_b.position),
+            local_axis_a: rotation_a.inv_rotate(axis),
+            reference_angle: body_b.angle - body_a.angle,
+            enable_limit: false,
+            lower_translation: 0.0,
+            upper_translation: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_force: 0.0,
+            collide_connected: false,
+            impulse: Vec2::new_zero(),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+            time_step: 0.0,
+            axis: Vec2::new_zero(),
+            perp: Vec2::new_zero(),
+            a1: 0.0,
+            a2: 0.0,
+            s1: 0.0,
+            s2: 0.0,
+            translation: 0.0,
+            axial_mass: 0.0,
+            k: Mat22::new_zero()
+        }
+    }
+
+    pub fn set_limits(&mut self, lower: Scalar, upper: Scalar) {
+        self.lower_translation = lower.min(upper);
+        self.upper_translation = lower.max(upper);
+        self.enable_limit = true;
+    }
+
+    pub fn set_motor(&mut self, speed: Scalar, max_force: Scalar) {
+        self.motor_speed = speed;
+        self.max_motor_force = max_force;
+        self.enable_motor = true;
+    }
+
+    // The force keeping body b on the axis, plus the motor and limits along it, over the
+    // last step.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+        return (self.perp.multiply(self.impulse.x) + self.axis.multiply(axial)).multiply(inv_dt);
+    }
+
+    // The torque keeping body b from turning against body a over the last step.
+    pub fn reaction_torque(&self, inv_dt: Scalar) -> Scalar {
+        return self.impulse.y * inv_dt;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+        let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+        let rotation_a = Rot::new(bodies[a].angle);
+        let r_a = rotation_a.rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let d = bodies[b].position + r_b - bodies[a].position - r_a;
+
+        self.time_step = time_step;
+        self.axis = rotation_a.rotate(self.local_axis_a);
+        self.a1 = (d + r_a).cross(self.axis);
+        self.a2 = r_b.cross(self.axis);
+        let axial_inv_mass = m_a + m_b + i_a * self.a1 * self.a1 + i_b * self.a2 * self.a2;
+        self.axial_mass = if axial_inv_mass > 0.0 { 1.0 / axial_inv_mass } else { 0.0 };
+
+        self.perp = Vec2::scalar_cross(1.0, self.axis);
+        self.s1 = (d + r_a).cross(self.perp);
+        self.s2 = r_b.cross(self.perp);
+        let k11 = m_a + m_b + i_a * self.s1 * self.s1 + i_b * self.s2 * self.s2;
+        let k12 = i_a * self.s1 + i_b * self.s2;
+        // Bodies that cannot rotate still need a solvable matrix
+        let k22 = if i_a + i_b == 0.0 { 1.0 } else { i_a + i_b };
+        self.k = Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22));
+
+        self.translation = self.axis.dot(d);
+        self.impulse = Vec2::new_zero();
+        self.motor_impulse = 0.0;
+        self.lower_impulse = 0.0;
+        self.upper_impulse = 0.0;
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        if self.enable_motor {
+            let cdot = self.axial_velocity(bodies, a, b);
+            let max_impulse = self.time_step * self.max_motor_force;
+            let old_impulse = self.motor_impulse;
+            self.motor_impulse = (old_impulse + self.axial_mass * (self.motor_speed - cdot)).max(-max_impulse).min(max_impulse);
+            let impulse = self.motor_impulse - old_impulse;
+            apply(bodies, a, b, self.axis.multiply(impulse), impulse * self.a1, impulse * self.a2);
+        }
+
+        if self.enable_limit && self.time_step > 0.0 {
+            let inv_time_step = 1.0 / self.time_step;
+
+            // Lower limit, pushing b forward along the axis
+            let c = self.translation - self.lower_translation;
+            let cdot = self.axial_velocity(bodies, a, b);
+            let old_impulse = self.lower_impulse;
+            self.lower_impulse = (old_impulse - self.axial_mass * (cdot + c.max(0.0) * inv_time_step)).max(0.0);
+            let impulse = self.lower_impulse - old_impulse;
+            apply(bodies, a, b, self.axis.multiply(impulse), impulse * self.a1, impulse * self.a2);
+
+            // Upper limit, pushing b back
+            let c = self.upper_translation - self.translation;
+            let cdot = -self.axial_velocity(bodies, a, b);
+            let old_impulse = self.upper_impulse;
+            self.upper_impulse = (old_impulse - self.axial_mass * (cdot + c.max(0.0) * inv_time_step)).max(0.0);
+            let impulse = self.upper_impulse - old_impulse;
+            apply(bodies, a, b, self.axis.multiply(-impulse), -impulse * self.a1, -impulse * self.a2);
+        }
+
+        // No sliding across the axis and no relative rotation
+        let cdot = Vec2::new(
+            self.perp.dot(bodies[b].velocity - bodies[a].velocity) + self.s2 * bodies[b].angular_velocity - self.s1 * bodies[a].angular_velocity,
+            bodies[b].angular_velocity - bodies[a].angular_velocity);
+        let impulse = self.k.solve(-cdot);
+        self.impulse += impulse;
+        apply(bodies, a, b, self.perp.multiply(impulse.x), impulse.x * self.s1 + impulse.y, impulse.x * self.s2 + impulse.y);
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+        let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+        let rotation_a = Rot::new(bodies[a].angle);
+        let r_a = rotation_a.rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let d = bodies[b].position + r_b - bodies[a].position - r_a;
+        let axis = rotation_a.rotate(self.local_axis_a);
+        let perp = Vec2::scalar_cross(1.0, axis);
+
+        let s1 = (d + r_a).cross(perp);
+        let s2 = r_b.cross(perp);
+        let c = Vec2::new(perp.dot(d), bodies[b].angle - bodies[a].angle - self.reference_angle);
+        let k11 = m_a + m_b + i_a * s1 * s1 + i_b * s2 * s2;
+        let k12 = i_a * s1 + i_b * s2;
+        let k22 = if i_a + i_b == 0.0 { 1.0 } else { i_a + i_b };
+        let impulse = Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22)).solve(-c);
+        apply_position(bodies, a, b, perp.multiply(impulse.x), impulse.x * s1 + impulse.y, impulse.x * s2 + impulse.y);
+
+        if !self.enable_limit {
+            return;
+        }
+        // Then pull the translation back inside the limits
+        let r_a = rotation_a.rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let d = bodies[b].position + r_b - bodies[a].position - r_a;
+        let translation = axis.dot(d);
+        let c = if translation < self.lower_translation - LINEAR_SLOP {
+            translation - self.lower_translation
+        } else if translation > self.upper_translation + LINEAR_SLOP {
+            translation - self.upper_translation
+        } else {
+            return;
+        };
+        let a1 = (d + r_a).cross(axis);
+        let a2 = r_b.cross(axis);
+        let axial_inv_mass = m_a + m_b + i_a * a1 * a1 + i_b * a2 * a2;
+        if axial_inv_mass > 0.0 {
+            let impulse = -c / axial_inv_mass;
+            apply_position(bodies, a, b, axis.multiply(impulse), impulse * a1, impulse * a2);
+        }
+    }
+
+    // How fast b is sliding along the axis relative to a.
+    fn axial_velocity(&self, bodies: &[Body], a: usize, b: usize) -> Scalar {
+        return self.axis.dot(bodies[b].velocity - bodies[a].velocity) +
+            self.a2 * bodies[b].angular_velocity - self.a1 * bodies[a].angular_velocity;
+    }
+}
+
+// Applies a linear impulse with matching angular impulses, pulling a back and pushing b
+// forward.
+fn apply(bodies: &mut [Body], a: usize, b: usize, impulse: Vec2, angular_a: Scalar, angular_b: Scalar) {
+    let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+    let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+    bodies[a].velocity -= impulse.multiply(m_a);
+    bodies[a].angular_velocity -= i_a * angular_a;
+    bodies[b].velocity += impulse.multiply(m_b);
+    bodies[b].angular_velocity += i_b * angular_b;
+}
+
+fn apply_position(bodies: &mut [Body], a: usize, b: usize, impulse: Vec2, angular_a: Scalar, angular_b: Scalar) {
+    let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+    let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+    bodies[a].position -= impulse.multiply(m_a);
+    bodies[a].angle -= i_a * angular_a;
+    bodies[b].position += impulse.multiply(m_b);
+    bodies[b].angle += i_b * angular_b;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    #[test]
+    fn sideways_impulses_do_not_move_a_vertical_slider() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let base = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let slider = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -2.0)).build());
+        let mut joint = PrismaticJoint::new(world.get_body(base).unwrap(), world.get_body(slider).unwrap(), Vec2::new(0.0, -2.0), Vec2::new(0.0, 1.0));
+        joint.set_limits(-3.0, 1.0);
+        world.add_joint(joint);
+
+        let mut drift: Scalar = 0.0;
+        for i in 0..300 {
+            if i % 20 == 0 {
+                world.get_body_mut(slider).unwrap().apply_impulse(Vec2::new(5.0, 0.0));
+            }
+            world.step(1.0 / 60.0);
+            let body = world.get_body(slider).unwrap();
+            drift = drift.max(body.position.x.abs() + body.angle.abs());
+        }
+        assert!(drift < 1.0e-4);
+        // Gravity slides it down to the upper limit, 1 below where it started
+        assert!((world.get_body(slider).unwrap().position.y + 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn motor_drives_the_slider_at_its_speed() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let base = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let slider = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody).build());
+        let mut joint = PrismaticJoint::new(world.get_body(base).unwrap(), world.get_body(slider).unwrap(), Vec2::new_zero(), Vec2::new(0.0, -1.0));
+        joint.set_motor(1.0, 100.0);
+        world.add_joint(joint);
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.get_body(slider).unwrap();
+        assert!((body.velocity.y + 1.0).abs() < 0.01);
+        assert!(body.position.y < -0.75);
+    }
+}
@@ -0,0 +1,197 @@
+use super::super::math::{Vec2, Rot, Scalar};
+use super::super::body::{Body, BodyHandle};
+
+// Rope segments shorter than this have no direction to pull in
+const MIN_ROPE_LENGTH: Scalar = 0.05;
+
+// Hangs two bodies from a rope running over two fixed ground anchors, so that
+// length_a + ratio * length_b stays the same. When one side goes down the other comes up,
+// with the ratio working like a block and tackle.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PulleyJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // The world space points the rope runs over
+    pub ground_anchor_a: Vec2,
+    pub ground_anchor_b: Vec2,
+    // The anchor relative to each body's position, in the body's unrotated frame
+    pub local_anchor_a: Vec2,
+    pub local_anchor_b: Vec2,
+    pub ratio: Scalar,
+    // length_a + ratio * length_b, taken from where the bodies are on the first step
+    // when left as None
+    pub rope_length: Option<Scalar>,
+    pub collide_connected: bool,
+    // Solver state, kept from one step to the next to warm start the solver
+    impulse: Scalar,
+    time_step: Scalar,
+    u_a: Vec2,
+    u_b: Vec2,
+    r_a: Vec2,
+    r_b: Vec2,
+    mass: Scalar
+}
+
+impl PulleyJoint {
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, ground_anchor_a: Vec2, ground_anchor_b: Vec2,
+               local_anchor_a: Vec2, local_anchor_b: Vec2, ratio: Scalar) -> PulleyJoint {
+        return PulleyJoint{
+            body_a: body_a,
+            body_b: body_b,
+            ground_anchor_a: ground_anchor_a,
+            ground_anchor_b: ground_anchor_b,
+            local_anchor_a: local_anchor_a,
+            local_anchor_b: local_anchor_b,
+            ratio: ratio,
+            rope_length: None,
+            collide_connected: true,
+            impulse: 0.0,
+            time_step: 0.0,
+            u_a: Vec2::new_zero(),
+            u_b: Vec2::new_zero(),
+            r_a: Vec2::new_zero(),
+            r_b: Vec2::new_zero(),
+            mass: 0.0
+        }
+    }
+
+    // The rope's pull on body b over the last step, towards its ground anchor.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return self.u_b.multiply(-self.ratio * self.impulse * inv_dt);
+    }
+
+    // A rope only pulls along itself, so there is never a torque.
+    pub fn reaction_torque(&self, _inv_dt: Scalar) -> Scalar {
+        return 0.0;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        let (r_a, r_b, u_a, u_b, length_a, length_b) = self.rope(&bodies[a], &bodies[b]);
+        self.r_a = r_a;
+        self.r_b = r_b;
+        self.u_a = u_a;
+        self.u_b = u_b;
+        if self.rope_length.is_none() {
+            self.rope_length = Some(length_a + self.ratio * length_b);
+        }
+        self.mass = rope_mass(&bodies[a], &bodies[b], r_a, r_b, u_a, u_b, self.ratio);
+
+        // Start from last step's impulse, scaled to the new step
+        let ratio = if self.time_step > 0.0 { time_step / self.time_step } else { 0.0 };
+        self.time_step = time_step;
+        self.impulse *= ratio;
+        let impulse = self.impulse;
+        self.apply(bodies, a, b, impulse);
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let velocity_a = bodies[a].velocity + Vec2::scalar_cross(bodies[a].angular_velocity, self.r_a);
+        let velocity_b = bodies[b].velocity + Vec2::scalar_cross(bodies[b].angular_velocity, self.r_b);
+        let cdot = -self.u_a.dot(velocity_a) - self.ratio * self.u_b.dot(velocity_b);
+
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        self.apply(bodies, a, b, impulse);
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let (r_a, r_b, u_a, u_b, length_a, length_b) = self.rope(&bodies[a], &bodies[b]);
+        let c = self.rope_length.unwrap_or(0.0) - length_a - self.ratio * length_b;
+        let impulse = -rope_mass(&bodies[a], &bodies[b], r_a, r_b, u_a, u_b, self.ratio) * c;
+
+        let impulse_a = u_a.multiply(-impulse);
+        let impulse_b = u_b.multiply(-self.ratio * impulse);
+        let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+        let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+        bodies[a].position += impulse_a.multiply(m_a);
+        bodies[a].angle += i_a * r_a.cross(impulse_a);
+        bodies[b].position += impulse_b.multiply(m_b);
+        bodies[b].angle += i_b * r_b.cross(impulse_b);
+    }
+
+    // The anchor offsets, the direction from each ground anchor to its body's anchor and
+    // the length of each side of the rope.
+    fn rope(&self, body_a: &Body, body_b: &Body) -> (Vec2, Vec2, Vec2, Vec2, Scalar, Scalar) {
+        let r_a = Rot::new(body_a.angle).rotate(self.local_anchor_a);
+        let r_b = Rot::new(body_b.angle).rotate(self.local_anchor_b);
+        let mut u_a = body_a.position + r_a - self.ground_anchor_a;
+        let mut u_b = body_b.position + r_b - self.ground_anchor_b;
+        let length_a = u_a.normalize();
+        let length_b = u_b.normalize();
+        if length_a < MIN_ROPE_LENGTH {
+            u_a = Vec2::new_zero();
+        }
+        if length_b < MIN_ROPE_LENGTH {
+            u_b = Vec2::new_zero();
+        }
+        return (r_a, r_b, u_a, u_b, length_a, length_b);
+    }
+
+    // Pulls both bodies towards their ground anchors, b by ratio times as much.
+    fn apply(&self, bodies: &mut [Body], a: usize, b: usize, impulse: Scalar) {
+        let impulse_a = self.u_a.multiply(-impulse);
+        let impulse_b = self.u_b.multiply(-self.ratio * impulse);
+        let (m_a, i_a) = (bodies[a].inv_mass(), bodies[a].inv_inertia());
+        let (m_b, i_b) = (bodies[b].inv_mass(), bodies[b].inv_inertia());
+        bodies[a].velocity += impulse_a.multiply(m_a);
+        bodies[a].angular_velocity += i_a * self.r_a.cross(impulse_a);
+        bodies[b].velocity += impulse_b.multiply(m_b);
+        bodies[b].angular_velocity += i_b * self.r_b.cross(impulse_b);
+    }
+}
+
+// The effective mass of the rope, with b's side geared by the ratio.
+fn rope_mass(body_a: &Body, body_b: &Body, r_a: Vec2, r_b: Vec2, u_a: Vec2, u_b: Vec2, ratio: Scalar) -> Scalar {
+    let ru_a = r_a.cross(u_a);
+    let ru_b = r_b.cross(u_b);
+    let inv_mass_a = body_a.inv_mass() + body_a.inv_inertia() * ru_a * ru_a;
+    let inv_mass_b = body_b.inv_mass() + body_b.inv_inertia() * ru_b * ru_b;
+    let inv_mass = inv_mass_a + ratio * ratio * inv_mass_b;
+    return if inv_mass > 0.0 { 1.0 / inv_mass } else { 0.0 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    // Two weights hung 5 below ground anchors at x = -2 and x = 2
+    fn hang(world: &mut World, mass_a: Scalar, mass_b: Scalar, ratio: Scalar) -> (BodyHandle, BodyHandle) {
+        let a = world.add_body(BodyDef::builder(Shape::box_shape(0.25, 0.25)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-2.0, 5.0)).mass(mass_a).build());
+        let b = world.add_body(BodyDef::builder(Shape::box_shape(0.25, 0.25)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(2.0, 5.0)).mass(mass_b).build());
+        world.add_joint(PulleyJoint::new(a, b, Vec2::new(-2.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new_zero(), Vec2::new_zero(), ratio));
+        return (a, b);
+    }
+
+    #[test]
+    fn lowering_one_side_raises_the_other_by_the_ratio() {
+        let mut world = World::new(Vec2::new_zero());
+        let (a, b) = hang(&mut world, 1.0, 1.0, 2.0);
+        for _ in 0..60 {
+            world.get_body_mut(b).unwrap().apply_force(Vec2::new(0.0, 5.0));
+            world.step(1.0 / 60.0);
+        }
+        let lowered = world.get_body(b).unwrap().position.y - 5.0;
+        let raised = 5.0 - world.get_body(a).unwrap().position.y;
+        assert!(lowered > 0.1);
+        assert!((raised - 2.0 * lowered).abs() < 0.01);
+    }
+
+    #[test]
+    fn heavier_side_falls() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let (a, b) = hang(&mut world, 1.0, 3.0, 1.0);
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let lowered = world.get_body(b).unwrap().position.y - 5.0;
+        let raised = 5.0 - world.get_body(a).unwrap().position.y;
+        assert!(lowered > 0.1);
+        assert!((raised - lowered).abs() < 0.01);
+    }
+}
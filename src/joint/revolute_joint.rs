@@ -0,0 +1,320 @@
+use super::super::math::{Vec2, Vec3, Rot, Mat22, Mat33, Scalar, consts};
+use super::super::body::{Body, BodyHandle};
+use super::joint::{apply_impulse, apply_position_impulse, apply_angular_impulse};
+
+const ANGULAR_SLOP: Scalar = 2.0 / 180.0 * consts::PI;
+// The most the position pass turns the bodies to fix a limit in one go
+const MAX_ANGULAR_CORRECTION: Scalar = 8.0 / 180.0 * consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum LimitState {
+     Inactive,
+     AtLower,
+     AtUpper,
+     // The limits are too close together to tell apart, so the angle is held
+     Equal,
+}
+
+// Pins two bodies together at a point they can both rotate around. The rotation of b
+// relative to a can be limited to a range and driven by a motor.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RevoluteJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // The anchor relative to each body's position, in the body's unrotated frame
+    pub local_anchor_a: Vec2,
+    pub local_anchor_b: Vec2,
+    // Angle of body b relative to body a when the joint was made, which the limits are
+    // measured from
+    pub reference_angle: Scalar,
+    pub enable_limit: bool,
+    pub lower_angle: Scalar,
+    pub upper_angle: Scalar,
+    pub enable_motor: bool,
+    // Target angular velocity of b relative to a, in radians per second
+    pub motor_speed: Scalar,
+    pub max_motor_torque: Scalar,
+    pub collide_connected: bool,
+    // Solver state, kept from one step to the next to warm start the solver
+    impulse: Vec2,
+    motor_impulse: Scalar,
+    limit_impulse: Scalar,
+    limit_state: LimitState,
+    time_step: Scalar,
+    r_a: Vec2,
+    r_b: Vec2,
+    mass: Mat22,
+    // The point and angle constraints together, for solving at a limit
+    limit_mass: Mat33,
+    axial_mass: Scalar
+}
+
+impl RevoluteJoint {
+    // Joins the bodies at a world space anchor, using their current positions.
+    pub fn new(body_a: &Body, body_b: &Body, anchor: Vec2) -> RevoluteJoint {
+        return RevoluteJoint{
+            body_a: body_a.handle,
+            body_b: body_b.handle,
+            local_anchor_a: Rot::new(body_a.angle).inv_rotate(anchor - body_a.position),
+            local_anchor_b: Rot::new(body_b.angle).inv_rotate(anchor - body_b.position),
+            reference_angle: body_b.angle - body_a.angle,
+            enable_limit: false,
+            lower_angle: 0.0,
+            upper_angle: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_torque: 0.0,
+            collide_connected: false,
+            impulse: Vec2::new_zero(),
+            motor_impulse: 0.0,
+            limit_impulse: 0.0,
+            limit_state: LimitState::Inactive,
+            time_step: 0.0,
+            r_a: Vec2::new_zero(),
+            r_b: Vec2::new_zero(),
+            mass: Mat22::new_zero(),
+            limit_mass: Mat33::new_zero(),
+            axial_mass: 0.0
+        }
+    }
+
+    // Angles of b relative to a in radians, measured from the reference angle.
+    pub fn set_limits(&mut self, lower: Scalar, upper: Scalar) {
+        self.lower_angle = lower.min(upper);
+        self.upper_angle = lower.max(upper);
+        self.enable_limit = true;
+    }
+
+    pub fn set_motor(&mut self, speed: Scalar, max_torque: Scalar) {
+        self.motor_speed = speed;
+        self.max_motor_torque = max_torque;
+        self.enable_motor = true;
+    }
+
+    // The force holding body b's anchor on body a's over the last step.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return self.impulse.multiply(inv_dt);
+    }
+
+    // The torque of the motor and the limit on body b over the last step.
+    pub fn reaction_torque(&self, inv_dt: Scalar) -> Scalar {
+        return (self.motor_impulse + self.limit_impulse) * inv_dt;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        self.r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        self.r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        self.mass = point_mass(&bodies[a], &bodies[b], self.r_a, self.r_b);
+        let (i_a, i_b) = (bodies[a].inv_inertia(), bodies[b].inv_inertia());
+        let angular_inv_mass = i_a + i_b;
+        self.axial_mass = if angular_inv_mass > 0.0 { 1.0 / angular_inv_mass } else { 0.0 };
+        let (r_a, r_b) = (self.r_a, self.r_b);
+        self.limit_mass = Mat33::new(
+            Vec3::new(self.mass.ex.x, self.mass.ex.y, -r_a.y * i_a - r_b.y * i_b),
+            Vec3::new(self.mass.ey.x, self.mass.ey.y, r_a.x * i_a + r_b.x * i_b),
+            Vec3::new(-r_a.y * i_a - r_b.y * i_b, r_a.x * i_a + r_b.x * i_b, angular_inv_mass));
+
+        let previous_state = self.limit_state;
+        self.limit_state = if !self.enable_limit || angular_inv_mass == 0.0 {
+            LimitState::Inactive
+        } else {
+            let angle = bodies[b].angle - bodies[a].angle - self.reference_angle;
+            if self.upper_angle - self.lower_angle < 2.0 * ANGULAR_SLOP {
+                LimitState::Equal
+            } else if angle <= self.lower_angle {
+                LimitState::AtLower
+            } else if angle >= self.upper_angle {
+                LimitState::AtUpper
+            } else {
+                LimitState::Inactive
+            }
+        };
+        if self.limit_state != previous_state || self.limit_state == LimitState::Inactive {
+            self.limit_impulse = 0.0;
+        }
+        if !self.enable_motor {
+            self.motor_impulse = 0.0;
+        }
+
+        // Start from last step's impulses, scaled to the new step. A pendulum bob that
+        // turns easily about its own center would need many iterations to stop at a limit
+        // from scratch.
+        let ratio = if self.time_step > 0.0 { time_step / self.time_step } else { 0.0 };
+        self.time_step = time_step;
+        self.impulse = self.impulse.multiply(ratio);
+        self.motor_impulse *= ratio;
+        self.limit_impulse *= ratio;
+        apply_impulse(bodies, a, b, self.r_a, self.r_b, self.impulse);
+        apply_angular_impulse(bodies, a, b, self.motor_impulse + self.limit_impulse);
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        if self.enable_motor && self.limit_state != LimitState::Equal {
+            // Torque towards the target speed, no more than the motor can give this step
+            let cdot = bodies[b].angular_velocity - bodies[a].angular_velocity - self.motor_speed;
+            let max_impulse = self.time_step * self.max_motor_torque;
+            let old_impulse = self.motor_impulse;
+            self.motor_impulse = (old_impulse - self.axial_mass * cdot).max(-max_impulse).min(max_impulse);
+            let impulse = self.motor_impulse - old_impulse;
+            apply_angular_impulse(bodies, a, b, impulse);
+        }
+
+        // The anchors must move together
+        let cdot = bodies[b].velocity + Vec2::scalar_cross(bodies[b].angular_velocity, self.r_b) -
+            bodies[a].velocity - Vec2::scalar_cross(bodies[a].angular_velocity, self.r_a);
+
+        if self.limit_state == LimitState::Inactive {
+            let impulse = self.mass.solve(-cdot);
+            self.impulse += impulse;
+            apply_impulse(bodies, a, b, self.r_a, self.r_b, impulse);
+            return;
+        }
+
+        // At a limit the anchors and the angle are solved together, as in Box2D. Solving
+        // them one after the other barely converges when b turns much more easily about its
+        // own center than about the anchor.
+        let angular_cdot = bodies[b].angular_velocity - bodies[a].angular_velocity;
+        let mut impulse = -self.limit_mass.solve33(Vec3::new(cdot.x, cdot.y, angular_cdot));
+        let new_limit_impulse = self.limit_impulse + impulse.z;
+        let pushes_through = match self.limit_state {
+            LimitState::AtLower => new_limit_impulse < 0.0,
+            LimitState::AtUpper => new_limit_impulse > 0.0,
+            _ => false
+        };
+        if pushes_through {
+            // The limit can only push away from itself, so release it and solve the anchors
+            let ez = Vec2::new(self.limit_mass.ez.x, self.limit_mass.ez.y);
+            let reduced = self.limit_mass.solve22(-cdot + ez.multiply(self.limit_impulse));
+            impulse = Vec3::new(reduced.x, reduced.y, -self.limit_impulse);
+            self.limit_impulse = 0.0;
+        } else {
+            self.limit_impulse = new_limit_impulse;
+        }
+        let point_impulse = Vec2::new(impulse.x, impulse.y);
+        self.impulse += point_impulse;
+        apply_impulse(bodies, a, b, self.r_a, self.r_b, point_impulse);
+        apply_angular_impulse(bodies, a, b, impulse.z);
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        if self.enable_limit && self.axial_mass > 0.0 {
+            let angle = bodies[b].angle - bodies[a].angle - self.reference_angle;
+            let c = if self.upper_angle - self.lower_angle < 2.0 * ANGULAR_SLOP {
+                (angle - self.lower_angle).clamp(-MAX_ANGULAR_CORRECTION, MAX_ANGULAR_CORRECTION)
+            } else if angle <= self.lower_angle {
+                (angle - self.lower_angle + ANGULAR_SLOP).clamp(-MAX_ANGULAR_CORRECTION, 0.0)
+            } else if angle >= self.upper_angle {
+                (angle - self.upper_angle - ANGULAR_SLOP).clamp(0.0, MAX_ANGULAR_CORRECTION)
+            } else {
+                0.0
+            };
+            let impulse = -self.axial_mass * c;
+            let (i_a, i_b) = (bodies[a].inv_inertia(), bodies[b].inv_inertia());
+            bodies[a].angle -= i_a * impulse;
+            bodies[b].angle += i_b * impulse;
+        }
+
+        let r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let c = bodies[b].position + r_b - bodies[a].position - r_a;
+        let impulse = -point_mass(&bodies[a], &bodies[b], r_a, r_b).solve(c);
+        apply_position_impulse(bodies, a, b, r_a, r_b, impulse);
+    }
+}
+
+// The effective mass matrix for keeping two anchor points together.
+pub fn point_mass(body_a: &Body, body_b: &Body, r_a: Vec2, r_b: Vec2) -> Mat22 {
+    let (m_a, m_b) = (body_a.inv_mass(), body_b.inv_mass());
+    let (i_a, i_b) = (body_a.inv_inertia(), body_b.inv_inertia());
+    let k11 = m_a + m_b + r_a.y * r_a.y * i_a + r_b.y * r_b.y * i_b;
+    let k12 = -r_a.y * r_a.x * i_a - r_b.y * r_b.x * i_b;
+    let k22 = m_a + m_b + r_a.x * r_a.x * i_a + r_b.x * r_b.x * i_b;
+    return Mat22::new(Vec2::new(k11, k12), Vec2::new(k12, k22));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    // A ball hung from a static pin at the origin by a joint at the pin.
+    fn pendulum(world: &mut World, ball_position: Vec2) -> (BodyHandle, RevoluteJoint) {
+        let pin = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(ball_position).build());
+        let joint = RevoluteJoint::new(world.get_body(pin).unwrap(), world.get_body(ball).unwrap(), Vec2::new_zero());
+        return (ball, joint);
+    }
+
+    #[test]
+    fn pendulum_stays_on_its_circle() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let (ball, joint) = pendulum(&mut world, Vec2::new(2.0, 0.0));
+        let handle = world.add_joint(joint);
+        let mut worst: Scalar = 0.0;
+        let mut leftmost: Scalar = 0.0;
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+            let position = world.get_body(ball).unwrap().position;
+            worst = worst.max((position.length() - 2.0).abs());
+            leftmost = leftmost.min(position.x);
+        }
+        assert!(worst < 0.01);
+        // It swings through to the other side
+        assert!(leftmost < -1.5);
+
+        assert!(world.remove_joint(handle));
+        assert!(!world.remove_joint(handle));
+    }
+
+    #[test]
+    fn motor_spins_a_wheel_up_to_its_speed() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let pin = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).build());
+        let wheel = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(2.0, 0.0)).build());
+        let mut joint = RevoluteJoint::new(world.get_body(pin).unwrap(), world.get_body(wheel).unwrap(), Vec2::new(2.0, 0.0));
+        joint.set_motor(5.0, 1.0);
+        world.add_joint(joint);
+        world.step(1.0 / 60.0);
+        let early = world.get_body(wheel).unwrap().angular_velocity;
+        // The torque is too weak to get there in one step
+        assert!(early > 0.0 && early < 5.0);
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(wheel).unwrap().angular_velocity - 5.0).abs() < 1.0e-3);
+    }
+
+    // The lowest, highest and final angles of a pendulum held between the given limits
+    fn swing_between(ball_position: Vec2, lower: Scalar, upper: Scalar) -> (Scalar, Scalar, Scalar) {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let (ball, mut joint) = pendulum(&mut world, ball_position);
+        joint.set_limits(lower, upper);
+        world.add_joint(joint);
+        let (mut lowest, mut highest): (Scalar, Scalar) = (0.0, 0.0);
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+            let angle = world.get_body(ball).unwrap().angle;
+            lowest = lowest.min(angle);
+            highest = highest.max(angle);
+        }
+        return (lowest, highest, world.get_body(ball).unwrap().angle);
+    }
+
+    #[test]
+    fn limits_stop_the_swing() {
+        // Released to the right, it swings clockwise into the upper limit and rests there
+        let (_, highest, last) = swing_between(Vec2::new(2.0, 0.0), -0.1, 0.8);
+        assert!(highest > 0.75 && highest < 0.86);
+        assert!((last - 0.8).abs() < 0.04);
+
+        let (lowest, _, last) = swing_between(Vec2::new(-2.0, 0.0), -0.5, 0.1);
+        assert!(lowest > -0.56);
+        assert!((last + 0.5).abs() < 0.04);
+    }
+}
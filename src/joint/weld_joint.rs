@@ -0,0 +1,211 @@
+use super::super::math::{Vec2, Vec3, Rot, Mat33, Scalar};
+use super::super::body::{Body, BodyHandle};
+use super::joint::{apply_impulse, apply_position_impulse, apply_angular_impulse};
+
+// Glues two bodies together at a point, holding both their offset and their relative
+// angle. With a stiffness the angle is held by a spring instead, so the pair can flex.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WeldJoint {
+    pub body_a: BodyHandle,
+    pub body_b: BodyHandle,
+    // The anchor relative to each body's position, in the body's unrotated frame
+    pub local_anchor_a: Vec2,
+    pub local_anchor_b: Vec2,
+    // Angle of body b relative to body a when the joint was made, which is held
+    pub reference_angle: Scalar,
+    // Angular spring stiffness in N*m/rad, zero for a rigid weld
+    pub stiffness: Scalar,
+    // Angular spring damping in N*m*s/rad, only used with a stiffness
+    pub damping: Scalar,
+    pub collide_connected: bool,
+    // Solver state, kept from one step to the next to warm start the solver
+    impulse: Vec3,
+    time_step: Scalar,
+    r_a: Vec2,
+    r_b: Vec2,
+    // The point and angle constraints together
+    mass: Mat33,
+    angular_mass: Scalar,
+    gamma: Scalar,
+    bias: Scalar
+}
+
+impl WeldJoint {
+    // Welds the bodies at a world space anchor, using their current positions.
+    pub fn new(body_a: &Body, body_b: &Body, anchor: Vec2) -> WeldJoint {
+        return WeldJoint{
+            body_a: body_a.handle,
+            body_b: body_b.handle,
+            local_anchor_a: Rot::new(body_a.angle).inv_rotate(anchor - body_a.position),
+            local_anchor_b: Rot::new(body_b.angle).inv_rotate(anchor - body_b.position),
+            reference_angle: body_b.angle - body_a.angle,
+            stiffness: 0.0,
+            damping: 0.0,
+            collide_connected: false,
+            impulse: Vec3::new_zero(),
+            time_step: 0.0,
+            r_a: Vec2::new_zero(),
+            r_b: Vec2::new_zero(),
+            mass: Mat33::new_zero(),
+            angular_mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0
+        }
+    }
+
+    // The force holding body b's anchor on body a's over the last step.
+    pub fn reaction_force(&self, inv_dt: Scalar) -> Vec2 {
+        return Vec2::new(self.impulse.x, self.impulse.y).multiply(inv_dt);
+    }
+
+    // The torque holding body b at the reference angle over the last step.
+    pub fn reaction_torque(&self, inv_dt: Scalar) -> Scalar {
+        return self.impulse.z * inv_dt;
+    }
+
+    pub fn prepare(&mut self, bodies: &mut [Body], a: usize, b: usize, time_step: Scalar) {
+        self.r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        self.r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        self.mass = weld_mass(&bodies[a], &bodies[b], self.r_a, self.r_b);
+        let angular_inv_mass = bodies[a].inv_inertia() + bodies[b].inv_inertia();
+
+        if self.stiffness > 0.0 {
+            // Soft angle as in Box2D, the spring is folded into the effective mass
+            let c = bodies[b].angle - bodies[a].angle - self.reference_angle;
+            let h = time_step;
+            self.gamma = h * (self.damping + h * self.stiffness);
+            self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+            self.bias = c * h * self.stiffness * self.gamma;
+            let soft_inv_mass = angular_inv_mass + self.gamma;
+            self.angular_mass = if soft_inv_mass != 0.0 { 1.0 / soft_inv_mass } else { 0.0 };
+        } else {
+            self.gamma = 0.0;
+            self.bias = 0.0;
+            self.angular_mass = if angular_inv_mass != 0.0 { 1.0 / angular_inv_mass } else { 0.0 };
+        }
+
+        // Start from last step's impulses, scaled to the new step
+        let ratio = if self.time_step > 0.0 { time_step / self.time_step } else { 0.0 };
+        self.time_step = time_step;
+        self.impulse = self.impulse.multiply(ratio);
+        apply_impulse(bodies, a, b, self.r_a, self.r_b, Vec2::new(self.impulse.x, self.impulse.y));
+        apply_angular_impulse(bodies, a, b, self.impulse.z);
+    }
+
+    pub fn solve_velocity(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let angular_cdot = bodies[b].angular_velocity - bodies[a].angular_velocity;
+
+        if self.stiffness > 0.0 {
+            // The spring first, then the anchors on their own
+            let impulse = -self.angular_mass * (angular_cdot + self.bias + self.gamma * self.impulse.z);
+            self.impulse.z += impulse;
+            apply_angular_impulse(bodies, a, b, impulse);
+
+            let cdot = point_velocity(bodies, a, b, self.r_a, self.r_b);
+            let impulse = self.mass.solve22(-cdot);
+            self.impulse = self.impulse + Vec3::new(impulse.x, impulse.y, 0.0);
+            apply_impulse(bodies, a, b, self.r_a, self.r_b, impulse);
+            return;
+        }
+
+        let cdot = point_velocity(bodies, a, b, self.r_a, self.r_b);
+        let impulse = -self.mass.solve33(Vec3::new(cdot.x, cdot.y, angular_cdot));
+        self.impulse = self.impulse + impulse;
+        apply_impulse(bodies, a, b, self.r_a, self.r_b, Vec2::new(impulse.x, impulse.y));
+        apply_angular_impulse(bodies, a, b, impulse.z);
+    }
+
+    pub fn solve_position(&mut self, bodies: &mut [Body], a: usize, b: usize) {
+        let r_a = Rot::new(bodies[a].angle).rotate(self.local_anchor_a);
+        let r_b = Rot::new(bodies[b].angle).rotate(self.local_anchor_b);
+        let mass = weld_mass(&bodies[a], &bodies[b], r_a, r_b);
+        let c = bodies[b].position + r_b - bodies[a].position - r_a;
+
+        // A spring is allowed to bend, and bodies that cannot turn only need their anchors fixed
+        if self.stiffness > 0.0 || mass.ez.z == 0.0 {
+            let impulse = -mass.solve22(c);
+            apply_position_impulse(bodies, a, b, r_a, r_b, impulse);
+            return;
+        }
+
+        let angular_c = bodies[b].angle - bodies[a].angle - self.reference_angle;
+        let impulse = -mass.solve33(Vec3::new(c.x, c.y, angular_c));
+        apply_position_impulse(bodies, a, b, r_a, r_b, Vec2::new(impulse.x, impulse.y));
+        let (i_a, i_b) = (bodies[a].inv_inertia(), bodies[b].inv_inertia());
+        bodies[a].angle -= i_a * impulse.z;
+        bodies[b].angle += i_b * impulse.z;
+    }
+}
+
+// How fast the anchors are moving apart.
+fn point_velocity(bodies: &[Body], a: usize, b: usize, r_a: Vec2, r_b: Vec2) -> Vec2 {
+    return bodies[b].velocity + Vec2::scalar_cross(bodies[b].angular_velocity, r_b) -
+        bodies[a].velocity - Vec2::scalar_cross(bodies[a].angular_velocity, r_a);
+}
+
+// The effective mass matrix for keeping the anchors together and the angle fixed.
+fn weld_mass(body_a: &Body, body_b: &Body, r_a: Vec2, r_b: Vec2) -> Mat33 {
+    let (m_a, m_b) = (body_a.inv_mass(), body_b.inv_mass());
+    let (i_a, i_b) = (body_a.inv_inertia(), body_b.inv_inertia());
+    let k12 = -r_a.y * r_a.x * i_a - r_b.y * r_b.x * i_b;
+    let k13 = -r_a.y * i_a - r_b.y * i_b;
+    let k23 = r_a.x * i_a + r_b.x * i_b;
+    return Mat33::new(
+        Vec3::new(m_a + m_b + r_a.y * r_a.y * i_a + r_b.y * r_b.y * i_b, k12, k13),
+        Vec3::new(k12, m_a + m_b + r_a.x * r_a.x * i_a + r_b.x * r_b.x * i_b, k23),
+        Vec3::new(k13, k23, i_a + i_b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::world::World;
+    use super::super::super::body::{BodyDef, BodyType};
+    use super::super::super::shape::shape::Shape;
+
+    fn unit_box(position: Vec2, body_type: BodyType) -> BodyDef {
+        return BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(body_type).position(position).build();
+    }
+
+    #[test]
+    fn impulse_on_one_welded_box_moves_both() {
+        let mut world = World::new(Vec2::new_zero());
+        let a = world.add_body(unit_box(Vec2::new_zero(), BodyType::DynamicBody));
+        let b = world.add_body(unit_box(Vec2::new(1.0, 0.0), BodyType::DynamicBody));
+        let joint = WeldJoint::new(world.get_body(a).unwrap(), world.get_body(b).unwrap(), Vec2::new(0.5, 0.0));
+        world.add_joint(joint);
+        world.get_body_mut(a).unwrap().apply_impulse(Vec2::new(0.0, 2.0));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let (body_a, body_b) = (world.get_body(a).unwrap(), world.get_body(b).unwrap());
+        assert!(((body_b.position - body_a.position).length() - 1.0).abs() < 0.01);
+        assert!((body_b.angle - body_a.angle).abs() < 0.01);
+        assert!(body_b.position.y > 0.5);
+    }
+
+    // Where a box welded beside a static one ends up under gravity
+    fn cantilever(stiffness: Scalar) -> (Vec2, Scalar) {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let wall = world.add_body(unit_box(Vec2::new_zero(), BodyType::StaticBody));
+        let beam = world.add_body(unit_box(Vec2::new(1.0, 0.0), BodyType::DynamicBody));
+        let mut joint = WeldJoint::new(world.get_body(wall).unwrap(), world.get_body(beam).unwrap(), Vec2::new(0.5, 0.0));
+        joint.stiffness = stiffness;
+        joint.damping = 2.0;
+        world.add_joint(joint);
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.get_body(beam).unwrap();
+        return (body.position, body.angle);
+    }
+
+    #[test]
+    fn rigid_weld_holds_level_and_soft_weld_bends() {
+        let (position, _) = cantilever(0.0);
+        assert!(position.y.abs() < 0.02);
+        let (_, angle) = cantilever(20.0);
+        assert!(angle > 0.1);
+    }
+}
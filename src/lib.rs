@@ -1,14 +1,34 @@
 #![crate_name = "box2d"]
 #![crate_type = "lib"]
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
 pub mod math;
 pub mod world;
 pub mod body;
+pub mod fixture;
 pub mod shape;
+pub mod aabb;
+pub mod ray_cast;
+pub mod distance;
+pub mod time_of_impact;
 pub mod collision;
 pub mod broad_phase;
 pub mod narrow_phase;
 pub mod collision_resolution;
+pub mod contact_listener;
 pub mod manifold;
+pub mod joint;
+pub mod profile;
+pub mod mixing_rule;
+pub mod debug_draw;
+pub mod buoyancy;
 mod default_broad_phase;
+mod brute_force_broad_phase;
+mod spatial_hash_broad_phase;
 mod default_narrow_phase;
+mod island;
@@ -1,16 +1,95 @@
-use super::body::Body;
-use super::math::Vec2;
+use super::math::{Vec2, Scalar};
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureType {
+     Vertex,
+     Face,
+}
+
+// The features of the two shapes that made a contact point, as in Box2D. The same
+// features touching on the next step give the same id, so the point can be recognised.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContactId {
+    pub index_a: u8,
+    pub index_b: u8,
+    pub type_a: FeatureType,
+    pub type_b: FeatureType
+}
+
+impl ContactId {
+    pub fn new(index_a: u8, type_a: FeatureType, index_b: u8, type_b: FeatureType) -> ContactId {
+        return ContactId{ index_a: index_a, index_b: index_b, type_a: type_a, type_b: type_b };
+    }
+
+    // The id for shapes with a single feature, like circles.
+    pub fn zero() -> ContactId {
+        return ContactId::new(0, FeatureType::Vertex, 0, FeatureType::Vertex);
+    }
+
+    // The same features with the shapes swapped.
+    pub fn swapped(self) -> ContactId {
+        return ContactId::new(self.index_b, self.type_b, self.index_a, self.type_a);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ContactPoint {
+    // World space point halfway between the two surfaces
+    pub position: Vec2,
+    pub penetration: Scalar,
+    // What the solver pushed the bodies apart and along the surface with, zero until
+    // World::step has solved the contact
+    pub normal_impulse: Scalar,
+    pub tangent_impulse: Scalar,
+    pub id: ContactId
+}
+
+impl ContactPoint {
+    pub fn new(position: Vec2, penetration: Scalar, id: ContactId) -> ContactPoint {
+        return ContactPoint{ position: position, penetration: penetration, normal_impulse: 0.0, tangent_impulse: 0.0, id: id };
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Manifold {
-    pub body_a: Body,
-    pub body_b: Body,
+    // Points from the second shape of the colliding pair towards the first
     pub normal: Vec2,
-    pub penetration: f32
+    // The deepest of the point penetrations
+    pub penetration: Scalar,
+    // One or two points
+    pub points: Vec<ContactPoint>
+}
+
+// A manifold between two bodies of the world, by index. The normal points from body_b
+// to body_a.
+#[derive(Clone, Debug)]
+pub struct Contact {
+    pub body_a: usize,
+    pub body_b: usize,
+    // Which of each body's shapes touched: None for the body's own shape, otherwise the
+    // index into its fixtures. A pair of bodies gets one contact per touching pair of shapes.
+    pub fixture_a: Option<usize>,
+    pub fixture_b: Option<usize>,
+    pub manifold: Manifold
 }
 
 impl Manifold {
-    pub fn new(body_a: Body, body_b: Body, normal: Vec2, penetration: f32) -> Manifold {
-        return Manifold{ body_a: body_a, body_b: body_b, normal: normal, penetration: penetration };
+    pub fn new(normal: Vec2, penetration: Scalar, points: Vec<ContactPoint>) -> Manifold {
+        return Manifold{ normal: normal, penetration: penetration, points: points };
+    }
+
+    // A single point contact for a circle as the first shape, in the middle of the overlap.
+    pub fn circle(center: Vec2, radius: Scalar, normal: Vec2, penetration: Scalar) -> Manifold {
+        let position = center - normal.multiply(radius - 0.5 * penetration);
+        return Manifold::new(normal, penetration, vec![ContactPoint::new(position, penetration, ContactId::zero())]);
+    }
+
+    // The same contact seen from the other shape.
+    pub fn flipped(mut self) -> Manifold {
+        self.normal = -self.normal;
+        for point in self.points.iter_mut() {
+            point.id = point.id.swapped();
+        }
+        return self;
     }
 }
@@ -1,13 +1,29 @@
-use std::ops::{Add, Sub};
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Neg, AddAssign, SubAssign};
 
-#[derive(Clone, Copy, Debug)]
+// The engine's floating point type. Building with the f64 feature trades speed for
+// precision, which keeps long or large simulations closer to reproducible.
+#[cfg(not(feature = "f64"))]
+pub type Scalar = f32;
+#[cfg(feature = "f64")]
+pub type Scalar = f64;
+
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
+
+pub const EPSILON: Scalar = Scalar::EPSILON;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Vec2 {
-	pub x: f32,
-	pub y: f32
+	pub x: Scalar,
+	pub y: Scalar
 }
 
 impl Vec2 {
-	pub fn new(x: f32, y: f32) -> Vec2 {
+	pub fn new(x: Scalar, y: Scalar) -> Vec2 {
 		return Vec2 {x: x, y: y}
 	}
 
@@ -15,24 +31,58 @@ impl Vec2 {
 		return Vec2 {x: 0.0, y: 0.0}
 	}
 
-	pub fn set(&mut self, x: f32, y: f32) {
+	pub fn set(&mut self, x: Scalar, y: Scalar) {
 		self.x = x;
 		self.y = y;
 	}
 
-	pub fn dot(&mut self, o: Vec2) -> f32 {
+	pub fn dot(self, o: Vec2) -> Scalar {
 		return self.x * o.x + self.y * o.y;
 	}
 
-	pub fn length(self) -> f32 {
-		return f32::sqrt(self.x * self.x + self.y * self.y);
+	// The 2D cross product, a scalar (the z component of the 3D cross product).
+	pub fn cross(self, o: Vec2) -> Scalar {
+		return self.x * o.y - self.y * o.x;
+	}
+
+	// Cross product of this vector with a scalar, v x s.
+	pub fn cross_scalar(self, s: Scalar) -> Vec2 {
+		return Vec2 {x: s * self.y, y: -s * self.x};
+	}
+
+	// Cross product of a scalar with a vector, s x v. Gives the velocity of a point at
+	// offset v on a body rotating with angular velocity s.
+	pub fn scalar_cross(s: Scalar, v: Vec2) -> Vec2 {
+		return Vec2 {x: -s * v.y, y: s * v.x};
+	}
+
+	pub fn length(self) -> Scalar {
+		return Scalar::sqrt(self.x * self.x + self.y * self.y);
+	}
+
+	pub fn length_squared(self) -> Scalar {
+		return self.x * self.x + self.y * self.y;
+	}
+
+	// Scales this vector to unit length and returns the length it had before. A vector
+	// too short to normalize is set to zero instead of becoming NaN.
+	pub fn normalize(&mut self) -> Scalar {
+		let length = self.length();
+		if length < EPSILON {
+			self.set(0.0, 0.0);
+			return 0.0;
+		}
+		let inv_length = 1.0 / length;
+		self.x *= inv_length;
+		self.y *= inv_length;
+		return length;
 	}
 
-	pub fn multiply(self, n: f32) -> Vec2 {
+	pub fn multiply(self, n: Scalar) -> Vec2 {
 		return Vec2 {x: self.x * n, y: self.y * n};
 	}
 
-	pub fn divide(self, n: f32) -> Vec2 {
+	pub fn divide(self, n: Scalar) -> Vec2 {
 		return Vec2 {x: self.x / n, y: self.y / n};
 	}
 
@@ -43,6 +93,19 @@ impl Vec2 {
 		}
 		return self.divide(length);
 	}
+
+	// Whether each component is within epsilon of the other vector's.
+	pub fn approx_eq(self, other: Vec2, epsilon: Scalar) -> bool {
+		return (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon;
+	}
+}
+
+// Prints (x, y) with 3 decimals unless the format asks for a precision, as in {:.1}.
+impl fmt::Display for Vec2 {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let precision = f.precision().unwrap_or(3);
+		return write!(f, "({:.*}, {:.*})", precision, self.x, precision, self.y);
+	}
 }
 
 impl Add for Vec2 {
@@ -60,3 +123,285 @@ impl Sub for Vec2 {
 		return Vec2::new(self.x - _rhs.x, self.y - _rhs.y);
 	}
 }
+
+impl Mul<Scalar> for Vec2 {
+	type Output = Vec2;
+
+	fn mul(self, _rhs: Scalar) -> Vec2 {
+		return self.multiply(_rhs);
+	}
+}
+
+impl Neg for Vec2 {
+	type Output = Vec2;
+
+	fn neg(self) -> Vec2 {
+		return Vec2::new(-self.x, -self.y);
+	}
+}
+
+impl AddAssign for Vec2 {
+	fn add_assign(&mut self, _rhs: Vec2) {
+		self.x += _rhs.x;
+		self.y += _rhs.y;
+	}
+}
+
+impl SubAssign for Vec2 {
+	fn sub_assign(&mut self, _rhs: Vec2) {
+		self.x -= _rhs.x;
+		self.y -= _rhs.y;
+	}
+}
+
+// A rotation stored as the sine and cosine of its angle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rot {
+	pub s: Scalar,
+	pub c: Scalar
+}
+
+impl Rot {
+	pub fn new(angle: Scalar) -> Rot {
+		return Rot {s: angle.sin(), c: angle.cos()};
+	}
+
+	pub fn identity() -> Rot {
+		return Rot {s: 0.0, c: 1.0};
+	}
+
+	pub fn angle(self) -> Scalar {
+		return self.s.atan2(self.c);
+	}
+
+	pub fn rotate(self, v: Vec2) -> Vec2 {
+		return Vec2::new(self.c * v.x - self.s * v.y, self.s * v.x + self.c * v.y);
+	}
+
+	pub fn inv_rotate(self, v: Vec2) -> Vec2 {
+		return Vec2::new(self.c * v.x + self.s * v.y, -self.s * v.x + self.c * v.y);
+	}
+}
+
+// A 2x2 matrix stored by columns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mat22 {
+	pub ex: Vec2,
+	pub ey: Vec2
+}
+
+impl Mat22 {
+	pub fn new(ex: Vec2, ey: Vec2) -> Mat22 {
+		return Mat22 {ex: ex, ey: ey};
+	}
+
+	pub fn new_zero() -> Mat22 {
+		return Mat22 {ex: Vec2::new_zero(), ey: Vec2::new_zero()};
+	}
+
+	// Solves A * x = b, returning zero if the matrix is singular.
+	pub fn solve(self, b: Vec2) -> Vec2 {
+		let mut det = self.ex.x * self.ey.y - self.ey.x * self.ex.y;
+		if det != 0.0 {
+			det = 1.0 / det;
+		}
+		return Vec2::new(det * (self.ey.y * b.x - self.ey.x * b.y), det * (self.ex.x * b.y - self.ex.y * b.x));
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Vec3 {
+	pub x: Scalar,
+	pub y: Scalar,
+	pub z: Scalar
+}
+
+impl Vec3 {
+	pub fn new(x: Scalar, y: Scalar, z: Scalar) -> Vec3 {
+		return Vec3 {x: x, y: y, z: z};
+	}
+
+	pub fn new_zero() -> Vec3 {
+		return Vec3 {x: 0.0, y: 0.0, z: 0.0};
+	}
+
+	pub fn dot(self, o: Vec3) -> Scalar {
+		return self.x * o.x + self.y * o.y + self.z * o.z;
+	}
+
+	pub fn cross(self, o: Vec3) -> Vec3 {
+		return Vec3::new(self.y * o.z - self.z * o.y, self.z * o.x - self.x * o.z, self.x * o.y - self.y * o.x);
+	}
+
+	pub fn multiply(self, n: Scalar) -> Vec3 {
+		return Vec3::new(self.x * n, self.y * n, self.z * n);
+	}
+}
+
+impl Add for Vec3 {
+	type Output = Vec3;
+
+	fn add(self, _rhs: Vec3) -> Vec3 {
+		return Vec3::new(self.x + _rhs.x, self.y + _rhs.y, self.z + _rhs.z);
+	}
+}
+
+impl Neg for Vec3 {
+	type Output = Vec3;
+
+	fn neg(self) -> Vec3 {
+		return Vec3::new(-self.x, -self.y, -self.z);
+	}
+}
+
+// A 3x3 matrix stored by columns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mat33 {
+	pub ex: Vec3,
+	pub ey: Vec3,
+	pub ez: Vec3
+}
+
+impl Mat33 {
+	pub fn new(ex: Vec3, ey: Vec3, ez: Vec3) -> Mat33 {
+		return Mat33 {ex: ex, ey: ey, ez: ez};
+	}
+
+	pub fn new_zero() -> Mat33 {
+		return Mat33 {ex: Vec3::new_zero(), ey: Vec3::new_zero(), ez: Vec3::new_zero()};
+	}
+
+	// Solves A * x = b, returning zero if the matrix is singular.
+	pub fn solve33(self, b: Vec3) -> Vec3 {
+		let mut det = self.ex.dot(self.ey.cross(self.ez));
+		if det != 0.0 {
+			det = 1.0 / det;
+		}
+		return Vec3::new(det * b.dot(self.ey.cross(self.ez)), det * self.ex.dot(b.cross(self.ez)), det * self.ex.dot(self.ey.cross(b)));
+	}
+
+	// Solves the upper left 2x2 block, A * x = b, returning zero if it is singular.
+	pub fn solve22(self, b: Vec2) -> Vec2 {
+		return Mat22::new(Vec2::new(self.ex.x, self.ex.y), Vec2::new(self.ey.x, self.ey.y)).solve(b);
+	}
+}
+
+// A translation and rotation, mapping points from a body's local space into world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transform {
+	pub position: Vec2,
+	pub rotation: Rot
+}
+
+impl Transform {
+	pub fn new(position: Vec2, angle: Scalar) -> Transform {
+		return Transform {position: position, rotation: Rot::new(angle)};
+	}
+
+	pub fn identity() -> Transform {
+		return Transform {position: Vec2::new_zero(), rotation: Rot::identity()};
+	}
+
+	pub fn apply(self, local: Vec2) -> Vec2 {
+		return self.rotation.rotate(local) + self.position;
+	}
+
+	pub fn apply_inverse(self, world: Vec2) -> Vec2 {
+		return self.rotation.inv_rotate(world - self.position);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dot_of_orthogonal_vectors_is_zero() {
+		assert_eq!(Vec2::new(2.0, 0.0).dot(Vec2::new(0.0, 3.0)), 0.0);
+		assert_eq!(Vec2::new(1.0, 1.0).dot(Vec2::new(-1.0, 1.0)), 0.0);
+		assert_eq!(Vec2::new(1.0, 2.0).dot(Vec2::new(3.0, 4.0)), 11.0);
+	}
+
+	#[test]
+	fn cross_is_positive_turning_from_x_to_y() {
+		let x = Vec2::new(1.0, 0.0);
+		let y = Vec2::new(0.0, 1.0);
+		assert_eq!(x.cross(y), 1.0);
+		assert_eq!(y.cross(x), -1.0);
+		assert_eq!(x.cross(x), 0.0);
+		assert_eq!(x.cross_scalar(2.0), Vec2::new(0.0, -2.0));
+		assert_eq!(Vec2::scalar_cross(2.0, x), Vec2::new(0.0, 2.0));
+	}
+
+	#[test]
+	fn operators_match_the_named_methods() {
+		let a = Vec2::new(3.0, -1.0);
+		let b = Vec2::new(0.5, 2.0);
+		assert_eq!(a - b, a + (-b));
+		assert_eq!(a * 2.0, Vec2::new(6.0, -2.0));
+		assert_eq!(a * 2.0, a.multiply(2.0));
+
+		let mut c = a;
+		c += b;
+		assert_eq!(c, a + b);
+		c -= b;
+		assert_eq!(c, a);
+	}
+
+	#[test]
+	fn normalize_returns_the_old_length() {
+		let mut v = Vec2::new(3.0, 4.0);
+		assert_eq!(v.length(), 5.0);
+		assert_eq!(v.length_squared(), 25.0);
+		assert_eq!(v.normalize(), 5.0);
+		assert!(v.approx_eq(Vec2::new(0.6, 0.8), 1.0e-6));
+	}
+
+	#[test]
+	fn normalizing_zero_gives_zero() {
+		let mut v = Vec2::new_zero();
+		assert_eq!(v.normalize(), 0.0);
+		assert_eq!(v, Vec2::new_zero());
+	}
+
+	#[test]
+	fn quarter_turn_maps_x_onto_y() {
+		let rotation = Rot::new(consts::FRAC_PI_2);
+		assert!(rotation.rotate(Vec2::new(1.0, 0.0)).approx_eq(Vec2::new(0.0, 1.0), 1.0e-6));
+		assert!(rotation.inv_rotate(Vec2::new(0.0, 1.0)).approx_eq(Vec2::new(1.0, 0.0), 1.0e-6));
+		assert!((rotation.angle() - consts::FRAC_PI_2).abs() < 1.0e-6);
+		assert_eq!(Rot::identity().rotate(Vec2::new(2.0, 3.0)), Vec2::new(2.0, 3.0));
+	}
+
+	#[test]
+	fn transform_round_trips_a_point() {
+		let transform = Transform::new(Vec2::new(1.0, 2.0), consts::FRAC_PI_2);
+		let world = transform.apply(Vec2::new(1.0, 0.0));
+		assert!(world.approx_eq(Vec2::new(1.0, 3.0), 1.0e-6));
+		assert!(transform.apply_inverse(world).approx_eq(Vec2::new(1.0, 0.0), 1.0e-6));
+	}
+
+	#[test]
+	fn mat22_solves_a_linear_system() {
+		let matrix = Mat22::new(Vec2::new(2.0, 0.0), Vec2::new(1.0, 4.0));
+		assert!(matrix.solve(Vec2::new(5.0, 8.0)).approx_eq(Vec2::new(1.5, 2.0), 1.0e-6));
+		assert_eq!(Mat22::new_zero().solve(Vec2::new(1.0, 1.0)), Vec2::new_zero());
+	}
+
+	#[test]
+	fn approx_eq_compares_each_component() {
+		assert!(Vec2::new(1.0, 2.0).approx_eq(Vec2::new(1.0 + 1.0e-7, 2.0), 1.0e-6));
+		assert!(!Vec2::new(1.0, 2.0).approx_eq(Vec2::new(1.0, 2.1), 1.0e-6));
+	}
+
+	#[test]
+	fn display_rounds_to_the_asked_precision() {
+		assert_eq!(format!("{}", Vec2::new(1.0, -2.5)), "(1.000, -2.500)");
+		assert_eq!(format!("{:.1}", Vec2::new(0.25, 3.0)), "(0.2, 3.0)");
+	}
+}
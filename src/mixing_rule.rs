@@ -0,0 +1,38 @@
+use super::math::Scalar;
+
+// How the friction or restitution of two touching bodies combine into the contact's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MixingRule {
+    Average,
+    GeometricMean,
+    Min,
+    Max,
+    Multiply,
+}
+
+impl MixingRule {
+    pub fn mix(self, a: Scalar, b: Scalar) -> Scalar {
+        match self {
+            MixingRule::Average => return 0.5 * (a + b),
+            MixingRule::GeometricMean => return (a * b).sqrt(),
+            MixingRule::Min => return a.min(b),
+            MixingRule::Max => return a.max(b),
+            MixingRule::Multiply => return a * b
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_rule_combines_the_two_values() {
+        assert_eq!(MixingRule::Average.mix(0.2, 0.8), 0.5);
+        assert_eq!(MixingRule::GeometricMean.mix(0.25, 1.0), 0.5);
+        assert_eq!(MixingRule::Min.mix(0.2, 0.8), 0.2);
+        assert_eq!(MixingRule::Max.mix(0.2, 0.8), 0.8);
+        assert_eq!(MixingRule::Multiply.mix(0.5, 0.5), 0.25);
+    }
+}
@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+// Where the last World::step spent its time, in microseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Profile {
+    // The whole step
+    pub step: f32,
+    // Moving the bodies and sweeping bullets
+    pub integrate: f32,
+    pub broad_phase: f32,
+    // Running the colliders and reporting contacts
+    pub narrow_phase: f32,
+    // Joints, contacts and sleep
+    pub solve: f32,
+    pub body_count: usize
+}
+
+impl Profile {
+    // Adds the times of another step, for a step split into sub steps.
+    pub fn add(&mut self, other: Profile) {
+        self.step += other.step;
+        self.integrate += other.integrate;
+        self.broad_phase += other.broad_phase;
+        self.narrow_phase += other.narrow_phase;
+        self.solve += other.solve;
+        self.body_count = other.body_count;
+    }
+}
+
+pub fn microseconds(duration: Duration) -> f32 {
+    return duration.as_secs() as f32 * 1.0e6 + duration.subsec_nanos() as f32 * 1.0e-3;
+}
+
+// Microseconds since `start`, resetting it to now.
+pub fn lap(start: &mut Instant) -> f32 {
+    let now = Instant::now();
+    let elapsed = microseconds(now.duration_since(*start));
+    *start = now;
+    return elapsed;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn microseconds_counts_seconds_and_nanoseconds() {
+        assert_eq!(microseconds(Duration::new(2, 500_000)), 2000500.0);
+    }
+}
@@ -0,0 +1,225 @@
+use super::math::{Vec2, EPSILON, Scalar};
+use super::shape::shape::{Shape, signed_area, edge_normal};
+use super::shape::ellipse::unit_circle_point;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RayCastHit {
+    pub body: usize,
+    pub point: Vec2,
+    pub normal: Vec2,
+    // The hit point is origin + direction * fraction
+    pub fraction: Scalar
+}
+
+impl Shape {
+    // Casts the ray against this shape alone with its body at shape_position, unturned, as
+    // ray_cast_shape does. The hit's body is left at 0 since there is no world to index.
+    pub fn ray_cast(&self, shape_position: Vec2, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<RayCastHit> {
+        return ray_cast_shape(self, shape_position, origin, direction, max_fraction).map(|(fraction, normal)| {
+            RayCastHit{ body: 0, point: origin + direction.multiply(fraction), normal: normal, fraction: fraction }
+        });
+    }
+}
+
+// Casts the ray origin + direction * t, 0 <= t <= max_fraction, against a shape on a body
+// at `position`. Returns the fraction and surface normal of the first hit.
+pub fn ray_cast_shape(shape: &Shape, position: Vec2, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<(Scalar, Vec2)> {
+    match *shape {
+        Shape::CircleShape{center, radius} => {
+            return ray_cast_circle(position + center, radius, origin, direction, max_fraction);
+        },
+        Shape::LineShape{point1, point2} => {
+            return ray_cast_segment(position + point1, position + point2, origin, direction, max_fraction);
+        },
+        Shape::ChainLineShape{ref points} => {
+            let mut best: Option<(Scalar, Vec2)> = None;
+            let mut max_fraction = max_fraction;
+            for i in 1..points.len() {
+                if let Some(hit) = ray_cast_segment(position + points[i - 1], position + points[i], origin, direction, max_fraction) {
+                    max_fraction = hit.0;
+                    best = Some(hit);
+                }
+            }
+            return best;
+        },
+        Shape::PolygonShape{ref points} => {
+            return ray_cast_polygon(points, position, origin, direction, max_fraction);
+        },
+        Shape::EdgeShape{vertex1, vertex2, one_sided} => {
+            // A one sided edge can only be hit from the front
+            if one_sided && edge_normal(vertex1, vertex2).dot(direction) > 0.0 {
+                return None;
+            }
+            return ray_cast_segment(position + vertex1, position + vertex2, origin, direction, max_fraction);
+        },
+        Shape::CapsuleShape{center1, center2, radius} => {
+            // The two end circles and the two flat sides
+            let offset = if (center2 - center1).length_squared() < EPSILON {
+                Vec2::new_zero()
+            } else {
+                edge_normal(center1, center2).multiply(radius)
+            };
+            let mut best: Option<(Scalar, Vec2)> = None;
+            let mut max_fraction = max_fraction;
+            let hits = [
+                ray_cast_circle(position + center1, radius, origin, direction, max_fraction),
+                ray_cast_circle(position + center2, radius, origin, direction, max_fraction),
+                ray_cast_segment(position + center1 + offset, position + center2 + offset, origin, direction, max_fraction),
+                ray_cast_segment(position + center1 - offset, position + center2 - offset, origin, direction, max_fraction)
+            ];
+            for hit in hits.iter() {
+                if let Some(hit) = *hit {
+                    if hit.0 <= max_fraction {
+                        max_fraction = hit.0;
+                        best = Some(hit);
+                    }
+                }
+            }
+            return best;
+        },
+        Shape::EllipseShape{center, radius_x, radius_y} => {
+            // Squashing space along the axes keeps the fraction, and the unit circle's
+            // normal is stretched back the other way
+            let local_origin = unit_circle_point(radius_x, radius_y, origin - position - center);
+            let local_direction = unit_circle_point(radius_x, radius_y, direction);
+            return ray_cast_circle(Vec2::new_zero(), 1.0, local_origin, local_direction, max_fraction).map(|(fraction, normal)| {
+                let mut normal = unit_circle_point(radius_x, radius_y, normal);
+                normal.normalize();
+                (fraction, normal)
+            });
+        },
+        Shape::HalfPlaneShape{normal, offset} => {
+            // Only a ray starting outside and heading in hits the surface
+            let separation = normal.dot(origin - position) - offset;
+            let denominator = normal.dot(direction);
+            if separation < 0.0 || denominator >= 0.0 {
+                return None;
+            }
+            let fraction = -separation / denominator;
+            if fraction > max_fraction {
+                return None;
+            }
+            return Some((fraction, normal));
+        }
+    }
+}
+
+fn ray_cast_circle(center: Vec2, radius: Scalar, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<(Scalar, Vec2)> {
+    // Solve |origin + direction * t - center| = radius for the smaller t
+    let s = origin - center;
+    let b = s.dot(s) - radius * radius;
+    let c = s.dot(direction);
+    let rr = direction.dot(direction);
+    let sigma = c * c - rr * b;
+    if sigma < 0.0 || rr < EPSILON {
+        return None;
+    }
+
+    let a = -(c + sigma.sqrt());
+    if a >= 0.0 && a <= max_fraction * rr {
+        let fraction = a / rr;
+        let mut normal = s + direction.multiply(fraction);
+        normal.normalize();
+        return Some((fraction, normal));
+    }
+    return None;
+}
+
+fn ray_cast_segment(v1: Vec2, v2: Vec2, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<(Scalar, Vec2)> {
+    let edge = v2 - v1;
+    let mut normal = Vec2::new(edge.y, -edge.x);
+    if normal.normalize() == 0.0 {
+        return None;
+    }
+
+    let denominator = normal.dot(direction);
+    if denominator == 0.0 {
+        return None;
+    }
+    let fraction = normal.dot(v1 - origin) / denominator;
+    if fraction < 0.0 || fraction > max_fraction {
+        return None;
+    }
+
+    // The hit has to land between the two end points
+    let point = origin + direction.multiply(fraction);
+    let s = (point - v1).dot(edge) / edge.dot(edge);
+    if !(0.0..=1.0).contains(&s) {
+        return None;
+    }
+
+    // Lines are two sided, so face the normal back along the ray
+    if denominator > 0.0 {
+        normal = -normal;
+    }
+    return Some((fraction, normal));
+}
+
+fn ray_cast_polygon(points: &[Vec2], position: Vec2, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<(Scalar, Vec2)> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    // Outward normals depend on the winding of the points
+    let winding = if signed_area(points) < 0.0 { -1.0 } else { 1.0 };
+
+    // Clip the ray against the inside of every edge
+    let mut lower = 0.0;
+    let mut upper = max_fraction;
+    let mut hit_normal: Option<Vec2> = None;
+    let local_origin = origin - position;
+    for i in 0..points.len() {
+        let edge = points[(i + 1) % points.len()] - points[i];
+        let mut normal = Vec2::new(edge.y, -edge.x).multiply(winding);
+        normal.normalize();
+
+        let numerator = normal.dot(points[i] - local_origin);
+        let denominator = normal.dot(direction);
+        if denominator == 0.0 {
+            if numerator < 0.0 {
+                return None;
+            }
+        } else if denominator < 0.0 && numerator < lower * denominator {
+            // Entering this edge's half plane
+            lower = numerator / denominator;
+            hit_normal = Some(normal);
+        } else if denominator > 0.0 && numerator < upper * denominator {
+            // Leaving this edge's half plane
+            upper = numerator / denominator;
+        }
+
+        if upper < lower {
+            return None;
+        }
+    }
+
+    match hit_normal {
+        Some(normal) => return Some((lower, normal)),
+        None => return None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_grazing_a_circle_touches_it_at_one_point() {
+        let circle = Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0};
+        let graze = circle.ray_cast(Vec2::new_zero(), Vec2::new(-3.0, 1.0), Vec2::new(6.0, 0.0), 1.0).unwrap();
+        assert!((graze.fraction - 0.5).abs() < 1.0e-2);
+        assert!(graze.normal.approx_eq(Vec2::new(0.0, 1.0), 1.0e-2));
+        assert!(circle.ray_cast(Vec2::new_zero(), Vec2::new(-3.0, 1.001), Vec2::new(6.0, 0.0), 1.0).is_none());
+    }
+
+    #[test]
+    fn ray_enters_a_polygon_through_its_facing_edge() {
+        let square = Shape::box_shape(1.0, 1.0);
+        let hit = square.ray_cast(Vec2::new(5.0, 0.0), Vec2::new(0.0, 0.5), Vec2::new(10.0, 0.0), 1.0).unwrap();
+        assert!((hit.fraction - 0.4).abs() < 1.0e-5);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        assert!(hit.point.approx_eq(Vec2::new(4.0, 0.5), 1.0e-5));
+        // Stopping short of the edge misses
+        assert!(square.ray_cast(Vec2::new(5.0, 0.0), Vec2::new(0.0, 0.5), Vec2::new(10.0, 0.0), 0.3).is_none());
+    }
+}
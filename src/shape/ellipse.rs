@@ -0,0 +1,101 @@
+use super::super::math::{Vec2, EPSILON, Scalar, consts};
+use super::shape::MassData;
+
+// How many points on the ellipse stand in for it against polygons and other flat shapes
+pub const ELLIPSE_SUPPORT_POINTS: usize = 16;
+
+// Refinement steps when finding the closest point, which settles well within the slop
+const CLOSEST_POINT_ITERATIONS: usize = 4;
+
+// Evenly spaced points around the ellipse, counter clockwise. Their polygon lies just inside
+// the ellipse and is what polygons, edges and chains collide against.
+pub fn ellipse_points(center: Vec2, radius_x: Scalar, radius_y: Scalar) -> Vec<Vec2> {
+    let step = 2.0 * consts::PI / ELLIPSE_SUPPORT_POINTS as Scalar;
+    return (0..ELLIPSE_SUPPORT_POINTS)
+        .map(|i| center + Vec2::new(radius_x * (step * i as Scalar).cos(), radius_y * (step * i as Scalar).sin()))
+        .collect();
+}
+
+// Where the point lands when the ellipse is squashed into a unit circle. Less than 1 in
+// length means the point is inside.
+pub fn unit_circle_point(radius_x: Scalar, radius_y: Scalar, local_point: Vec2) -> Vec2 {
+    return Vec2::new(local_point.x / radius_x, local_point.y / radius_y);
+}
+
+// The point on the ellipse centered on the origin nearest to `local_point`, from inside or
+// outside. Each step moves along the ellipse by how far the point is round from the local
+// center of curvature, working in the first quadrant and mirroring back.
+pub fn closest_point_on_ellipse(radius_x: Scalar, radius_y: Scalar, local_point: Vec2) -> Vec2 {
+    let px = local_point.x.abs();
+    let py = local_point.y.abs();
+    let (mut tx, mut ty) = (consts::FRAC_1_SQRT_2, consts::FRAC_1_SQRT_2);
+
+    for _ in 0..CLOSEST_POINT_ITERATIONS {
+        let x = radius_x * tx;
+        let y = radius_y * ty;
+        // The center of curvature for this point of the ellipse
+        let ex = (radius_x * radius_x - radius_y * radius_y) * tx * tx * tx / radius_x;
+        let ey = (radius_y * radius_y - radius_x * radius_x) * ty * ty * ty / radius_y;
+        let r = Vec2::new(x - ex, y - ey).length();
+        let q = Vec2::new(px - ex, py - ey);
+        let q_length = q.length();
+        if q_length < EPSILON {
+            break;
+        }
+
+        let mut t = Vec2::new(((q.x * r / q_length + ex) / radius_x).clamp(0.0, 1.0), ((q.y * r / q_length + ey) / radius_y).clamp(0.0, 1.0));
+        t.normalize();
+        tx = t.x;
+        ty = t.y;
+    }
+
+    return Vec2::new((radius_x * tx).copysign(local_point.x), (radius_y * ty).copysign(local_point.y));
+}
+
+// The outward unit normal at a point on the ellipse centered on the origin.
+pub fn ellipse_normal(radius_x: Scalar, radius_y: Scalar, point_on_ellipse: Vec2) -> Vec2 {
+    let mut normal = Vec2::new(point_on_ellipse.x / (radius_x * radius_x), point_on_ellipse.y / (radius_y * radius_y));
+    normal.normalize();
+    return normal;
+}
+
+// A circle stretched along both axes, so the area and inertia scale with the radii.
+pub fn ellipse_mass(center: Vec2, radius_x: Scalar, radius_y: Scalar, density: Scalar) -> MassData {
+    let mass = density * consts::PI * radius_x * radius_y;
+    // Inertia about the center, moved to the body origin
+    let inertia = mass * (0.25 * (radius_x * radius_x + radius_y * radius_y) + center.dot(center));
+    return MassData{ mass: mass, center: center, inertia: inertia };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::shape::Shape;
+
+    #[test]
+    fn contains_points_outside_the_inscribed_circle() {
+        let ellipse = Shape::EllipseShape{center: Vec2::new_zero(), radius_x: 2.0, radius_y: 0.5};
+        assert!(ellipse.contains_point(Vec2::new_zero(), Vec2::new(1.5, 0.1)));
+        assert!(!ellipse.contains_point(Vec2::new_zero(), Vec2::new(1.5, 0.45)));
+        let aabb = ellipse.compute_aabb(Vec2::new(1.0, 1.0));
+        assert!(aabb.min.approx_eq(Vec2::new(-1.0, 0.5), 1.0e-5));
+        assert!(aabb.max.approx_eq(Vec2::new(3.0, 1.5), 1.0e-5));
+    }
+
+    #[test]
+    fn closest_point_matches_a_search_around_the_ellipse() {
+        for &(radius_x, radius_y) in [(2.0, 0.5), (0.5, 2.0), (1.0, 1.0)].iter() {
+            for i in 0..16 {
+                for j in 0..16 {
+                    let point = Vec2::new(-4.0 + i as Scalar * 0.5, -3.99 + j as Scalar * 0.5);
+                    let found = (point - closest_point_on_ellipse(radius_x, radius_y, point)).length();
+                    let best = (0..4000).map(|k| {
+                        let angle = k as Scalar / 4000.0 * 2.0 * consts::PI;
+                        return (point - Vec2::new(radius_x * angle.cos(), radius_y * angle.sin())).length();
+                    }).fold(Scalar::MAX, |best: Scalar, distance| best.min(distance));
+                    assert!(found - best < 2.0e-3);
+                }
+            }
+        }
+    }
+}
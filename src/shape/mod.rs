@@ -1 +1,3 @@
 pub mod shape;
+pub mod polygon;
+pub mod ellipse;
@@ -0,0 +1,377 @@
+use std::fmt;
+use std::cmp::Ordering;
+use std::error::Error;
+use super::super::math::{Vec2, EPSILON, Scalar, consts};
+use super::shape::{Shape, signed_area};
+
+// The most vertices a polygon may have, as in Box2D
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+// Points closer together than this are welded into one
+const WELD_DISTANCE: Scalar = 0.0025;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolygonError {
+     // Fewer than 3 distinct points, or all of them on one line
+     TooFewVertices,
+     // The hull has more than MAX_POLYGON_VERTICES points
+     TooManyVertices(usize),
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolygonError::TooFewVertices => {
+                return write!(f, "a polygon needs at least 3 vertices that are not on one line");
+            },
+            PolygonError::TooManyVertices(count) => {
+                return write!(f, "a polygon can have at most {} vertices, not {}", MAX_POLYGON_VERTICES, count);
+            }
+        }
+    }
+}
+
+impl Error for PolygonError {}
+
+impl Shape {
+    // A PolygonShape from the convex hull of the points, wound counter clockwise. The
+    // colliders assume convex polygons, so use this for points that may not be.
+    pub fn polygon_from_points(points: &[Vec2]) -> Result<Shape, PolygonError> {
+        let mut welded: Vec<Vec2> = Vec::new();
+        for &point in points.iter() {
+            if !welded.iter().any(|&other| (point - other).length_squared() < WELD_DISTANCE * WELD_DISTANCE) {
+                welded.push(point);
+            }
+        }
+        if welded.len() < 3 {
+            return Err(PolygonError::TooFewVertices);
+        }
+
+        let hull = convex_hull(&welded);
+        if hull.len() < 3 || signed_area(&hull) <= 0.0 {
+            return Err(PolygonError::TooFewVertices);
+        }
+        if hull.len() > MAX_POLYGON_VERTICES {
+            return Err(PolygonError::TooManyVertices(hull.len()));
+        }
+        return Ok(Shape::PolygonShape{points: hull});
+    }
+
+    // An axis aligned box centered on the body origin.
+    pub fn box_shape(half_width: Scalar, half_height: Scalar) -> Shape {
+        return Shape::PolygonShape{points: vec![
+            Vec2::new(-half_width, -half_height),
+            Vec2::new(half_width, -half_height),
+            Vec2::new(half_width, half_height),
+            Vec2::new(-half_width, half_height)
+        ]};
+    }
+
+    // A polygon with equal sides and its vertices on a circle of the radius around the
+    // body origin, the first one on the x axis. Takes 3 to MAX_POLYGON_VERTICES sides and
+    // a positive radius.
+    pub fn regular_polygon(sides: u32, radius: Scalar) -> Result<Shape, PolygonError> {
+        if sides < 3 || radius.is_nan() || radius <= 0.0 {
+            return Err(PolygonError::TooFewVertices);
+        }
+        if sides as usize > MAX_POLYGON_VERTICES {
+            return Err(PolygonError::TooManyVertices(sides as usize));
+        }
+        return Ok(Shape::PolygonShape{points: tessellate_circle(Vec2::new_zero(), radius, sides as usize)});
+    }
+
+    // A polygon with every face pushed out by `skin`, keeping its corners sharp. Other
+    // shapes come back unchanged.
+    pub fn with_skin(&self, skin: Scalar) -> Shape {
+        let points = match *self {
+            Shape::PolygonShape{ref points} if points.len() >= 3 && skin > 0.0 => points,
+            _ => return self.clone()
+        };
+        let winding = if signed_area(points) < 0.0 { -1.0 } else { 1.0 };
+        let normals: Vec<Vec2> = (0..points.len()).map(|i| {
+            let edge = points[(i + 1) % points.len()] - points[i];
+            let mut normal = Vec2::new(edge.y, -edge.x).multiply(winding);
+            normal.normalize();
+            normal
+        }).collect();
+
+        // Each corner moves along the sum of its two face normals, far enough for both faces
+        let grown = (0..points.len()).map(|i| {
+            let previous = normals[(i + points.len() - 1) % points.len()];
+            let next = normals[i];
+            points[i] + (previous + next).multiply(skin / (1.0 + previous.dot(next)).max(EPSILON))
+        }).collect();
+        return Shape::PolygonShape{points: grown};
+    }
+}
+
+// Evenly spaced points on the circle, counter clockwise from the one straight along the x
+// axis from the center. The same segments always give the same points, so a renderer
+// drawing a circle and a PolygonShape standing in for it agree. A polygon can take up to
+// MAX_POLYGON_VERTICES segments.
+pub fn tessellate_circle(center: Vec2, radius: Scalar, segments: usize) -> Vec<Vec2> {
+    let step = 2.0 * consts::PI / segments as Scalar;
+    return (0..segments)
+        .map(|i| center + Vec2::new(radius * (step * i as Scalar).cos(), radius * (step * i as Scalar).sin()))
+        .collect();
+}
+
+// The convex hull of the points, counter clockwise and without collinear points, using
+// Andrew's monotone chain.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal).then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal)));
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let turns_left = |o: Vec2, a: Vec2, b: Vec2| (a - o).cross(b - o) > 0.0;
+    let mut hull: Vec<Vec2> = Vec::new();
+    // Lower half, left to right
+    for &point in sorted.iter() {
+        while hull.len() >= 2 && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], point) {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // Upper half, right to left
+    let lower_len = hull.len() + 1;
+    for &point in sorted.iter().rev().skip(1) {
+        while hull.len() >= lower_len && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], point) {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // The last point is the first one again
+    hull.pop();
+    return hull;
+}
+
+// Splits a simple polygon, convex or not and wound either way, into convex polygons
+// wound counter clockwise that together cover it, ready to attach as fixtures. The
+// polygon is cut into triangles by ear clipping and then neighbouring pieces are merged
+// back together for as long as the result stays convex and within MAX_POLYGON_VERTICES.
+// Points that cross over themselves give pieces that overlap or leave gaps.
+pub fn decompose_concave(points: &[Vec2]) -> Vec<Shape> {
+    let mut ring: Vec<Vec2> = points.to_vec();
+    if ring.len() < 3 {
+        return Vec::new();
+    }
+    if signed_area(&ring) < 0.0 {
+        ring.reverse();
+    }
+
+    let mut pieces: Vec<Vec<Vec2>> = Vec::new();
+    while ring.len() > 3 {
+        let len = ring.len();
+        let mut clipped = false;
+        for i in 0..len {
+            let (previous, current, next) = (ring[(i + len - 1) % len], ring[i], ring[(i + 1) % len]);
+            let turn = (current - previous).cross(next - current);
+            // A point in the middle of a straight edge only adds a sliver
+            if turn.abs() <= EPSILON {
+                ring.remove(i);
+                clipped = true;
+                break;
+            }
+            if turn < 0.0 {
+                continue;
+            }
+            let blocked = ring.iter().any(|&point| point != previous && point != current && point != next &&
+                in_triangle(point, previous, current, next));
+            if !blocked {
+                pieces.push(vec![previous, current, next]);
+                ring.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+        if !clipped {
+            // Only a polygon that crosses itself has no ear left
+            break;
+        }
+    }
+    if ring.len() == 3 && signed_area(&ring) > EPSILON {
+        pieces.push(ring);
+    }
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'pairs: for i in 0..pieces.len() {
+            for j in (i + 1)..pieces.len() {
+                if let Some(piece) = merge_pieces(&pieces[i], &pieces[j]) {
+                    pieces[i] = piece;
+                    pieces.remove(j);
+                    merged = true;
+                    break 'pairs;
+                }
+            }
+        }
+    }
+    return pieces.into_iter().map(|points| Shape::PolygonShape{points: points}).collect();
+}
+
+// Inside or on the edge of the counter clockwise triangle.
+fn in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    return (b - a).cross(point - a) >= 0.0 && (c - b).cross(point - b) >= 0.0 && (a - c).cross(point - c) >= 0.0;
+}
+
+// The two counter clockwise pieces joined along the edge they share, or None if they
+// share no edge or the joined polygon would be concave or have too many vertices.
+fn merge_pieces(first: &[Vec2], second: &[Vec2]) -> Option<Vec<Vec2>> {
+    let (n, m) = (first.len(), second.len());
+    for i in 0..n {
+        let (p, q) = (first[i], first[(i + 1) % n]);
+        // The neighbour runs along the shared edge the other way
+        let j = match (0..m).find(|&j| second[j] == q && second[(j + 1) % m] == p) {
+            Some(j) => j,
+            None => continue
+        };
+        let mut joined: Vec<Vec2> = (0..n).map(|k| first[(i + 1 + k) % n]).collect();
+        joined.extend((1..m - 1).map(|k| second[(j + 1 + k) % m]));
+
+        // Drop points left in the middle of a straight edge, then the rest have to turn left
+        let mut k = 0;
+        while k < joined.len() && joined.len() > 3 {
+            let len = joined.len();
+            let turn = (joined[k] - joined[(k + len - 1) % len]).cross(joined[(k + 1) % len] - joined[k]);
+            if turn.abs() <= EPSILON {
+                joined.remove(k);
+            } else if turn < 0.0 {
+                return None;
+            } else {
+                k += 1;
+            }
+        }
+        if joined.len() > MAX_POLYGON_VERTICES {
+            return None;
+        }
+        return Some(joined);
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular_polygon_takes_three_to_max_sides() {
+        assert_eq!(Shape::regular_polygon(2, 1.0).err(), Some(PolygonError::TooFewVertices));
+        assert_eq!(Shape::regular_polygon(9, 1.0).err(), Some(PolygonError::TooManyVertices(9)));
+        assert_eq!(Shape::regular_polygon(6, 0.0).err(), Some(PolygonError::TooFewVertices));
+        match Shape::regular_polygon(MAX_POLYGON_VERTICES as u32, 2.0) {
+            Ok(Shape::PolygonShape{points}) => {
+                assert_eq!(points.len(), MAX_POLYGON_VERTICES);
+                assert!(points[0].approx_eq(Vec2::new(2.0, 0.0), 1.0e-5));
+                assert!(signed_area(&points) > 0.0);
+            },
+            _ => panic!("expected an octagon")
+        }
+    }
+
+    #[test]
+    fn from_points_takes_the_counter_clockwise_hull() {
+        // Clockwise corners, with a point inside, one on an edge and one nearly on a corner
+        let points = vec![Vec2::new(-0.75, -0.75), Vec2::new(-0.75, 0.75), Vec2::new(0.75, 0.75), Vec2::new(0.75, -0.75),
+            Vec2::new(0.0, 0.0), Vec2::new(0.75, 0.0), Vec2::new(0.7501, 0.75)];
+        match Shape::polygon_from_points(&points) {
+            Ok(Shape::PolygonShape{points}) => {
+                assert_eq!(points.len(), 4);
+                assert!(signed_area(&points) > 0.0);
+            },
+            _ => panic!("expected a square")
+        }
+    }
+
+    #[test]
+    fn from_points_rejects_degenerate_and_oversized_polygons() {
+        let segment = vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)];
+        assert_eq!(Shape::polygon_from_points(&segment).err(), Some(PolygonError::TooFewVertices));
+        let collinear = vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)];
+        assert_eq!(Shape::polygon_from_points(&collinear).err(), Some(PolygonError::TooFewVertices));
+        let circle: Vec<Vec2> = (0..10).map(|i| {
+            let angle = i as Scalar * 0.628;
+            return Vec2::new(angle.cos(), angle.sin());
+        }).collect();
+        assert_eq!(Shape::polygon_from_points(&circle).err(), Some(PolygonError::TooManyVertices(10)));
+    }
+
+    // The total area of the pieces, checking each is a convex polygon small enough to use
+    fn pieces_area(pieces: &[Shape]) -> Scalar {
+        return pieces.iter().map(|piece| match *piece {
+            Shape::PolygonShape{ref points} => {
+                assert!(points.len() <= MAX_POLYGON_VERTICES);
+                let n = points.len();
+                for i in 0..n {
+                    assert!((points[(i + 1) % n] - points[i]).cross(points[(i + 2) % n] - points[(i + 1) % n]) > 0.0);
+                }
+                return 0.5 * signed_area(points);
+            },
+            _ => panic!("expected polygons")
+        }).sum();
+    }
+
+    #[test]
+    fn concave_outlines_split_into_convex_pieces_of_the_same_area() {
+        let l_shape = vec![Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 1.0), Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0), Vec2::new(0.0, 3.0)];
+        assert!((pieces_area(&decompose_concave(&l_shape)) - 4.0).abs() < 1.0e-5);
+        let mut clockwise = l_shape.clone();
+        clockwise.reverse();
+        assert!((pieces_area(&decompose_concave(&clockwise)) - 4.0).abs() < 1.0e-5);
+
+        let u_shape = vec![Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(3.0, 3.0), Vec2::new(2.0, 3.0),
+            Vec2::new(2.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 3.0), Vec2::new(0.0, 3.0)];
+        assert!((pieces_area(&decompose_concave(&u_shape)) - 7.0).abs() < 1.0e-5);
+
+        let star: Vec<Vec2> = (0..10).map(|i| {
+            let radius = if i % 2 == 0 { 2.0 } else { 0.8 };
+            let angle = i as Scalar * consts::PI / 5.0;
+            return Vec2::new(radius * angle.cos(), radius * angle.sin());
+        }).collect();
+        assert!((pieces_area(&decompose_concave(&star)) - 0.5 * signed_area(&star)).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn convex_outline_stays_in_one_piece() {
+        let square = [Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, 1.0)];
+        assert_eq!(decompose_concave(&square).len(), 1);
+    }
+
+    #[test]
+    fn tessellated_circles_are_counter_clockwise_and_repeatable() {
+        let center = Vec2::new(2.0, -1.0);
+        let points = tessellate_circle(center, 1.5, 8);
+        assert_eq!(points.len(), 8);
+        for &point in points.iter() {
+            assert!(((point - center).length() - 1.5).abs() < 1.0e-5);
+        }
+        assert!(signed_area(&points) > 0.0);
+        assert_eq!(tessellate_circle(center, 1.5, 8), points);
+
+        let octagon = Shape::PolygonShape{points: tessellate_circle(Vec2::new_zero(), 1.0, 8)};
+        assert!((octagon.area() - 2.0 * (2.0 as Scalar).sqrt()).abs() < 1.0e-5);
+        match Shape::regular_polygon(6, 1.0) {
+            Ok(Shape::PolygonShape{points}) => assert_eq!(points, tessellate_circle(Vec2::new_zero(), 1.0, 6)),
+            _ => panic!("expected a hexagon")
+        }
+    }
+
+    #[test]
+    fn box_shape_has_the_four_corners() {
+        match Shape::box_shape(0.75, 0.75) {
+            Shape::PolygonShape{points} => {
+                assert_eq!(points.len(), 4);
+                // The corners may come in any order as long as they wind counter clockwise
+                for &corner in [Vec2::new(-0.75, -0.75), Vec2::new(-0.75, 0.75),
+                        Vec2::new(0.75, 0.75), Vec2::new(0.75, -0.75)].iter() {
+                    assert!(points.contains(&corner));
+                }
+                assert!(signed_area(&points) > 0.0);
+            },
+            _ => panic!("expected a polygon")
+        }
+    }
+}
@@ -1,9 +1,541 @@
-use super::super::math::Vec2;
+use super::super::math::{Vec2, Transform, EPSILON, Scalar, consts};
+use super::super::aabb::AABB;
+use super::ellipse::{ellipse_points, ellipse_mass, unit_circle_point};
+
+#[derive(Clone, Copy, Debug)]
+pub struct MassData {
+    pub mass: Scalar,
+    // Centroid of the shape in body local coordinates
+    pub center: Vec2,
+    // Rotational inertia about the body origin
+    pub inertia: Scalar
+}
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Shape {
-     CircleShape{center: Vec2, radius: f32},
+     CircleShape{center: Vec2, radius: Scalar},
      LineShape{point1: Vec2, point2: Vec2},
      ChainLineShape{points: Vec<Vec2>},
      PolygonShape{points: Vec<Vec2>},
+     // A single segment for level geometry. A one sided edge only collides with shapes in
+     // front of it, on the side edge_normal points to.
+     EdgeShape{vertex1: Vec2, vertex2: Vec2, one_sided: bool},
+     // Every point within radius of the segment from center1 to center2
+     CapsuleShape{center1: Vec2, center2: Vec2, radius: Scalar},
+     // An axis aligned ellipse, a circle stretched to radius_x and radius_y
+     EllipseShape{center: Vec2, radius_x: Scalar, radius_y: Scalar},
+     // Everything on and behind the line of points p with p.dot(normal) == offset, for an
+     // endless floor or wall. The normal is a unit vector pointing out of the solid side.
+     // It has no mass, so it only makes sense on static bodies.
+     HalfPlaneShape{normal: Vec2, offset: Scalar},
+}
+
+impl Shape {
+    // The world space bounding box of this shape when its body is at `position`.
+    pub fn compute_aabb(&self, position: Vec2) -> AABB {
+        match *self {
+            Shape::CircleShape{center, radius} => {
+                let r = Vec2::new(radius, radius);
+                return AABB::new(position + center - r, position + center + r);
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                let r = Vec2::new(radius, radius);
+                let min = Vec2::new(center1.x.min(center2.x), center1.y.min(center2.y));
+                let max = Vec2::new(center1.x.max(center2.x), center1.y.max(center2.y));
+                return AABB::new(position + min - r, position + max + r);
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                let r = Vec2::new(radius_x, radius_y);
+                return AABB::new(position + center - r, position + center + r);
+            },
+            Shape::LineShape{point1, point2} | Shape::EdgeShape{vertex1: point1, vertex2: point2, ..} => {
+                return AABB::from_points(&[point1, point2], position);
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                // Endless, except on the open side of an axis aligned surface
+                let surface = position + normal.multiply(offset);
+                let mut min = Vec2::new(-Scalar::INFINITY, -Scalar::INFINITY);
+                let mut max = Vec2::new(Scalar::INFINITY, Scalar::INFINITY);
+                if normal.x == 0.0 {
+                    if normal.y > 0.0 { max.y = surface.y; } else { min.y = surface.y; }
+                }
+                if normal.y == 0.0 {
+                    if normal.x > 0.0 { max.x = surface.x; } else { min.x = surface.x; }
+                }
+                return AABB::new(min, max);
+            },
+            Shape::ChainLineShape{ref points} | Shape::PolygonShape{ref points} => {
+                if points.is_empty() {
+                    return AABB::new(position, position);
+                }
+                return AABB::from_points(points, position);
+            }
+        }
+    }
+
+    // The bounds of the shape moved and rotated by the transform.
+    pub fn compute_transformed_aabb(&self, transform: Transform) -> AABB {
+        if transform.rotation.s == 0.0 && transform.rotation.c == 1.0 {
+            return self.compute_aabb(transform.position);
+        }
+        // The support points of a turned ellipse would cut its corners off the bounds
+        if let Shape::EllipseShape{center, radius_x, radius_y} = *self {
+            let (c, s) = (transform.rotation.c, transform.rotation.s);
+            let r = Vec2::new((radius_x * radius_x * c * c + radius_y * radius_y * s * s).sqrt(), (radius_x * radius_x * s * s + radius_y * radius_y * c * c).sqrt());
+            let world_center = transform.apply(center);
+            return AABB::new(world_center - r, world_center + r);
+        }
+        return self.transformed(transform).compute_aabb(Vec2::new_zero());
+    }
+
+    // A copy of the shape with every point mapped through the transform, which turns a
+    // body's local shape into its world space shape.
+    pub fn transformed(&self, transform: Transform) -> Shape {
+        let map_points = |points: &Vec<Vec2>| points.iter().map(|p| transform.apply(*p)).collect();
+        match *self {
+            Shape::CircleShape{center, radius} => {
+                return Shape::CircleShape{center: transform.apply(center), radius: radius};
+            },
+            Shape::LineShape{point1, point2} => {
+                return Shape::LineShape{point1: transform.apply(point1), point2: transform.apply(point2)};
+            },
+            Shape::ChainLineShape{ref points} => {
+                return Shape::ChainLineShape{points: map_points(points)};
+            },
+            Shape::PolygonShape{ref points} => {
+                return Shape::PolygonShape{points: map_points(points)};
+            },
+            Shape::EdgeShape{vertex1, vertex2, one_sided} => {
+                return Shape::EdgeShape{vertex1: transform.apply(vertex1), vertex2: transform.apply(vertex2), one_sided: one_sided};
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                return Shape::CapsuleShape{center1: transform.apply(center1), center2: transform.apply(center2), radius: radius};
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                // A turned ellipse is no longer axis aligned, so it becomes the polygon
+                // through its support points
+                return Shape::PolygonShape{points: map_points(&ellipse_points(center, radius_x, radius_y))};
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                let normal = transform.rotation.rotate(normal);
+                return Shape::HalfPlaneShape{normal: normal, offset: offset + normal.dot(transform.position)};
+            }
+        }
+    }
+
+    // A copy of the shape with every point moved by the offset.
+    pub fn translated(&self, offset: Vec2) -> Shape {
+        match *self {
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                return Shape::EllipseShape{center: center + offset, radius_x: radius_x, radius_y: radius_y};
+            },
+            _ => return self.transformed(Transform::new(offset, 0.0))
+        }
+    }
+
+    // A copy of the shape with every point's x and y multiplied by the factor's, about the
+    // body origin. Neither factor may be zero. A circle scaled unevenly becomes an ellipse,
+    // and a capsule's radius grows by the smaller factor so it stays within the stretched
+    // shape. Mirroring keeps polygons counter clockwise and one sided edges facing out.
+    pub fn scaled(&self, factor: Vec2) -> Shape {
+        let scale = |p: Vec2| Vec2::new(p.x * factor.x, p.y * factor.y);
+        let (scale_x, scale_y) = (factor.x.abs(), factor.y.abs());
+        let mirrored = factor.x * factor.y < 0.0;
+        match *self {
+            Shape::CircleShape{center, radius} => {
+                if scale_x == scale_y {
+                    return Shape::CircleShape{center: scale(center), radius: radius * scale_x};
+                }
+                return Shape::EllipseShape{center: scale(center), radius_x: radius * scale_x, radius_y: radius * scale_y};
+            },
+            Shape::LineShape{point1, point2} => {
+                return Shape::LineShape{point1: scale(point1), point2: scale(point2)};
+            },
+            Shape::ChainLineShape{ref points} => {
+                return Shape::ChainLineShape{points: points.iter().map(|p| scale(*p)).collect()};
+            },
+            Shape::PolygonShape{ref points} => {
+                let mut points: Vec<Vec2> = points.iter().map(|p| scale(*p)).collect();
+                if mirrored {
+                    points.reverse();
+                }
+                return Shape::PolygonShape{points: points};
+            },
+            Shape::EdgeShape{vertex1, vertex2, one_sided} => {
+                if mirrored {
+                    return Shape::EdgeShape{vertex1: scale(vertex2), vertex2: scale(vertex1), one_sided: one_sided};
+                }
+                return Shape::EdgeShape{vertex1: scale(vertex1), vertex2: scale(vertex2), one_sided: one_sided};
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                return Shape::CapsuleShape{center1: scale(center1), center2: scale(center2), radius: radius * scale_x.min(scale_y)};
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                return Shape::EllipseShape{center: scale(center), radius_x: radius_x * scale_x, radius_y: radius_y * scale_y};
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                // p.dot(normal) == offset becomes p.dot(normal / factor) == offset
+                let mut normal = Vec2::new(normal.x / factor.x, normal.y / factor.y);
+                let length = normal.normalize();
+                return Shape::HalfPlaneShape{normal: normal, offset: offset / length};
+            }
+        }
+    }
+
+    // The center of the shape in body local coordinates. Polygons use their area weighted
+    // centroid, which differs from the average of the vertices unless they are symmetric,
+    // and chains the middle of their length.
+    pub fn centroid(&self) -> Vec2 {
+        match *self {
+            Shape::CircleShape{center, ..} | Shape::EllipseShape{center, ..} => {
+                return center;
+            },
+            Shape::LineShape{point1, point2} | Shape::EdgeShape{vertex1: point1, vertex2: point2, ..} |
+            Shape::CapsuleShape{center1: point1, center2: point2, ..} => {
+                return (point1 + point2).multiply(0.5);
+            },
+            Shape::PolygonShape{ref points} if points.len() >= 3 && signed_area(points) != 0.0 => {
+                return polygon_mass(points, 1.0).center;
+            },
+            Shape::PolygonShape{ref points} | Shape::ChainLineShape{ref points} => {
+                if points.is_empty() {
+                    return Vec2::new_zero();
+                }
+                let mut length = 0.0;
+                let mut sum = Vec2::new_zero();
+                for i in 1..points.len() {
+                    let segment_length = (points[i] - points[i - 1]).length();
+                    length += segment_length;
+                    sum += (points[i] + points[i - 1]).multiply(0.5 * segment_length);
+                }
+                if length == 0.0 {
+                    return points[0];
+                }
+                return sum.divide(length);
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                // The point of the surface nearest the body origin
+                return normal.multiply(offset);
+            }
+        }
+    }
+
+    // The area the shape covers, positive whichever way a polygon winds. Lines, edges and
+    // chains cover none and a half plane covers an endless area.
+    pub fn area(&self) -> Scalar {
+        match *self {
+            Shape::CircleShape{radius, ..} => {
+                return consts::PI * radius * radius;
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                return consts::PI * radius * radius + 2.0 * radius * (center2 - center1).length();
+            },
+            Shape::EllipseShape{radius_x, radius_y, ..} => {
+                return consts::PI * radius_x * radius_y;
+            },
+            Shape::PolygonShape{ref points} => {
+                return 0.5 * signed_area(points).abs();
+            },
+            Shape::LineShape{..} | Shape::EdgeShape{..} | Shape::ChainLineShape{..} => {
+                return 0.0;
+            },
+            Shape::HalfPlaneShape{..} => {
+                return Scalar::INFINITY;
+            }
+        }
+    }
+
+    // Lines, edges and chains have no area and half planes are only for static bodies, so
+    // none of them have mass.
+    pub fn compute_mass(&self, density: Scalar) -> MassData {
+        match *self {
+            Shape::CircleShape{center, radius} => {
+                let mass = density * consts::PI * radius * radius;
+                // Inertia about the center, moved to the body origin
+                let inertia = mass * (0.5 * radius * radius + center.dot(center));
+                return MassData{ mass: mass, center: center, inertia: inertia };
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                return capsule_mass(center1, center2, radius, density);
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                return ellipse_mass(center, radius_x, radius_y, density);
+            },
+            Shape::PolygonShape{ref points} if points.len() >= 3 => {
+                return polygon_mass(points, density);
+            },
+            Shape::LineShape{point1, point2} | Shape::EdgeShape{vertex1: point1, vertex2: point2, ..} => {
+                return MassData{ mass: 0.0, center: (point1 + point2).multiply(0.5), inertia: 0.0 };
+            },
+            _ => {
+                return MassData{ mass: 0.0, center: Vec2::new_zero(), inertia: 0.0 };
+            }
+        }
+    }
+
+    // Lines, edges and chains have no interior, so they never contain a point.
+    pub fn contains_point(&self, shape_position: Vec2, world_point: Vec2) -> bool {
+        let local_point = world_point - shape_position;
+        match *self {
+            Shape::CircleShape{center, radius} => {
+                return (local_point - center).length_squared() <= radius * radius;
+            },
+            Shape::CapsuleShape{center1, center2, radius} => {
+                let closest_point = closest_point_on_segment(center1, center2, local_point);
+                return (local_point - closest_point).length_squared() <= radius * radius;
+            },
+            Shape::EllipseShape{center, radius_x, radius_y} => {
+                return unit_circle_point(radius_x, radius_y, local_point - center).length_squared() <= 1.0;
+            },
+            Shape::HalfPlaneShape{normal, offset} => {
+                return local_point.dot(normal) <= offset;
+            },
+            Shape::PolygonShape{ref points} => {
+                if points.len() < 3 {
+                    return false;
+                }
+                // Inside a convex polygon means being on the inner side of every edge
+                let winding = if signed_area(points) < 0.0 { -1.0 } else { 1.0 };
+                for i in 0..points.len() {
+                    let edge = points[(i + 1) % points.len()] - points[i];
+                    if edge.cross(local_point - points[i]) * winding < 0.0 {
+                        return false;
+                    }
+                }
+                return true;
+            },
+            _ => {
+                return false;
+            }
+        }
+    }
+}
+
+// The unit normal of the segment from vertex1 to vertex2, pointing to its right when
+// the y axis points up.
+pub fn edge_normal(vertex1: Vec2, vertex2: Vec2) -> Vec2 {
+    let edge = vertex2 - vertex1;
+    let mut normal = Vec2::new(edge.y, -edge.x);
+    normal.normalize();
+    return normal;
+}
+
+// The point on the segment from vertex1 to vertex2 nearest to `point`.
+pub fn closest_point_on_segment(vertex1: Vec2, vertex2: Vec2, point: Vec2) -> Vec2 {
+    let edge = vertex2 - vertex1;
+    let length_squared = edge.length_squared();
+    if length_squared < EPSILON {
+        return vertex1;
+    }
+    let t = ((point - vertex1).dot(edge) / length_squared).clamp(0.0, 1.0);
+    return vertex1 + edge.multiply(t);
+}
+
+// Twice the area enclosed by the points, positive when they wind counter clockwise.
+pub fn signed_area(points: &[Vec2]) -> Scalar {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        area += points[i].cross(points[(i + 1) % points.len()]);
+    }
+    return area;
+}
+
+// A rectangle between the centers plus two half circles, following Box2D.
+fn capsule_mass(center1: Vec2, center2: Vec2, radius: Scalar, density: Scalar) -> MassData {
+    let length = (center2 - center1).length();
+    let circle_mass = density * consts::PI * radius * radius;
+    let box_mass = density * 2.0 * radius * length;
+    let mass = circle_mass + box_mass;
+    let center = (center1 + center2).multiply(0.5);
+
+    // Each half circle is moved from its own centroid out to the end of the rectangle
+    let centroid_offset = 4.0 * radius / (3.0 * consts::PI);
+    let half_length = 0.5 * length;
+    let circle_inertia = circle_mass * (0.5 * radius * radius + half_length * half_length + 2.0 * half_length * centroid_offset);
+    let box_inertia = box_mass * (4.0 * radius * radius + length * length) / 12.0;
+
+    // Inertia about the center, moved to the body origin
+    let inertia = circle_inertia + box_inertia + mass * center.dot(center);
+    return MassData{ mass: mass, center: center, inertia: inertia };
+}
+
+// Splits the polygon into a fan of triangles around its first point and sums their area,
+// centroid and second moment, as in Box2D.
+fn polygon_mass(points: &[Vec2], density: Scalar) -> MassData {
+    let reference = points[0];
+    let mut area = 0.0;
+    let mut center = Vec2::new_zero();
+    let mut inertia = 0.0;
+    let inv3 = 1.0 / 3.0;
+
+    for i in 0..points.len() {
+        let e1 = points[i] - reference;
+        let e2 = points[(i + 1) % points.len()] - reference;
+        let d = e1.cross(e2);
+
+        let triangle_area = 0.5 * d;
+        area += triangle_area;
+        center += (e1 + e2).multiply(triangle_area * inv3);
+
+        let intx2 = e1.x * e1.x + e2.x * e1.x + e2.x * e2.x;
+        let inty2 = e1.y * e1.y + e2.y * e1.y + e2.y * e2.y;
+        inertia += (0.25 * inv3 * d) * (intx2 + inty2);
+    }
+
+    if area == 0.0 {
+        return MassData{ mass: 0.0, center: reference, inertia: 0.0 };
+    }
+
+    // Clockwise points give a negative area and inertia
+    let sign = if area < 0.0 { -1.0 } else { 1.0 };
+    let mass = density * area * sign;
+    let local_center = center.multiply(1.0 / area);
+    let centroid = local_center + reference;
+
+    // The sum is about the reference point; move it to the centroid and then to the origin
+    let inertia = density * inertia * sign + mass * (centroid.dot(centroid) - local_center.dot(local_center));
+    return MassData{ mass: mass, center: centroid, inertia: inertia };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_bounds_grow_by_the_radius() {
+        let circle = Shape::CircleShape{center: Vec2::new(1.0, 0.0), radius: 0.5};
+        assert_eq!(circle.compute_aabb(Vec2::new(2.0, 3.0)), AABB::new(Vec2::new(2.5, 2.5), Vec2::new(3.5, 3.5)));
+    }
+
+    #[test]
+    fn line_and_polygon_bounds_cover_their_points() {
+        let line = Shape::LineShape{point1: Vec2::new(1.0, -1.0), point2: Vec2::new(-2.0, 3.0)};
+        assert_eq!(line.compute_aabb(Vec2::new_zero()), AABB::new(Vec2::new(-2.0, -1.0), Vec2::new(1.0, 3.0)));
+
+        let polygon = Shape::PolygonShape{points: vec![Vec2::new(0.0, 0.0), Vec2::new(2.0, 1.0), Vec2::new(-1.0, 4.0)]};
+        assert_eq!(polygon.compute_aabb(Vec2::new(1.0, 1.0)), AABB::new(Vec2::new(0.0, 1.0), Vec2::new(3.0, 5.0)));
+    }
+
+    #[test]
+    fn chain_of_one_point_has_empty_bounds_at_that_point() {
+        let chain = Shape::ChainLineShape{points: vec![Vec2::new(1.0, 2.0)]};
+        assert_eq!(chain.compute_aabb(Vec2::new(1.0, 0.0)), AABB::new(Vec2::new(2.0, 2.0), Vec2::new(2.0, 2.0)));
+        let empty = Shape::ChainLineShape{points: Vec::new()};
+        assert_eq!(empty.compute_aabb(Vec2::new(1.0, 0.0)), AABB::new(Vec2::new(1.0, 0.0), Vec2::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn square_contains_points_inside_and_on_its_edge() {
+        let square = Shape::box_shape(1.0, 1.0);
+        let position = Vec2::new(10.0, 0.0);
+        assert!(square.contains_point(position, Vec2::new(10.5, 0.5)));
+        assert!(square.contains_point(position, Vec2::new(11.0, 0.5)));
+        assert!(!square.contains_point(position, Vec2::new(11.01, 0.5)));
+
+        // Either winding works
+        let clockwise = Shape::PolygonShape{points: vec![Vec2::new(-1.0, -1.0), Vec2::new(-1.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, -1.0)]};
+        assert!(clockwise.contains_point(Vec2::new_zero(), Vec2::new(0.9, -0.9)));
+        assert!(!clockwise.contains_point(Vec2::new_zero(), Vec2::new(1.1, -0.9)));
+    }
+
+    #[test]
+    fn circle_contains_points_within_its_radius() {
+        let circle = Shape::CircleShape{center: Vec2::new(1.0, 0.0), radius: 1.0};
+        assert!(circle.contains_point(Vec2::new_zero(), Vec2::new(2.0, 0.0)));
+        assert!(!circle.contains_point(Vec2::new_zero(), Vec2::new(1.8, 0.8)));
+        let line = Shape::LineShape{point1: Vec2::new(-1.0, 0.0), point2: Vec2::new(1.0, 0.0)};
+        assert!(!line.contains_point(Vec2::new_zero(), Vec2::new_zero()));
+    }
+
+    #[test]
+    fn square_mass_matches_the_analytic_inertia() {
+        // A square of side s has mass density * s^2 and inertia m * s^2 / 6 about its center
+        let centered = Shape::box_shape(0.5, 0.5).compute_mass(2.0);
+        assert!((centered.mass - 2.0).abs() < 1.0e-5);
+        assert!((centered.inertia - 2.0 / 6.0).abs() < 1.0e-5);
+        assert!(centered.center.approx_eq(Vec2::new_zero(), 1.0e-6));
+
+        // Off the body origin the parallel axis theorem adds m * d^2
+        let offset = Shape::PolygonShape{points: vec![Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0)]}.compute_mass(1.0);
+        assert!((offset.mass - 1.0).abs() < 1.0e-5);
+        assert!((offset.inertia - (1.0 / 6.0 + 0.5)).abs() < 1.0e-5);
+        assert!(offset.center.approx_eq(Vec2::new(0.5, 0.5), 1.0e-5));
+    }
+
+    #[test]
+    fn circle_mass_is_density_times_area() {
+        let circle = Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0}.compute_mass(1.0);
+        assert!((circle.mass - consts::PI).abs() < 1.0e-5);
+        assert!((circle.inertia - consts::PI / 2.0).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn capsule_covers_everything_within_radius_of_its_segment() {
+        let capsule = Shape::CapsuleShape{center1: Vec2::new(-1.0, 0.0), center2: Vec2::new(1.0, 0.0), radius: 0.5};
+        assert_eq!(capsule.compute_aabb(Vec2::new(0.0, 1.0)), AABB::new(Vec2::new(-1.5, 0.5), Vec2::new(1.5, 1.5)));
+        assert!(capsule.contains_point(Vec2::new_zero(), Vec2::new(0.0, 0.49)));
+        assert!(capsule.contains_point(Vec2::new_zero(), Vec2::new(1.4, 0.0)));
+        assert!(!capsule.contains_point(Vec2::new_zero(), Vec2::new(1.4, 0.4)));
+        // A 2 by 1 box and two half circles
+        let mass = capsule.compute_mass(1.0);
+        assert!((mass.mass - (2.0 + consts::PI * 0.25)).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn centroid_is_the_center_of_area() {
+        let triangle = Shape::PolygonShape{points: vec![Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(0.0, 3.0)]};
+        assert!(triangle.centroid().approx_eq(Vec2::new(1.0, 1.0), 1.0e-5));
+        // A corner cut off the top right pulls the centroid away from the vertex average
+        let notched = Shape::PolygonShape{points: vec![Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, 1.0),
+            Vec2::new(3.0, 2.0), Vec2::new(0.0, 2.0)]};
+        assert!(notched.centroid().approx_eq(Vec2::new(17.0 / 9.0, 43.0 / 45.0), 1.0e-5));
+        let line = Shape::LineShape{point1: Vec2::new(0.0, 0.0), point2: Vec2::new(2.0, 4.0)};
+        assert_eq!(line.centroid(), Vec2::new(1.0, 2.0));
+    }
+
+    #[test]
+    fn scaling_a_square_scales_its_points_and_bounds() {
+        match Shape::box_shape(0.5, 0.5).scaled(Vec2::new(2.0, 2.0)) {
+            Shape::PolygonShape{ref points} => {
+                assert_eq!(points[0], Vec2::new(-1.0, -1.0));
+                assert_eq!(points[2], Vec2::new(1.0, 1.0));
+                let aabb = Shape::PolygonShape{points: points.clone()}.compute_aabb(Vec2::new_zero());
+                assert_eq!((aabb.min, aabb.max), (Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)));
+            },
+            _ => panic!("expected a polygon")
+        }
+        // Mirroring keeps the winding counter clockwise
+        match Shape::box_shape(0.5, 0.5).scaled(Vec2::new(-1.0, 1.0)) {
+            Shape::PolygonShape{ref points} => assert!(signed_area(points) > 0.0),
+            _ => panic!("expected a polygon")
+        }
+        let moved = Shape::box_shape(0.5, 0.5).translated(Vec2::new(3.0, 0.0));
+        assert_eq!(moved.compute_aabb(Vec2::new_zero()).min, Vec2::new(2.5, -0.5));
+    }
+
+    #[test]
+    fn scaling_circles_and_half_planes() {
+        let circle = Shape::CircleShape{center: Vec2::new(1.0, 0.0), radius: 1.0};
+        match circle.scaled(Vec2::new(3.0, 3.0)) {
+            Shape::CircleShape{center, radius} => assert_eq!((center, radius), (Vec2::new(3.0, 0.0), 3.0)),
+            _ => panic!("expected a circle")
+        }
+        let half_plane = Shape::HalfPlaneShape{normal: Vec2::new(0.0, -1.0), offset: 1.0};
+        match half_plane.scaled(Vec2::new(1.0, 2.0)) {
+            Shape::HalfPlaneShape{normal, offset} => assert_eq!((normal, offset), (Vec2::new(0.0, -1.0), 2.0)),
+            _ => panic!("expected a half plane")
+        }
+    }
+
+    #[test]
+    fn area_ignores_winding_and_is_zero_for_lines() {
+        let circle = Shape::CircleShape{center: Vec2::new(3.0, 1.0), radius: 1.0};
+        assert!((circle.area() - consts::PI).abs() < 1.0e-6);
+        assert_eq!(Shape::box_shape(0.5, 0.5).area(), 1.0);
+        let clockwise = Shape::PolygonShape{points: vec![Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0)]};
+        assert_eq!(clockwise.area(), 1.0);
+        assert_eq!(Shape::LineShape{point1: Vec2::new(0.0, 0.0), point2: Vec2::new(4.0, 0.0)}.area(), 0.0);
+        assert_eq!(Shape::ChainLineShape{points: vec![Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, 4.0)]}.area(), 0.0);
+    }
 }
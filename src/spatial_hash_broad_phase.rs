@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use super::body::Body;
+use super::broad_phase::BroadPhase;
+use super::math::Scalar;
+
+// Bodies covering more cells than this are tested against every other body instead, which
+// keeps half planes and other huge bodies from filling the grid
+const MAX_CELLS_PER_BODY: i64 = 64;
+
+// Buckets the bodies by the grid cells their bounds cover and only compares bodies that
+// share a cell. Works best when most bodies are about a cell in size.
+pub struct SpatialHashBroadPhase {
+    cell_size: Scalar
+}
+
+impl BroadPhase for SpatialHashBroadPhase {
+    fn run(&self, bodies: &Vec<Body>) -> Vec<(usize, usize)> {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        let mut oversized: Vec<usize> = Vec::new();
+        for (i, body) in bodies.iter().enumerate() {
+            let aabb = body.aabb();
+            let min = ((aabb.min.x / self.cell_size).floor(), (aabb.min.y / self.cell_size).floor());
+            let max = ((aabb.max.x / self.cell_size).floor(), (aabb.max.y / self.cell_size).floor());
+            let cell_count = (max.0 - min.0 + 1.0) * (max.1 - min.1 + 1.0);
+            if cell_count.is_nan() || cell_count > MAX_CELLS_PER_BODY as Scalar {
+                oversized.push(i);
+                continue;
+            }
+            for x in min.0 as i32..max.0 as i32 + 1 {
+                for y in min.1 as i32..max.1 as i32 + 1 {
+                    cells.entry((x, y)).or_default().push(i);
+                }
+            }
+        }
+
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for bucket in cells.values() {
+            for (i, &a) in bucket.iter().enumerate() {
+                for &b in bucket[i + 1..].iter() {
+                    if bodies[a].aabb().overlaps(&bodies[b].aabb()) {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        for &a in oversized.iter() {
+            for b in 0..bodies.len() {
+                if a != b && bodies[a].aabb().overlaps(&bodies[b].aabb()) {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+        // Bodies sharing several cells are found in each of them, and two oversized bodies
+        // find each other twice
+        pairs.sort();
+        pairs.dedup();
+        return pairs;
+    }
+}
+
+impl SpatialHashBroadPhase {
+    // Panics unless the cell size is positive and finite.
+    pub fn new(cell_size: Scalar) -> SpatialHashBroadPhase {
+        assert!(cell_size > 0.0 && cell_size.is_finite(), "SpatialHashBroadPhase::new: the cell size must be positive and finite, not {}", cell_size);
+        return SpatialHashBroadPhase{ cell_size: cell_size };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::body::{BodyDef, BodyHandle, BodyType};
+    use super::super::brute_force_broad_phase::BruteForceBroadPhase;
+    use super::super::default_broad_phase::DefaultBroadPhase;
+    use super::super::math::Vec2;
+    use super::super::shape::shape::Shape;
+
+    #[test]
+    fn finds_the_same_pairs_as_the_other_broad_phases() {
+        let mut bodies: Vec<Body> = Vec::new();
+        for i in 0..60 {
+            // A scattered mix of small and large bodies, some straddling cell edges
+            let x = ((i * 37) % 23) as Scalar * 0.7 - 8.0;
+            let y = ((i * 11) % 17) as Scalar * 0.6 - 5.0;
+            let half_size = 0.2 + ((i * 7) % 5) as Scalar * 0.3;
+            let def = BodyDef::builder(Shape::box_shape(half_size, half_size)).body_type(BodyType::DynamicBody)
+                .position(Vec2::new(x, y)).build();
+            bodies.push(Body::new(i, BodyHandle{ index: i as u32, generation: 0 }, def));
+        }
+        bodies.push(Body::new(60, BodyHandle{ index: 60, generation: 0 },
+            BodyDef::builder(Shape::box_shape(100.0, 0.5)).position(Vec2::new(0.0, 3.0)).build()));
+
+        let mut expected = BruteForceBroadPhase::new().run(&bodies);
+        expected.sort();
+        assert!(!expected.is_empty());
+        assert_eq!(DefaultBroadPhase::new().run(&bodies), expected);
+        for &cell_size in [0.5, 1.0, 4.0].iter() {
+            let mut pairs = SpatialHashBroadPhase::new(cell_size).run(&bodies);
+            pairs.sort();
+            assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_a_cell_size_of_zero() {
+        SpatialHashBroadPhase::new(0.0);
+    }
+}
@@ -0,0 +1,143 @@
+use super::math::{Vec2, Transform, Scalar};
+use super::shape::shape::Shape;
+use super::distance::distance;
+
+// How close the shapes are brought before they count as touching
+pub const TARGET_SEPARATION: Scalar = 0.005;
+
+const MAX_ITERATIONS: usize = 30;
+
+// A shape's motion from its start transform to its end transform. Positions move in a
+// straight line and the angle turns the short way round.
+#[derive(Clone, Copy, Debug)]
+pub struct Sweep {
+    pub start: Transform,
+    pub end: Transform
+}
+
+impl Sweep {
+    pub fn new(start: Transform, end: Transform) -> Sweep {
+        return Sweep{ start: start, end: end };
+    }
+
+    // A shape that doesn't move.
+    pub fn fixed(transform: Transform) -> Sweep {
+        return Sweep{ start: transform, end: transform };
+    }
+
+    // The transform a fraction of the way through the motion.
+    pub fn transform(&self, fraction: Scalar) -> Transform {
+        let position = self.start.position + (self.end.position - self.start.position).multiply(fraction);
+        return Transform::new(position, self.start.rotation.angle() + self.turn() * fraction);
+    }
+
+    // The angle turned over the whole motion, between -pi and pi.
+    fn turn(&self) -> Scalar {
+        let start = self.start.rotation;
+        let end = self.end.rotation;
+        return (start.c * end.s - start.s * end.c).atan2(start.c * end.c + start.s * end.s);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TOIState {
+     // The shapes come within TARGET_SEPARATION of each other at the fraction
+     Touching,
+     // The shapes never touch during the motion
+     Separated,
+     // The shapes already overlap at the start
+     Overlapped,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TOIOutput {
+    pub state: TOIState,
+    // Between 0 and 1. Where the shapes touch, or 1 when they don't and 0 when they overlap
+    pub fraction: Scalar,
+    // Points from b to a where they touch, zero otherwise
+    pub normal: Vec2
+}
+
+// When the swept shapes first touch, by conservative advancement as in Box2D. Each step
+// moves both shapes forward by their distance over the fastest they can close on each
+// other, which can never step past the first contact. Half planes are assumed not to turn.
+pub fn time_of_impact(shape_a: &Shape, sweep_a: Sweep, shape_b: &Shape, sweep_b: Sweep) -> TOIOutput {
+    let translation = (sweep_a.end.position - sweep_a.start.position) - (sweep_b.end.position - sweep_b.start.position);
+    let max_speed = translation.length() + turn_speed(shape_a, sweep_a) + turn_speed(shape_b, sweep_b);
+
+    let mut fraction = 0.0;
+    for i in 0..MAX_ITERATIONS {
+        let output = distance(shape_a, sweep_a.transform(fraction), shape_b, sweep_b.transform(fraction));
+        if output.distance <= 0.0 && i == 0 {
+            return TOIOutput{ state: TOIState::Overlapped, fraction: 0.0, normal: Vec2::new_zero() };
+        }
+        if output.distance < 2.0 * TARGET_SEPARATION {
+            let mut normal = output.point_a - output.point_b;
+            normal.normalize();
+            return TOIOutput{ state: TOIState::Touching, fraction: fraction, normal: normal };
+        }
+        if max_speed <= 0.0 {
+            break;
+        }
+        fraction += (output.distance - TARGET_SEPARATION) / max_speed;
+        if fraction >= 1.0 {
+            break;
+        }
+    }
+    return TOIOutput{ state: TOIState::Separated, fraction: 1.0, normal: Vec2::new_zero() };
+}
+
+// How far any point of the shape can move over the sweep due to turning alone.
+fn turn_speed(shape: &Shape, sweep: Sweep) -> Scalar {
+    let turn = sweep.turn().abs();
+    if turn == 0.0 {
+        return 0.0;
+    }
+    let bounds = shape.compute_aabb(Vec2::new_zero());
+    let reach = Vec2::new(bounds.min.x.abs().max(bounds.max.x.abs()), bounds.min.y.abs().max(bounds.max.y.abs())).length();
+    if !reach.is_finite() {
+        return 0.0;
+    }
+    return turn * reach;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::math::consts;
+
+    fn circle() -> Shape {
+        return Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5};
+    }
+
+    #[test]
+    fn circle_sliding_into_a_box_stops_halfway() {
+        let at_rest = Sweep::fixed(Transform::identity());
+        let sweep = Sweep::new(Transform::new(Vec2::new(-3.5, 0.0), 0.0), Transform::new(Vec2::new(0.5, 0.0), 0.0));
+        let hit = time_of_impact(&circle(), sweep, &Shape::box_shape(1.0, 1.0), at_rest);
+        assert_eq!(hit.state, TOIState::Touching);
+        assert!((hit.fraction - 0.5).abs() < 0.01);
+        assert!(hit.normal.x < -0.99);
+    }
+
+    #[test]
+    fn misses_and_overlaps_are_told_apart() {
+        let at_rest = Sweep::fixed(Transform::identity());
+        let passing = Sweep::new(Transform::new(Vec2::new(-3.5, 3.0), 0.0), Transform::new(Vec2::new(3.5, 3.0), 0.0));
+        let miss = time_of_impact(&circle(), passing, &Shape::box_shape(1.0, 1.0), at_rest);
+        assert_eq!(miss.state, TOIState::Separated);
+        assert_eq!(miss.fraction, 1.0);
+        assert_eq!(time_of_impact(&circle(), at_rest, &Shape::box_shape(1.0, 1.0), at_rest).state, TOIState::Overlapped);
+    }
+
+    #[test]
+    fn turning_bar_stops_just_short_of_a_circle() {
+        let bar = Shape::PolygonShape{points: vec![Vec2::new(-0.1, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, 0.2), Vec2::new(-0.1, 0.2)]};
+        let circle_transform = Transform::new(Vec2::new(0.0, -3.0), 0.0);
+        let swing = Sweep::new(Transform::identity(), Transform::new(Vec2::new_zero(), -consts::FRAC_PI_2));
+        let hit = time_of_impact(&bar, swing, &circle(), Sweep::fixed(circle_transform));
+        assert_eq!(hit.state, TOIState::Touching);
+        let gap = distance(&bar, swing.transform(hit.fraction), &circle(), circle_transform).distance;
+        assert!(gap > 0.0 && gap < 2.0 * TARGET_SEPARATION + 0.001);
+    }
+}
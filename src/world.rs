@@ -1,115 +1,3297 @@
-use super::math::Vec2;
-use super::body::BodyDef;
+use super::math::{Vec2, Transform, Scalar, consts};
+use super::body::{BodyDef, BodyDefError};
 use super::body::Body;
-use super::manifold::Manifold;
-use super::broad_phase::BroadPhase;
+use super::shape::shape::{Shape, signed_area};
+use super::body::BodyHandle;
+use super::body::BodyType;
+use super::body::{LINEAR_SLEEP_TOLERANCE, ANGULAR_SLEEP_TOLERANCE, TIME_TO_SLEEP};
+use super::manifold::{Manifold, Contact, ContactId};
+use super::broad_phase::{BroadPhase, BroadphaseKind};
 use super::default_broad_phase::DefaultBroadPhase;
+use super::brute_force_broad_phase::BruteForceBroadPhase;
+use super::spatial_hash_broad_phase::SpatialHashBroadPhase;
 use super::narrow_phase::NarrowPhase;
 use super::default_narrow_phase::DefaultNarrowPhase;
+use super::collision::collider_result::ColliderResult;
 use super::collision_resolution::CollisionResolution;
+use super::contact_listener::{ContactListener, ContactImpulse};
+use super::ray_cast::RayCastHit;
+use super::aabb::AABB;
+use super::joint::joint::{Joint, JointHandle};
+use super::time_of_impact::{time_of_impact, Sweep, TOIState};
+use super::distance::distance;
+use super::profile::{Profile, microseconds, lap};
+use super::mixing_rule::MixingRule;
+use super::debug_draw::DebugShape;
+use super::island::Islands;
+use super::buoyancy::BuoyancyRegion;
+use std::time::Instant;
+use std::cmp::Ordering;
 
+// Metres per second, as in Box2D
+const DEFAULT_RESTITUTION_THRESHOLD: Scalar = 1.0;
+// Twice the slop, as Box2D's polygon radius
+const DEFAULT_POLYGON_SKIN: Scalar = 0.01;
+// Metres, as in Box2D
+const DEFAULT_MAX_LINEAR_CORRECTION: Scalar = 0.2;
+// Share of the penetration pushed out per position iteration, and the penetration left alone
+const DEFAULT_BAUMGARTE: Scalar = 0.5;
+const DEFAULT_LINEAR_SLOP: Scalar = 0.01;
+// Per step, as in Box2D
+const DEFAULT_MAX_TRANSLATION: Scalar = 2.0;
+const DEFAULT_MAX_ROTATION: Scalar = 0.5 * consts::PI;
+// Longest frame advance will catch up on, so a slow frame can't make the next one slower
+const MAX_FRAME_TIME: Scalar = 0.2;
+// How far two polygons may move or turn relative to each other before their cached
+// manifold is found again
+const MANIFOLD_CACHE_DISTANCE: Scalar = 1.0e-4;
+const MANIFOLD_CACHE_ANGLE: Scalar = 1.0e-4;
+// Static boxes this close to lining up are welded together
+const STATIC_MERGE_TOLERANCE: Scalar = 1.0e-4;
+
+// A touching pair being solved this step. The normal points from b to a.
+struct ContactConstraint {
+	a: usize,
+	b: usize,
+	fixture_a: Option<usize>,
+	fixture_b: Option<usize>,
+	normal: Vec2,
+	start_position_a: Vec2,
+	start_position_b: Vec2,
+	start_angle_a: Scalar,
+	start_angle_b: Scalar,
+	inv_mass_a: Scalar,
+	inv_mass_b: Scalar,
+	inv_inertia_a: Scalar,
+	inv_inertia_b: Scalar,
+	friction: Scalar,
+	points: Vec<ContactConstraintPoint>
+}
+
+struct ContactConstraintPoint {
+	id: ContactId,
+	// From each body's position to the contact point
+	r_a: Vec2,
+	r_b: Vec2,
+	penetration: Scalar,
+	normal_mass: Scalar,
+	tangent_mass: Scalar,
+	// Relative velocity along the normal the normal impulse aims for, negative to bounce
+	target_velocity: Scalar,
+	normal_impulse: Scalar,
+	tangent_impulse: Scalar
+}
+
+// The impulses a contact ended the last step with, by contact point id.
+#[derive(Clone)]
+struct CachedContact {
+	a: usize,
+	b: usize,
+	fixture_a: Option<usize>,
+	fixture_b: Option<usize>,
+	impulses: Vec<(ContactId, Scalar, Scalar)>
+}
+
+// The narrow phase result for a pair of polygons, kept in body a's frame along with
+// where the bodies were relative to each other and the points it was found for.
+#[derive(Clone)]
+struct CachedManifold {
+	a: usize,
+	b: usize,
+	fixture_a: Option<usize>,
+	fixture_b: Option<usize>,
+	points_a: Vec<Vec2>,
+	points_b: Vec<Vec2>,
+	relative_position: Vec2,
+	relative_angle: Scalar,
+	manifold: Option<Manifold>
+}
+
+// Where the body a handle refers to currently lives in World::bodies.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct BodySlot {
+	generation: u32,
+	index: Option<usize>
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct JointSlot {
+	generation: u32,
+	joint: Option<Joint>
+}
+
+// Static bodies World::optimize_static_geometry is welding into one box. Only boxes with
+// the same key, everything about a body that its collisions depend on besides the shape,
+// are welded.
+struct StaticBox {
+	key: (u16, u16, i16, bool, u64, u64, u64),
+	aabb: AABB,
+	bodies: Vec<usize>
+}
+
+// What a call to World::advance did.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Advance {
+	// How many fixed steps were run
+	pub steps: usize,
+	// Time not yet stepped, carried over to the next call
+	pub leftover: Scalar,
+	// How far the leftover is into the next step, from 0 to 1, for interpolating
+	pub alpha: Scalar
+}
+
+// What a call to World::step did, for game logic too simple to need a contact listener.
+// A step split into sub steps adds them up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepResult {
+	// Pairs of bodies that started or stopped touching, as the listener would hear
+	pub contacts_begun: u32,
+	pub contacts_ended: u32,
+	// Dynamic and kinematic bodies asleep once the step is done
+	pub bodies_asleep: u32,
+	// Velocity and position passes the solver ran, added up over the islands with contacts
+	// or joints to solve, so nothing to solve takes none.
+	pub iterations_used: u32
+}
+
+// Saving a world keeps its bodies and settings. The broad and narrow phase go back to the
+// defaults, the contact listener is dropped, and contacts are found again on the next step.
+// An f64 world saved with serde_json only loads back exactly with its float_roundtrip feature.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct World {
 	gravity: Vec2,
-	broad_phase: Box<BroadPhase>,
-	narrow_phase: Box<NarrowPhase>,
+	#[cfg_attr(feature = "serde", serde(skip, default = "default_broad_phase"))]
+	broad_phase: Box<dyn BroadPhase>,
+	#[cfg_attr(feature = "serde", serde(skip, default = "default_narrow_phase"))]
+	narrow_phase: Box<dyn NarrowPhase>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	contact_listener: Option<Box<dyn ContactListener>>,
+	// Pairs of bodies touching as of the last step, sorted
+	#[cfg_attr(feature = "serde", serde(skip))]
+	contact_pairs: Vec<(usize, usize)>,
+	// Last step's contact impulses, sorted by body pair, to warm start the solver with and
+	// to report in contacts
+	#[cfg_attr(feature = "serde", serde(skip))]
+	contact_cache: Vec<CachedContact>,
+	// Last step's polygon pair manifolds, sorted by body pair, so pairs that haven't moved
+	// relative to each other skip the narrow phase
+	#[cfg_attr(feature = "serde", serde(skip))]
+	manifold_cache: Vec<CachedManifold>,
+	// The manifolds found by the last step, before the solver moved the bodies
+	#[cfg_attr(feature = "serde", serde(skip))]
+	contacts: Vec<Contact>,
+	body_slots: Vec<BodySlot>,
+	free_slots: Vec<u32>,
+	joint_slots: Vec<JointSlot>,
+	velocity_iterations: usize,
+	position_iterations: usize,
+	// Approach speed below which contacts don't bounce
+	#[cfg_attr(feature = "serde", serde(default = "default_restitution_threshold"))]
+	restitution_threshold: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_warm_starting"))]
+	warm_starting: bool,
+	#[cfg_attr(feature = "serde", serde(default = "default_friction_mixing"))]
+	friction_mixing: MixingRule,
+	#[cfg_attr(feature = "serde", serde(default = "default_restitution_mixing"))]
+	restitution_mixing: MixingRule,
+	#[cfg_attr(feature = "serde", serde(default = "default_polygon_skin"))]
+	polygon_skin: Scalar,
+	// Furthest a contact pushes its bodies apart in one step
+	#[cfg_attr(feature = "serde", serde(default = "default_max_linear_correction"))]
+	max_linear_correction: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_baumgarte"))]
+	baumgarte: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_linear_slop"))]
+	linear_slop: Scalar,
+	// Furthest a body moves or turns in one step
+	#[cfg_attr(feature = "serde", serde(default = "default_max_translation"))]
+	max_translation: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_max_rotation"))]
+	max_rotation: Scalar,
+	// How slow a body has to be, and for how long, to fall asleep
+	#[cfg_attr(feature = "serde", serde(default = "default_linear_sleep_tolerance"))]
+	linear_sleep_tolerance: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_angular_sleep_tolerance"))]
+	angular_sleep_tolerance: Scalar,
+	#[cfg_attr(feature = "serde", serde(default = "default_time_to_sleep"))]
+	time_to_sleep: Scalar,
+	// Longest time step solved in one go, zero for no limit
+	#[cfg_attr(feature = "serde", serde(default))]
+	max_sub_step: Scalar,
+	// Frame time World::advance has not stepped yet
+	#[cfg_attr(feature = "serde", serde(default))]
+	accumulator: Scalar,
+	#[cfg_attr(feature = "serde", serde(default))]
+	buoyancy_regions: Vec<BuoyancyRegion>,
+	// Bodies entirely outside it are left where they are, None simulates everywhere
+	#[cfg_attr(feature = "serde", serde(default))]
+	active_region: Option<AABB>,
+	// Which bodies were outside the active region when this step began
+	#[cfg_attr(feature = "serde", serde(skip))]
+	frozen: Vec<bool>,
+	// The skinned copies of static bodies' shapes the broad and narrow phase see, kept
+	// from step to step, with the body and fixture of each. static_key holds the index,
+	// handle and revision of each static body they were built from.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	static_proxies: Vec<Body>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	static_owners: Vec<(usize, Option<usize>)>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	static_key: Vec<(usize, BodyHandle, u32)>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	static_skin: Scalar,
+	// Velocity and position passes the solver ran over all islands on the last step
+	#[cfg_attr(feature = "serde", serde(skip))]
+	solver_passes: u32,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	profile: Profile,
+	#[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_bodies"))]
 	pub bodies: Vec<Body>
 }
 
+// The copy steps exactly like the original. It gets fresh default broad and narrow phases,
+// which keep no state between steps, and no contact listener.
+impl Clone for World {
+	fn clone(&self) -> World {
+		return World{ gravity: self.gravity,
+					broad_phase: Box::new(DefaultBroadPhase::new()),
+					narrow_phase: Box::new(DefaultNarrowPhase::new()),
+					contact_listener: None,
+					contact_pairs: self.contact_pairs.clone(),
+					contact_cache: self.contact_cache.clone(),
+					manifold_cache: self.manifold_cache.clone(),
+					contacts: self.contacts.clone(),
+					body_slots: self.body_slots.clone(),
+					free_slots: self.free_slots.clone(),
+					joint_slots: self.joint_slots.clone(),
+					velocity_iterations: self.velocity_iterations,
+					position_iterations: self.position_iterations,
+					restitution_threshold: self.restitution_threshold,
+					warm_starting: self.warm_starting,
+					friction_mixing: self.friction_mixing,
+					restitution_mixing: self.restitution_mixing,
+					polygon_skin: self.polygon_skin,
+					max_linear_correction: self.max_linear_correction,
+					baumgarte: self.baumgarte,
+					linear_slop: self.linear_slop,
+					max_translation: self.max_translation,
+					max_rotation: self.max_rotation,
+					linear_sleep_tolerance: self.linear_sleep_tolerance,
+					angular_sleep_tolerance: self.angular_sleep_tolerance,
+					time_to_sleep: self.time_to_sleep,
+					max_sub_step: self.max_sub_step,
+					accumulator: self.accumulator,
+					buoyancy_regions: self.buoyancy_regions.clone(),
+					active_region: self.active_region,
+					frozen: self.frozen.clone(),
+					static_proxies: self.static_proxies.clone(),
+					static_owners: self.static_owners.clone(),
+					static_key: self.static_key.clone(),
+					static_skin: self.static_skin,
+					solver_passes: self.solver_passes,
+					profile: self.profile,
+					bodies: self.bodies.clone() }
+	}
+}
+
+#[cfg(feature = "serde")]
+fn default_broad_phase() -> Box<dyn BroadPhase> {
+	return Box::new(DefaultBroadPhase::new());
+}
+
+#[cfg(feature = "serde")]
+fn default_narrow_phase() -> Box<dyn NarrowPhase> {
+	return Box::new(DefaultNarrowPhase::new());
+}
+
+#[cfg(feature = "serde")]
+fn default_restitution_threshold() -> Scalar {
+	return DEFAULT_RESTITUTION_THRESHOLD;
+}
+
+#[cfg(feature = "serde")]
+fn default_warm_starting() -> bool {
+	return true;
+}
+
+#[cfg(feature = "serde")]
+fn default_friction_mixing() -> MixingRule {
+	return MixingRule::GeometricMean;
+}
+
+#[cfg(feature = "serde")]
+fn default_restitution_mixing() -> MixingRule {
+	return MixingRule::Min;
+}
+
+#[cfg(feature = "serde")]
+fn default_polygon_skin() -> Scalar {
+	return DEFAULT_POLYGON_SKIN;
+}
+
+#[cfg(feature = "serde")]
+fn default_max_linear_correction() -> Scalar {
+	return DEFAULT_MAX_LINEAR_CORRECTION;
+}
+
+#[cfg(feature = "serde")]
+fn default_baumgarte() -> Scalar {
+	return DEFAULT_BAUMGARTE;
+}
+
+#[cfg(feature = "serde")]
+fn default_linear_slop() -> Scalar {
+	return DEFAULT_LINEAR_SLOP;
+}
+
+#[cfg(feature = "serde")]
+fn default_max_translation() -> Scalar {
+	return DEFAULT_MAX_TRANSLATION;
+}
+
+#[cfg(feature = "serde")]
+fn default_max_rotation() -> Scalar {
+	return DEFAULT_MAX_ROTATION;
+}
+
+#[cfg(feature = "serde")]
+fn default_linear_sleep_tolerance() -> Scalar {
+	return LINEAR_SLEEP_TOLERANCE;
+}
+
+#[cfg(feature = "serde")]
+fn default_angular_sleep_tolerance() -> Scalar {
+	return ANGULAR_SLEEP_TOLERANCE;
+}
+
+#[cfg(feature = "serde")]
+fn default_time_to_sleep() -> Scalar {
+	return TIME_TO_SLEEP;
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_bodies<'de, D>(deserializer: D) -> Result<Vec<Body>, D::Error> where D: ::serde::Deserializer<'de> {
+	let mut bodies: Vec<Body> = ::serde::Deserialize::deserialize(deserializer)?;
+	for body in bodies.iter_mut() {
+		body.update_aabb();
+	}
+	return Ok(bodies);
+}
+
+// The bounds of a static body's shape in world coordinates when the shape is a box lined
+// up with the axes, so that it fills them exactly.
+fn axis_aligned_box(body: &Body) -> Option<AABB> {
+	let points = match body.shape {
+		Shape::PolygonShape{ref points} if points.len() == 4 => points,
+		_ => return None
+	};
+	let transform = body.transform();
+	let world: Vec<Vec2> = points.iter().map(|&point| transform.apply(point)).collect();
+	let aabb = AABB::from_points(&world, Vec2::new_zero());
+	let extents = aabb.extents();
+	if extents.x <= STATIC_MERGE_TOLERANCE || extents.y <= STATIC_MERGE_TOLERANCE {
+		return None;
+	}
+	let near = |a: Scalar, b: Scalar| (a - b).abs() <= STATIC_MERGE_TOLERANCE;
+	let on_corner = |point: &Vec2| (near(point.x, aabb.min.x) || near(point.x, aabb.max.x)) &&
+		(near(point.y, aabb.min.y) || near(point.y, aabb.max.y));
+	// Four corners and the area of the bounds, so no two points share a corner
+	let box_area = 4.0 * extents.x * extents.y;
+	if !world.iter().all(on_corner) || (0.5 * signed_area(&world).abs() - box_area).abs() > STATIC_MERGE_TOLERANCE * box_area {
+		return None;
+	}
+	return Some(aabb);
+}
+
+// Joins boxes of the same key that follow on from each other along x, or along y, and
+// span the same range across it.
+fn weld_boxes(mut boxes: Vec<StaticBox>, along_x: bool) -> Vec<StaticBox> {
+	// Along and across the direction of welding
+	let split = |point: Vec2| if along_x { (point.x, point.y) } else { (point.y, point.x) };
+	let order = |a: Scalar, b: Scalar| a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+	boxes.sort_by(|p, q| p.key.cmp(&q.key)
+		.then(order(split(p.aabb.min).1, split(q.aabb.min).1))
+		.then(order(split(p.aabb.max).1, split(q.aabb.max).1))
+		.then(order(split(p.aabb.min).0, split(q.aabb.min).0)));
+
+	let mut welded: Vec<StaticBox> = Vec::new();
+	for next in boxes.into_iter() {
+		if let Some(last) = welded.last_mut() {
+			let same_span = (split(last.aabb.min).1 - split(next.aabb.min).1).abs() <= STATIC_MERGE_TOLERANCE &&
+				(split(last.aabb.max).1 - split(next.aabb.max).1).abs() <= STATIC_MERGE_TOLERANCE;
+			if last.key == next.key && same_span && split(next.aabb.min).0 <= split(last.aabb.max).0 + STATIC_MERGE_TOLERANCE {
+				last.aabb = last.aabb.combine(&next.aabb);
+				last.bodies.extend(next.bodies);
+				continue;
+			}
+		}
+		welded.push(next);
+	}
+	return welded;
+}
+
+// The body's own shape and each of its fixtures as bodies of their own, leaving out
+// sensors, for sweeping one fixture at a time.
+fn solid_fixtures(body: &Body) -> Vec<Body> {
+	return ::std::iter::once(None).chain((0..body.fixtures.len()).map(Some))
+		.map(|fixture| body.fixture_body(fixture))
+		.filter(|piece| !piece.is_sensor)
+		.collect();
+}
+
+// The manifold with its points and normal mapped into another frame.
+fn to_frame<P: Fn(Vec2) -> Vec2, N: Fn(Vec2) -> Vec2>(manifold: &Manifold, point: P, normal: N) -> Manifold {
+	let mut mapped = manifold.clone();
+	mapped.normal = normal(manifold.normal);
+	for contact_point in mapped.points.iter_mut() {
+		contact_point.position = point(contact_point.position);
+	}
+	return mapped;
+}
+
 impl World {
 	pub fn new(gravity: Vec2) -> World {
 		return World{ gravity: gravity,
 					broad_phase: Box::new(DefaultBroadPhase::new()),
 					narrow_phase: Box::new(DefaultNarrowPhase::new()),
+					contact_listener: None,
+					contact_pairs: Vec::new(),
+					contact_cache: Vec::new(),
+					manifold_cache: Vec::new(),
+					contacts: Vec::new(),
+					body_slots: Vec::new(),
+					free_slots: Vec::new(),
+					joint_slots: Vec::new(),
+					velocity_iterations: 8,
+					position_iterations: 3,
+					restitution_threshold: DEFAULT_RESTITUTION_THRESHOLD,
+					warm_starting: true,
+					friction_mixing: MixingRule::GeometricMean,
+					restitution_mixing: MixingRule::Min,
+					polygon_skin: DEFAULT_POLYGON_SKIN,
+					max_linear_correction: DEFAULT_MAX_LINEAR_CORRECTION,
+					baumgarte: DEFAULT_BAUMGARTE,
+					linear_slop: DEFAULT_LINEAR_SLOP,
+					max_translation: DEFAULT_MAX_TRANSLATION,
+					max_rotation: DEFAULT_MAX_ROTATION,
+					linear_sleep_tolerance: LINEAR_SLEEP_TOLERANCE,
+					angular_sleep_tolerance: ANGULAR_SLEEP_TOLERANCE,
+					time_to_sleep: TIME_TO_SLEEP,
+					max_sub_step: 0.0,
+					accumulator: 0.0,
+					buoyancy_regions: Vec::new(),
+					active_region: None,
+					frozen: Vec::new(),
+					static_proxies: Vec::new(),
+					static_owners: Vec::new(),
+					static_key: Vec::new(),
+					static_skin: 0.0,
+					solver_passes: 0,
+					profile: Profile::default(),
 					bodies: Vec::new() }
 	}
 
-	pub fn add_body(&mut self, body_def: BodyDef) {
+	// Debug builds panic on a definition that try_add_body would turn down.
+	pub fn add_body(&mut self, body_def: BodyDef) -> BodyHandle {
+		if cfg!(debug_assertions) {
+			if let Err(error) = body_def.validate() {
+				panic!("add_body: {}", error);
+			}
+		}
 		let id = self.bodies.len();
-		let body = Body::new(id, body_def);
+		let handle = match self.free_slots.pop() {
+			Some(slot) => {
+				let slot_data = &mut self.body_slots[slot as usize];
+				slot_data.index = Some(id);
+				BodyHandle{ index: slot, generation: slot_data.generation }
+			},
+			None => {
+				self.body_slots.push(BodySlot{ generation: 0, index: Some(id) });
+				BodyHandle{ index: (self.body_slots.len() - 1) as u32, generation: 0 }
+			}
+		};
+		let body = Body::new(id, handle, body_def);
 		self.bodies.push(body);
+		return handle;
+	}
+
+	// Adds the body if its definition passes BodyDef::validate.
+	pub fn try_add_body(&mut self, body_def: BodyDef) -> Result<BodyHandle, BodyDefError> {
+		body_def.validate()?;
+		return Ok(self.add_body(body_def));
+	}
+
+	// The current index of the body in World::bodies, or None if it has been removed.
+	pub fn body_index(&self, handle: BodyHandle) -> Option<usize> {
+		match self.body_slots.get(handle.index as usize) {
+			Some(slot) if slot.generation == handle.generation => return slot.index,
+			_ => return None
+		}
+	}
+
+	pub fn get_body(&self, handle: BodyHandle) -> Option<&Body> {
+		match self.body_index(handle) {
+			Some(index) => return Some(&self.bodies[index]),
+			None => return None
+		}
+	}
+
+	pub fn get_body_mut(&mut self, handle: BodyHandle) -> Option<&mut Body> {
+		match self.body_index(handle) {
+			Some(index) => return Some(&mut self.bodies[index]),
+			None => return None
+		}
+	}
+
+	// Removes the body, returning false if it was already gone. Bodies after it in
+	// World::bodies shift down by one index. Contacts involving the body are ended first.
+	pub fn remove_body(&mut self, handle: BodyHandle) -> bool {
+		let index = match self.body_index(handle) {
+			Some(index) => index,
+			None => return false
+		};
+		let mut removed = vec![false; self.bodies.len()];
+		removed[index] = true;
+		self.remove_marked(&removed);
+		return true;
+	}
+
+	// Removes every body marked in removed in a single pass, with their contacts and the
+	// joints attached to them, and moves the bodies left down to fill the gaps.
+	fn remove_marked(&mut self, removed: &[bool]) {
+		let mut remap: Vec<Option<usize>> = Vec::with_capacity(removed.len());
+		let mut kept = 0;
+		for &gone in removed.iter() {
+			if gone {
+				remap.push(None);
+			} else {
+				remap.push(Some(kept));
+				kept += 1;
+			}
+		}
+
+		let mut pairs: Vec<(usize, usize)> = Vec::new();
+		for &(a, b) in self.contact_pairs.iter() {
+			match (remap[a], remap[b]) {
+				(Some(new_a), Some(new_b)) => pairs.push((new_a, new_b)),
+				_ => if let Some(ref mut listener) = self.contact_listener {
+					listener.end_contact(a, b);
+				}
+			}
+		}
+		self.contact_pairs = pairs;
+
+		self.contact_cache = self.contact_cache.drain(..).filter_map(|cached| match (remap[cached.a], remap[cached.b]) {
+			(Some(a), Some(b)) => Some(CachedContact{ a: a, b: b, ..cached }),
+			_ => None
+		}).collect();
+		self.manifold_cache = self.manifold_cache.drain(..).filter_map(|cached| match (remap[cached.a], remap[cached.b]) {
+			(Some(a), Some(b)) => Some(CachedManifold{ a: a, b: b, ..cached }),
+			_ => None
+		}).collect();
+		self.contacts = self.contacts.drain(..).filter_map(|contact| match (remap[contact.body_a], remap[contact.body_b]) {
+			(Some(a), Some(b)) => Some(Contact{ body_a: a, body_b: b, ..contact }),
+			_ => None
+		}).collect();
+
+		{
+			let body_slots = &self.body_slots;
+			let is_removed = |handle: BodyHandle| match body_slots.get(handle.index as usize) {
+				Some(slot) if slot.generation == handle.generation => slot.index.is_some_and(|i| removed[i]),
+				_ => false
+			};
+			for slot in self.joint_slots.iter_mut() {
+				let attached = match slot.joint {
+					Some(ref joint) => is_removed(joint.body_a()) || is_removed(joint.body_b()),
+					None => false
+				};
+				if attached {
+					slot.joint = None;
+					slot.generation = slot.generation.wrapping_add(1);
+				}
+			}
+		}
+
+		// Bumping the generation makes any remaining copies of the handles stale
+		for (i, &gone) in removed.iter().enumerate() {
+			if gone {
+				let handle = self.bodies[i].handle;
+				let slot = &mut self.body_slots[handle.index as usize];
+				slot.generation = slot.generation.wrapping_add(1);
+				slot.index = None;
+				self.free_slots.push(handle.index);
+			}
+		}
+		let mut index = 0;
+		self.bodies.retain(|_| {
+			index += 1;
+			return !removed[index - 1];
+		});
+		for i in 0..self.bodies.len() {
+			self.bodies[i].id = i;
+			self.body_slots[self.bodies[i].handle.index as usize].index = Some(i);
+		}
+	}
+
+	// Joints attached to a body are removed along with it. A gear joint is bound to the
+	// joints it couples here, and panics unless they are revolute or prismatic joints
+	// already in the world.
+	pub fn add_joint<J: Into<Joint>>(&mut self, joint: J) -> JointHandle {
+		let mut joint = joint.into();
+		if let Joint::Gear(ref mut gear) = joint {
+			let bound = match (self.get_joint(gear.joint_1), self.get_joint(gear.joint_2)) {
+				(Some(joint_1), Some(joint_2)) => gear.bind(joint_1, joint_2),
+				_ => false
+			};
+			if !bound {
+				panic!("add_joint: a gear joint needs two revolute or prismatic joints in the world");
+			}
+		}
+		for (i, slot) in self.joint_slots.iter_mut().enumerate() {
+			if slot.joint.is_none() {
+				slot.joint = Some(joint);
+				return JointHandle{ index: i as u32, generation: slot.generation };
+			}
+		}
+		self.joint_slots.push(JointSlot{ generation: 0, joint: Some(joint) });
+		return JointHandle{ index: (self.joint_slots.len() - 1) as u32, generation: 0 };
+	}
+
+	// Removes the joint, returning false if it was already gone.
+	pub fn remove_joint(&mut self, handle: JointHandle) -> bool {
+		match self.joint_slots.get_mut(handle.index as usize) {
+			Some(slot) if slot.generation == handle.generation && slot.joint.is_some() => {
+				slot.joint = None;
+				slot.generation = slot.generation.wrapping_add(1);
+				return true;
+			},
+			_ => return false
+		}
+	}
+
+	pub fn get_joint(&self, handle: JointHandle) -> Option<&Joint> {
+		match self.joint_slots.get(handle.index as usize) {
+			Some(slot) if slot.generation == handle.generation => return slot.joint.as_ref(),
+			_ => return None
+		}
+	}
+
+	pub fn get_joint_mut(&mut self, handle: JointHandle) -> Option<&mut Joint> {
+		match self.joint_slots.get_mut(handle.index as usize) {
+			Some(slot) if slot.generation == handle.generation => return slot.joint.as_mut(),
+			_ => return None
+		}
+	}
+
+	// Every joint in the world with its handle, in slot order.
+	pub fn joints<'a>(&'a self) -> impl Iterator<Item = (JointHandle, &'a Joint)> + 'a {
+		return self.joint_slots.iter().enumerate().filter_map(|(i, slot)| {
+			slot.joint.as_ref().map(|joint| (JointHandle{ index: i as u32, generation: slot.generation }, joint))
+		});
+	}
+
+	// Like joints, for changing motor speeds and the like. Joints can't be removed while
+	// iterating, so collect the handles of those to break and remove them afterwards.
+	pub fn joints_mut<'a>(&'a mut self) -> impl Iterator<Item = (JointHandle, &'a mut Joint)> + 'a {
+		return self.joint_slots.iter_mut().enumerate().filter_map(|(i, slot)| {
+			let generation = slot.generation;
+			slot.joint.as_mut().map(|joint| (JointHandle{ index: i as u32, generation: generation }, joint))
+		});
+	}
+
+	pub fn body(&self, index: usize) -> &Body {
+		return &self.bodies[index];
+	}
+
+	pub fn body_mut(&mut self, index: usize) -> &mut Body {
+		return &mut self.bodies[index];
 	}
 
+	pub fn gravity(&self) -> Vec2 {
+		return self.gravity;
+	}
+
+	// Sleeping bodies are woken so they respond to the new gravity.
+	pub fn set_gravity(&mut self, gravity: Vec2) {
+		self.gravity = gravity;
+		for body in self.bodies.iter_mut() {
+			if body.body_type == BodyType::DynamicBody && !body.is_awake() {
+				body.set_awake(true);
+			}
+		}
+	}
+
+	pub fn velocity_iterations(&self) -> usize {
+		return self.velocity_iterations;
+	}
+
+	// More iterations make stacks and chains of contacts stiffer at the cost of speed.
+	pub fn set_velocity_iterations(&mut self, iterations: usize) {
+		self.velocity_iterations = iterations;
+	}
+
+	pub fn position_iterations(&self) -> usize {
+		return self.position_iterations;
+	}
+
+	// More iterations leave less penetration between resting bodies.
+	pub fn set_position_iterations(&mut self, iterations: usize) {
+		self.position_iterations = iterations;
+	}
+
+	pub fn warm_starting(&self) -> bool {
+		return self.warm_starting;
+	}
+
+	// Starting each step's contact solve from the impulses the last step ended with lets
+	// stacks settle in far fewer iterations. On by default.
+	pub fn set_warm_starting(&mut self, warm_starting: bool) {
+		self.warm_starting = warm_starting;
+		if !warm_starting {
+			self.contact_cache.clear();
+		}
+	}
+
+	pub fn restitution_threshold(&self) -> Scalar {
+		return self.restitution_threshold;
+	}
+
+	// Contacts approaching slower than this are treated as inelastic, so bouncy bodies
+	// settle instead of jittering at rest.
+	pub fn set_restitution_threshold(&mut self, threshold: Scalar) {
+		self.restitution_threshold = threshold;
+	}
+
+	// Switches how pairs that might touch are found. They all find the same pairs, so this
+	// only changes how fast a step is. Panics if the cell size of a spatial hash is not
+	// positive and finite.
+	pub fn set_broad_phase_kind(&mut self, kind: BroadphaseKind) {
+		if let BroadphaseKind::SpatialHash{cell_size} = kind {
+			assert!(cell_size > 0.0 && cell_size.is_finite(), "set_broad_phase_kind: the cell size of a spatial hash must be positive and finite, not {}", cell_size);
+		}
+		self.broad_phase = match kind {
+			BroadphaseKind::BruteForce => Box::new(BruteForceBroadPhase::new()),
+			BroadphaseKind::SortAndSweep => Box::new(DefaultBroadPhase::new()),
+			BroadphaseKind::SpatialHash{cell_size} => Box::new(SpatialHashBroadPhase::new(cell_size))
+		};
+	}
+
+	pub fn friction_mixing(&self) -> MixingRule {
+		return self.friction_mixing;
+	}
+
+	// How the friction of two touching bodies combines. The geometric mean by default, so
+	// either body having no friction makes the contact frictionless.
+	pub fn set_friction_mixing(&mut self, rule: MixingRule) {
+		self.friction_mixing = rule;
+	}
+
+	pub fn restitution_mixing(&self) -> MixingRule {
+		return self.restitution_mixing;
+	}
+
+	// How the restitution of two touching bodies combines. The smaller one by default.
+	pub fn set_restitution_mixing(&mut self, rule: MixingRule) {
+		self.restitution_mixing = rule;
+	}
+
+	pub fn max_linear_correction(&self) -> Scalar {
+		return self.max_linear_correction;
+	}
+
+	// How far each contact may push its bodies apart in one step. A body moved deep into
+	// another then slides out over a few steps instead of jumping clear at once.
+	pub fn set_max_linear_correction(&mut self, max_linear_correction: Scalar) {
+		self.max_linear_correction = max_linear_correction;
+	}
+
+	pub fn baumgarte(&self) -> Scalar {
+		return self.baumgarte;
+	}
+
+	// The share of a contact's penetration pushed out by each position iteration, between
+	// 0 and 1, 0.5 by default. Higher values clear overlap in fewer steps but make stacks
+	// jitter.
+	pub fn set_baumgarte(&mut self, baumgarte: Scalar) {
+		self.baumgarte = baumgarte.clamp(0.0, 1.0);
+	}
+
+	pub fn linear_slop(&self) -> Scalar {
+		return self.linear_slop;
+	}
+
+	// How deep contacts may overlap before they are pushed apart, 1 cm by default. Leaving
+	// a little overlap keeps resting contacts touching from one step to the next.
+	pub fn set_linear_slop(&mut self, linear_slop: Scalar) {
+		self.linear_slop = linear_slop.max(0.0);
+	}
+
+	pub fn max_translation(&self) -> Scalar {
+		return self.max_translation;
+	}
+
+	// How far a body may move in one step, 2 metres by default. Faster bodies are slowed
+	// down to it, which keeps a bad contact from flinging them across the world.
+	pub fn set_max_translation(&mut self, max_translation: Scalar) {
+		self.max_translation = max_translation;
+	}
+
+	pub fn max_rotation(&self) -> Scalar {
+		return self.max_rotation;
+	}
+
+	// How far a body may turn in one step, a quarter turn by default.
+	pub fn set_max_rotation(&mut self, max_rotation: Scalar) {
+		self.max_rotation = max_rotation;
+	}
+
+	pub fn polygon_skin(&self) -> Scalar {
+		return self.polygon_skin;
+	}
+
+	// How far out from its faces a polygon starts touching things. Contacts that are
+	// cushioned by the skin stay put instead of coming and going as a resting body
+	// settles. Zero collides polygons exactly as they are.
+	pub fn set_polygon_skin(&mut self, skin: Scalar) {
+		self.polygon_skin = skin.max(0.0);
+	}
+
+	// The linear and angular speed below which a body counts as still, and how many
+	// seconds it has to stay still to fall asleep.
+	pub fn sleep_thresholds(&self) -> (Scalar, Scalar, Scalar) {
+		return (self.linear_sleep_tolerance, self.angular_sleep_tolerance, self.time_to_sleep);
+	}
+
+	// The defaults suit bodies measured in metres, so a world in other units needs
+	// thresholds scaled to match. A time of zero puts bodies to sleep on the first step
+	// they are still.
+	pub fn set_sleep_thresholds(&mut self, linear: Scalar, angular: Scalar, time: Scalar) {
+		self.linear_sleep_tolerance = linear.max(0.0);
+		self.angular_sleep_tolerance = angular.max(0.0);
+		self.time_to_sleep = time.max(0.0);
+	}
+
+	pub fn active_region(&self) -> Option<AABB> {
+		return self.active_region;
+	}
+
+	// Only simulates bodies whose bounds overlap the region, say around the player. The
+	// rest keep their place, velocity and any forces applied to them, and neither move
+	// nor collide until the region takes them in again. Static bodies are always there
+	// for the others to hit. None simulates the whole world.
+	pub fn set_active_region(&mut self, region: Option<AABB>) {
+		self.active_region = region;
+	}
+
+	pub fn max_sub_step(&self) -> Scalar {
+		return self.max_sub_step;
+	}
+
+	// Makes step split a time step longer than this into equal sub steps no longer than
+	// it, so a frame time spike after a pause doesn't make the solver blow up or let bodies
+	// tunnel. Zero, the default, always takes the time step in one go.
+	pub fn set_max_sub_step(&mut self, max_sub_step: Scalar) {
+		self.max_sub_step = max_sub_step.max(0.0);
+	}
+
+	pub fn set_contact_listener(&mut self, listener: Box<dyn ContactListener>) {
+		self.contact_listener = Some(listener);
+	}
+
+	// Removes every body and joint, keeping gravity, the iteration counts and the other
+	// settings, so a level can be restarted in the same world. Handles to the removed bodies
+	// and joints stay invalid, and touching pairs get their end contacts first.
 	pub fn clear(&mut self) {
+		if let Some(ref mut listener) = self.contact_listener {
+			for &(a, b) in self.contact_pairs.iter() {
+				listener.end_contact(a, b);
+			}
+		}
+		for body in self.bodies.iter() {
+			let slot = &mut self.body_slots[body.handle.index as usize];
+			slot.generation = slot.generation.wrapping_add(1);
+			slot.index = None;
+			self.free_slots.push(body.handle.index);
+		}
 		self.bodies.clear();
+		self.contact_pairs.clear();
+		self.contact_cache.clear();
+		self.manifold_cache.clear();
+		self.contacts.clear();
+		self.static_proxies.clear();
+		self.static_owners.clear();
+		self.static_key.clear();
+		for slot in self.joint_slots.iter_mut() {
+			if slot.joint.is_some() {
+				slot.joint = None;
+				slot.generation = slot.generation.wrapping_add(1);
+			}
+		}
 	}
 
-	pub fn step(&mut self, time_step: f32) {
-		let len = self.bodies.len();
-		for i in 0..len {
-			self.bodies[i].integrate(time_step, self.gravity);
+	// Indices of the bodies whose bounding boxes overlap, each pair once with the lower
+	// index first.
+	pub fn overlapping_pairs(&self) -> Vec<(usize, usize)> {
+		return self.broad_phase.run(&self.bodies);
+	}
+
+	// The closest body hit by the ray origin + direction * t for 0 <= t <= max_fraction.
+	pub fn ray_cast(&self, origin: Vec2, direction: Vec2, max_fraction: Scalar) -> Option<RayCastHit> {
+		let mut closest: Option<RayCastHit> = None;
+		let mut max_fraction = max_fraction;
+		for body in self.bodies.iter() {
+			// Cast in the body's frame so rotated shapes are hit where they are drawn
+			let transform = body.transform();
+			let local_origin = transform.apply_inverse(origin);
+			let local_direction = transform.rotation.inv_rotate(direction);
+			for shape in body.shapes() {
+				if let Some(hit) = shape.ray_cast(Vec2::new_zero(), local_origin, local_direction, max_fraction) {
+					max_fraction = hit.fraction;
+					closest = Some(RayCastHit{ body: body.id, point: origin + direction.multiply(hit.fraction),
+						normal: transform.rotation.rotate(hit.normal), fraction: hit.fraction });
+				}
+			}
 		}
+		return closest;
+	}
 
-		let pairs = self.broad_phase.run(&self.bodies);
-		let collider_results = self.narrow_phase.run(&pairs);
-		let mut manifolds: Vec<Manifold> = Vec::new();
-		for result in collider_results.iter() {
-			if result.is_colliding {
-				match result.manifold {
-					Some(ref manifold) => {
-						manifolds.push((*manifold).clone());
-					},
-					None => {
-						//Do nothing
-					}
+	// Indices of every body whose bounding box overlaps the region.
+	pub fn query_aabb(&self, region: AABB) -> Vec<usize> {
+		return self.bodies_in(region).map(|body| body.id).collect();
+	}
+
+	pub fn dynamic_bodies<'a>(&'a self) -> impl Iterator<Item = &'a Body> + 'a {
+		return self.bodies.iter().filter(|body| body.body_type == BodyType::DynamicBody);
+	}
+
+	pub fn static_bodies<'a>(&'a self) -> impl Iterator<Item = &'a Body> + 'a {
+		return self.bodies.iter().filter(|body| body.body_type == BodyType::StaticBody);
+	}
+
+	// Every body whose bounding box overlaps the region, like query_aabb.
+	pub fn bodies_in<'a>(&'a self, region: AABB) -> impl Iterator<Item = &'a Body> + 'a {
+		return self.bodies.iter().filter(move |body| body.aabb().overlaps(&region));
+	}
+
+	// The first body with a shape containing the point.
+	pub fn query_point(&self, world_point: Vec2) -> Option<usize> {
+		for body in self.bodies.iter() {
+			let local_point = body.transform().apply_inverse(world_point);
+			if body.shapes().any(|shape| shape.contains_point(Vec2::new_zero(), local_point)) {
+				return Some(body.id);
+			}
+		}
+		return None;
+	}
+
+	// The body with the shape nearest to the point, no further away than max_distance.
+	// Unlike query_point the point doesn't have to be inside a shape, and one that is
+	// counts as zero away. Chains count as the convex hull of their points, as in distance.
+	pub fn closest_body(&self, point: Vec2, max_distance: Scalar) -> Option<usize> {
+		let reach = Vec2::new(max_distance, max_distance);
+		let region = AABB::new(point - reach, point + reach);
+		let target = Shape::CircleShape{center: Vec2::new_zero(), radius: 0.0};
+		let mut closest: Option<(usize, Scalar)> = None;
+		for body in self.bodies_in(region) {
+			for shape in body.shapes() {
+				let output = distance(shape, body.transform(), &target, Transform::new(point, 0.0));
+				if output.distance > max_distance {
+					continue;
+				}
+				match closest {
+					Some((_, closest_distance)) if closest_distance <= output.distance => {},
+					_ => closest = Some((body.id, output.distance))
 				}
 			}
 		}
-		self.resolve_collisions(&manifolds);
+		return closest.map(|(body, _)| body);
 	}
-}
 
-impl CollisionResolution for World {
-    fn resolve_collisions(&mut self, manifolds: &Vec<Manifold>) {
-        for m in manifolds.iter() {
-            let manifold = m.clone();
-            let body_a = manifold.body_a;
-            let body_b = manifold.body_b;
-            let mut rv = body_b.velocity - body_a.velocity;
-            let vel_along_normal = rv.dot(manifold.normal);
-
-            if vel_along_normal > 0.0 {
-                let a_restitution = body_a.restitution;
-                let b_restitution = body_b.restitution;
-                let e = a_restitution.min(b_restitution);
-
-                let mut j = vel_along_normal * (-(1.0 + e));
-                if body_a.mass != 0.0 && body_b.mass != 0.0 {
-                    j /= 1.0 / body_a.mass + (1.0 / body_b.mass);
-                } else if body_a.mass != 0.0 && body_b.mass == 0.0 {
-                    j /= 1.0 / body_a.mass;
-                } else if body_a.mass == 0.0 && body_b.mass != 0.0 {
-                    j /= 1.0 / body_b.mass;
-                }
+	// Pushes every dynamic body whose position is within radius of the center straight
+	// away from it, like an explosion. The impulse is strength at the center and falls off
+	// linearly to nothing at the radius, so lighter bodies fly further. A body exactly at
+	// the center has no direction to go and is left alone.
+	pub fn apply_radial_impulse(&mut self, center: Vec2, radius: Scalar, strength: Scalar) {
+		if radius.is_nan() || radius <= 0.0 {
+			return;
+		}
+		for body in self.bodies.iter_mut().filter(|body| body.body_type == BodyType::DynamicBody && body.is_enabled()) {
+			let mut direction = body.position - center;
+			let distance = direction.normalize();
+			if distance == 0.0 || distance >= radius {
+				continue;
+			}
+			body.apply_impulse(direction.multiply(strength * (1.0 - distance / radius)));
+		}
+	}
 
-                let impulse = manifold.normal.multiply(j);
-                if body_a.mass != 0.0 {
-					self.bodies[body_a.id].velocity = body_a.velocity - impulse.multiply(1.0 / body_a.mass);
-                } if body_b.mass != 0.0 {
-					self.bodies[body_b.id].velocity = body_b.velocity - impulse.multiply(1.0 / body_b.mass);
-                }
+	// Fills the box with fluid of the given density, in mass per unit area like body
+	// densities, which then holds up every dynamic body dipping into it on each step. A
+	// body lighter than the fluid floats with as much of its area under the surface as it
+	// takes to match its mass, a heavier one sinks. Returns the region's index for
+	// remove_buoyancy_region.
+	pub fn add_buoyancy_region(&mut self, aabb: AABB, density: Scalar, linear_drag: Scalar) -> usize {
+		self.buoyancy_regions.push(BuoyancyRegion::new(aabb, density, linear_drag));
+		return self.buoyancy_regions.len() - 1;
+	}
 
-                let k_slop = 0.01;
-                let percent = 0.5;
-                let maximum = (manifold.penetration - k_slop).max(0.0);
-                let body_a_inv_mass = 1.0 / body_a.mass;
-                let body_b_inv_mass = 1.0 / body_b.mass;
-                let mut correction = Vec2::new(0.0, 0.0);
-                if body_b_inv_mass.is_infinite() || body_b_inv_mass.is_nan() {
-                    correction = manifold.normal.multiply(maximum / (body_a_inv_mass) * percent);
-                } else if body_a_inv_mass.is_infinite() || body_a_inv_mass.is_nan() {
-                    correction = manifold.normal.multiply(maximum / (body_b_inv_mass) * percent);
-                } else {
-                    correction = manifold.normal.multiply(maximum / (body_a_inv_mass + body_b_inv_mass) * percent);
-                }
+	// Regions after the removed one move down an index.
+	pub fn remove_buoyancy_region(&mut self, index: usize) -> bool {
+		if index >= self.buoyancy_regions.len() {
+			return false;
+		}
+		self.buoyancy_regions.remove(index);
+		return true;
+	}
 
-                if !body_a_inv_mass.is_infinite() && !body_a_inv_mass.is_nan() && body_a_inv_mass > 0.0 {
-					self.bodies[body_a.id].position = body_a.position + correction.multiply(body_a_inv_mass);
-                }
+	pub fn buoyancy_regions(&self) -> &[BuoyancyRegion] {
+		return &self.buoyancy_regions;
+	}
 
-                if !body_b_inv_mass.is_infinite() && !body_b_inv_mass.is_nan() && body_b_inv_mass > 0.0 {
-					self.bodies[body_b.id].position = body_b.position - correction.multiply(body_b_inv_mass);
-                }
-            }
-        }
+	// Welds static boxes that meet edge to edge or overlap, such as the tiles of a level,
+	// into larger boxes, first along rows and then rows of the same span into rectangles.
+	// That leaves fewer bodies and no seams between tiles for sliding bodies to catch on,
+	// while covering the same area. Only unturned PolygonShape boxes without fixtures or
+	// joints take part, and only with boxes of the same friction, restitution, filter,
+	// sensor flag and user data. The lowest index body of each group keeps its handle and
+	// takes the welded box, the others are removed. Returns how many bodies were removed.
+	pub fn optimize_static_geometry(&mut self) -> usize {
+		let joined: Vec<usize> = self.joined_pairs().iter().flat_map(|&(a, b)| vec![a, b]).collect();
+		let mut boxes: Vec<StaticBox> = Vec::new();
+		for (i, body) in self.bodies.iter().enumerate() {
+			if body.body_type != BodyType::StaticBody || !body.is_enabled() || !body.fixtures.is_empty() || joined.contains(&i) {
+				continue;
+			}
+			if let Some(aabb) = axis_aligned_box(body) {
+				let key = (body.category_bits, body.mask_bits, body.group_index, body.is_sensor, body.user_data,
+					(body.friction as f64).to_bits(), (body.restitution as f64).to_bits());
+				boxes.push(StaticBox{ key: key, aabb: aabb, bodies: vec![i] });
+			}
+		}
+		let rows = weld_boxes(boxes, true);
+		let rectangles = weld_boxes(rows, false);
+
+		let mut removed = vec![false; self.bodies.len()];
+		let mut count = 0;
+		for rectangle in rectangles.into_iter().filter(|rectangle| rectangle.bodies.len() > 1) {
+			let keep = rectangle.bodies.iter().cloned().min().unwrap_or(0);
+			let extents = rectangle.aabb.extents();
+			let center = rectangle.aabb.center();
+			{
+				let body = &mut self.bodies[keep];
+				body.set_shape(Shape::box_shape(extents.x, extents.y));
+				body.set_transform(center, 0.0);
+				body.previous_position = center;
+				body.previous_angle = 0.0;
+			}
+			for &i in rectangle.bodies.iter().filter(|&&i| i != keep) {
+				removed[i] = true;
+				count += 1;
+			}
+		}
+		if count > 0 {
+			self.remove_marked(&removed);
+		}
+		return count;
+	}
+
+	// Advances the world by time_step seconds:
+	//  1. Integrate each body with semi-implicit Euler, velocity first and then position
+	//     from the new velocity, and sweep bullets against static geometry.
+	//  2. Find touching pairs, report begin and end contacts to the listener and let its
+	//     pre_solve switch contacts off for the step.
+	//  3. Wake sleeping bodies that are touched, then split the bodies into islands joined
+	//     by contacts and joints and solve each island's joints and contacts on their own
+	//     with velocity_iterations impulse passes and position_iterations correction passes.
+	//     Bodies pushed past their max_linear_speed are slowed back down to it, and the
+	//     listener's post_solve then hears what each contact was pushed with.
+	//  4. Put islands to sleep whose bodies have all been still long enough.
+	// Bodies, pairs and joints are always visited in index order and there is no hashing,
+	// so the same world stepped with the same time steps gives bit identical results.
+	// Debug builds panic if a body's motion is already NaN or infinite, which otherwise
+	// spreads through every contact and joint it touches.
+	// With a max sub step set, a longer time step is taken as several equal steps and the
+	// profile adds them up.
+	pub fn step(&mut self, time_step: Scalar) -> StepResult {
+		for body in self.bodies.iter_mut() {
+			body.previous_position = body.position;
+			body.previous_angle = body.angle;
+		}
+		if self.max_sub_step <= 0.0 || time_step <= self.max_sub_step {
+			return self.single_step(time_step);
+		}
+		// Allow for rounding, so 0.25s at 1/60s is 15 steps and not 16
+		let count = ((time_step / self.max_sub_step) - 1.0e-4).ceil().max(1.0) as usize;
+		let mut profile = Profile::default();
+		let mut result = StepResult::default();
+		for _ in 0..count {
+			let sub_step = self.single_step(time_step / count as Scalar);
+			profile.add(self.profile);
+			result.contacts_begun += sub_step.contacts_begun;
+			result.contacts_ended += sub_step.contacts_ended;
+			result.iterations_used += sub_step.iterations_used;
+			result.bodies_asleep = sub_step.bodies_asleep;
+		}
+		self.profile = profile;
+		return result;
+	}
+
+	// Runs as many steps of fixed_step as fit in the frame time plus what was left over
+	// from earlier frames, the usual fixed time step loop. Frames longer than 0.2 seconds
+	// count as 0.2 seconds, so the simulation slows down rather than falling further and
+	// further behind. Panics if the fixed step is not positive and finite.
+	pub fn advance(&mut self, frame_time: Scalar, fixed_step: Scalar) -> Advance {
+		assert!(fixed_step > 0.0 && fixed_step.is_finite(), "advance: the fixed step must be positive and finite, not {}", fixed_step);
+		self.accumulator += frame_time.clamp(0.0, MAX_FRAME_TIME);
+		let mut steps = 0;
+		// Leave room for rounding, so a frame of exactly three steps runs three
+		while self.accumulator >= fixed_step * (1.0 - 1.0e-4) {
+			self.step(fixed_step);
+			self.accumulator = (self.accumulator - fixed_step).max(0.0);
+			steps += 1;
+		}
+		return Advance{ steps: steps, leftover: self.accumulator, alpha: self.accumulator / fixed_step };
+	}
+
+	fn single_step(&mut self, time_step: Scalar) -> StepResult {
+		if cfg!(debug_assertions) {
+			for (i, body) in self.bodies.iter().enumerate() {
+				assert!(body.is_finite(), "step: body {} has a non finite position {:?}, angle {}, velocity {:?} or angular velocity {}",
+					i, body.position, body.angle, body.velocity, body.angular_velocity);
+			}
+		}
+		let step_start = Instant::now();
+		let mut lap_start = step_start;
+		let len = self.bodies.len();
+		let starts: Vec<(Vec2, Scalar)> = self.bodies.iter().map(|body| (body.position, body.angle)).collect();
+		self.frozen = match self.active_region {
+			Some(region) => self.bodies.iter().map(|body| body.body_type != BodyType::StaticBody && !body.aabb().overlaps(&region)).collect(),
+			None => vec![false; len]
+		};
+		self.apply_buoyancy();
+		// Static bodies never move by themselves, so their bounds stay as set_transform left them
+		for (i, body) in self.bodies.iter_mut().enumerate() {
+			if body.body_type != BodyType::StaticBody && body.is_enabled() && !self.frozen[i] {
+				body.integrate_limited(time_step, self.gravity, self.max_translation, self.max_rotation);
+				body.update_aabb();
+			}
+		}
+		self.sweep_bullets(&starts);
+		let integrate_time = lap(&mut lap_start);
+
+		// Each fixture collides on its own, as a copy of its body with the fixture's shape.
+		// Both phases see the polygons grown by the skin, so resting contacts are found
+		// before the shapes themselves touch. The copies of static bodies come first and are
+		// reused from the last step, so still level geometry is neither copied nor measured.
+		self.refresh_static_proxies();
+		let mut skinned: Vec<Body> = ::std::mem::take(&mut self.static_proxies);
+		let mut owners: Vec<(usize, Option<usize>)> = ::std::mem::take(&mut self.static_owners);
+		let static_count = skinned.len();
+		for (i, body) in self.bodies.iter().enumerate().filter(|&(_, body)| body.body_type != BodyType::StaticBody) {
+			let fixtures = ::std::iter::once(None).chain((0..body.fixtures.len()).map(Some));
+			for fixture in fixtures {
+				let skinned_body = if body.fixtures.is_empty() { self.with_skin(body) } else { self.with_skin(&body.fixture_body(fixture)) };
+				skinned.push(skinned_body);
+				owners.push((i, fixture));
+			}
+		}
+		let overlapping = self.broad_phase.run(&skinned);
+		let broad_phase_time = lap(&mut lap_start);
+
+		// Pairs are taken in body and fixture order, whatever order the copies are in
+		let mut indices: Vec<(usize, usize)> = overlapping.into_iter()
+			.map(|(p, q)| if owners[p] <= owners[q] { (p, q) } else { (q, p) })
+			.filter(|&(p, q)| owners[p].0 != owners[q].0 && !self.frozen[owners[p].0] && !self.frozen[owners[q].0] &&
+				skinned[p].should_collide(&skinned[q]) && !self.joint_prevents_collision(owners[p].0, owners[q].0))
+			.collect();
+		indices.sort_by_key(|&(p, q)| (owners[p], owners[q]));
+		let collider_results = self.cached_narrow_phase(&indices, &skinned, &owners);
+		let mut contacts: Vec<Contact> = Vec::new();
+		for (&(p, q), result) in indices.iter().zip(collider_results.iter()) {
+			if result.is_colliding {
+				match result.manifold {
+					Some(ref manifold) => {
+						let ((a, fixture_a), (b, fixture_b)) = (owners[p], owners[q]);
+						contacts.push(Contact{ body_a: a, body_b: b, fixture_a: fixture_a, fixture_b: fixture_b, manifold: (*manifold).clone() });
+					},
+					None => {
+						//Do nothing
+					}
+				}
+			}
+		}
+		skinned.truncate(static_count);
+		owners.truncate(static_count);
+		self.static_proxies = skinned;
+		self.static_owners = owners;
+		let (contacts_begun, contacts_ended) = self.update_contact_pairs(&contacts);
+		let narrow_phase_time = lap(&mut lap_start);
+
+		// Contacts the listener turns down stay in the contact list but are not solved
+		let solved: Option<Vec<Contact>> = self.contact_listener.as_mut().map(|listener| contacts.iter()
+				.filter(|contact| listener.pre_solve(contact.body_a, contact.body_b, &contact.manifold))
+				.cloned().collect());
+
+		self.wake_touched_bodies();
+		self.prepare_joints(time_step);
+		self.resolve_collisions(solved.as_ref().unwrap_or(&contacts));
+		for body in self.bodies.iter_mut().filter(|body| body.body_type == BodyType::DynamicBody) {
+			body.limit_speed();
+		}
+		self.record_impulses(&mut contacts);
+		self.report_impulses(&contacts);
+		self.update_sleep(time_step);
+
+		// The solver moves bodies, so bring the bounding boxes up to date for queries
+		for body in self.bodies.iter_mut().filter(|body| body.body_type != BodyType::StaticBody) {
+			body.update_aabb();
+		}
+		let solve_time = lap(&mut lap_start);
+		self.contacts = contacts;
+
+		self.profile = Profile{ step: microseconds(step_start.elapsed()), integrate: integrate_time,
+			broad_phase: broad_phase_time, narrow_phase: narrow_phase_time, solve: solve_time, body_count: len };
+		let bodies_asleep = self.bodies.iter().filter(|body| body.body_type != BodyType::StaticBody && !body.is_awake()).count();
+		return StepResult{ contacts_begun: contacts_begun, contacts_ended: contacts_ended, bodies_asleep: bodies_asleep as u32,
+			iterations_used: self.solver_passes };
+	}
+
+	// Every touching pair from the last step, in pair order, with its world space points,
+	// normal and penetration. Bodies removed since then are left out.
+	pub fn contacts(&self) -> &[Contact] {
+		return &self.contacts;
+	}
+
+	// The bodies touching the given one as of the last step, in index order. Sensors
+	// count, so this also tells what a sensor overlaps.
+	pub fn bodies_touching(&self, body: usize) -> Vec<usize> {
+		return self.contact_pairs.iter()
+			.filter_map(|&(a, b)| if a == body { Some(b) } else if b == body { Some(a) } else { None })
+			.collect();
+	}
+
+	// How many bodies the given one touched on the last step plus how many joints it has.
+	pub fn constraint_count(&self, body: usize) -> usize {
+		let touching = self.contact_pairs.iter().filter(|&&(a, b)| a == body || b == body).count();
+		let joined = self.joined_pairs().iter().filter(|&&(a, b)| a == body || b == body).count();
+		return touching + joined;
+	}
+
+	// The dynamic bodies that touched or were joined to each other as of the last step, as
+	// lists of body indices. Each island falls asleep as a whole. Static and kinematic
+	// bodies are on no island.
+	pub fn islands(&self) -> Vec<Vec<usize>> {
+		let mut pairs = self.contact_pairs.clone();
+		pairs.extend(self.joined_pairs());
+		return self.islands_of(&pairs).groups().into_iter()
+			.filter(|group| self.bodies[group[0]].body_type == BodyType::DynamicBody)
+			.collect();
+	}
+
+	// The average normal of the last step's contacts holding the body up, counting only
+	// those within max_slope of straight up against gravity, or None if there are none.
+	// Walls and too steep slopes are left out, so a body in a corner still finds the floor.
+	pub fn ground_normal(&self, body: usize, max_slope: Scalar) -> Option<Vec2> {
+		let mut up = -self.gravity;
+		if up.normalize() == 0.0 {
+			return None;
+		}
+		let min_dot = max_slope.cos();
+		let mut sum = Vec2::new_zero();
+		for contact in self.contacts.iter() {
+			// Manifold normals point from b to a, so away from whatever the body stands on
+			let normal = if contact.body_a == body {
+				contact.manifold.normal
+			} else if contact.body_b == body {
+				-contact.manifold.normal
+			} else {
+				continue;
+			};
+			if self.material(contact.body_a, contact.fixture_a).2 || self.material(contact.body_b, contact.fixture_b).2 {
+				continue;
+			}
+			if normal.dot(up) >= min_dot {
+				sum += normal;
+			}
+		}
+		if sum.normalize() == 0.0 {
+			return None;
+		}
+		return Some(sum);
+	}
+
+	// The total kinetic energy of the dynamic bodies, 0.5 * m * v² + 0.5 * I * ω² each.
+	// A world left to itself should only lose energy, so this growing from step to step
+	// points at an unstable solve.
+	pub fn kinetic_energy(&self) -> Scalar {
+		return self.dynamic_bodies().map(|body| {
+			let inertia = if body.fixed_rotation { 0.0 } else { body.inertia };
+			0.5 * body.mass * body.velocity.length_squared() + 0.5 * inertia * body.angular_velocity * body.angular_velocity
+		}).sum();
+	}
+
+	// The outline of each body's shape and fixtures in world coordinates along with where
+	// it touched others in the last step, for renderers that shouldn't depend on Shape.
+	pub fn debug_draw_data(&self) -> Vec<DebugShape> {
+		let mut shapes: Vec<DebugShape> = Vec::new();
+		for body in self.bodies.iter() {
+			let fixtures = ::std::iter::once(None).chain((0..body.fixtures.len()).map(Some));
+			for (fixture, shape) in fixtures.zip(body.shapes()) {
+				let contact_points = self.contacts.iter()
+					.filter(|contact| (contact.body_a == body.id && contact.fixture_a == fixture) || (contact.body_b == body.id && contact.fixture_b == fixture))
+					.flat_map(|contact| contact.manifold.points.iter().map(|point| point.position))
+					.collect();
+				shapes.push(DebugShape::with_shape(body, shape, contact_points));
+			}
+		}
+		return shapes;
+	}
+
+	// Timings of the last call to step.
+	pub fn last_profile(&self) -> Profile {
+		return self.profile;
+	}
+
+	// Moves each bullet back to where its motion this step first reaches static geometry,
+	// and takes away the velocity that would carry it further in. Each fixture of the
+	// bullet is swept against each fixture of the geometry, turning as well as moving, so
+	// the corners of a spinning box are caught too. The turn is taken the short way round,
+	// which max_rotation keeps true.
+	fn sweep_bullets(&mut self, starts: &[(Vec2, Scalar)]) {
+		for (i, &(start, start_angle)) in starts.iter().enumerate() {
+			if !self.bodies[i].bullet || self.bodies[i].body_type != BodyType::DynamicBody {
+				continue;
+			}
+			let (end, end_angle) = (self.bodies[i].position, self.bodies[i].angle);
+			if start == end && start_angle == end_angle {
+				continue;
+			}
+			let start_transform = Transform::new(start, start_angle);
+			let swept_aabb = self.bodies[i].shapes()
+				.fold(self.bodies[i].aabb(), |aabb, shape| aabb.combine(&shape.compute_transformed_aabb(start_transform)));
+			let sweep = Sweep::new(start_transform, Transform::new(end, end_angle));
+			let pieces = solid_fixtures(&self.bodies[i]);
+
+			let mut first_hit: Option<(Scalar, Vec2)> = None;
+			for j in 0..self.bodies.len() {
+				let other = &self.bodies[j];
+				if other.body_type == BodyType::DynamicBody || !other.aabb().overlaps(&swept_aabb) {
+					continue;
+				}
+				let other_sweep = Sweep::fixed(Transform::new(other.position, other.angle));
+				for other_piece in solid_fixtures(other).iter() {
+					for piece in pieces.iter().filter(|piece| piece.should_collide(other_piece)) {
+						let output = time_of_impact(&piece.shape, sweep, &other_piece.shape, other_sweep);
+						if output.state != TOIState::Touching {
+							// Already overlapping before moving is left to the contact solver
+							continue;
+						}
+						match first_hit {
+							Some((first_fraction, _)) if first_fraction <= output.fraction => {},
+							_ => first_hit = Some((output.fraction, output.normal))
+						}
+					}
+				}
+			}
+
+			if let Some((fraction, normal)) = first_hit {
+				let body = &mut self.bodies[i];
+				body.position = start + (end - start).multiply(fraction);
+				body.angle = start_angle + (end_angle - start_angle) * fraction;
+				let vel_along_normal = body.velocity.dot(normal);
+				if vel_along_normal < 0.0 {
+					let e = if -vel_along_normal > self.restitution_threshold { body.restitution } else { 0.0 };
+					body.velocity -= normal.multiply(vel_along_normal * (1.0 + e));
+				}
+				body.update_aabb();
+			}
+		}
+	}
+
+	// Adds the lift and drag of every buoyancy region to the awake dynamic bodies in it,
+	// for the integration that follows. A body at rest in the fluid may sleep there.
+	fn apply_buoyancy(&mut self) {
+		let gravity = self.gravity;
+		for region in self.buoyancy_regions.iter() {
+			for (i, body) in self.bodies.iter_mut().enumerate() {
+				if body.body_type != BodyType::DynamicBody || !body.is_awake() || !body.is_enabled() || self.frozen[i] || !body.aabb().overlaps(&region.aabb) {
+					continue;
+				}
+				let transform = body.transform();
+				let mut area = 0.0;
+				let mut moment = Vec2::new_zero();
+				for shape in body.shapes() {
+					let (shape_area, centroid) = region.submerged(shape, transform);
+					area += shape_area;
+					moment += centroid.multiply(shape_area);
+				}
+				if area <= 0.0 {
+					continue;
+				}
+				let centroid = moment.divide(area);
+				let velocity = body.velocity + Vec2::scalar_cross(body.angular_velocity, centroid - body.position);
+				let force = gravity.multiply(-region.density * area) - velocity.multiply(region.linear_drag * area);
+				body.apply_force_at_point(force, centroid);
+			}
+		}
+	}
+
+	// A sleeping body touched by an awake one, or by a moving kinematic body, has to wake
+	// up to respond.
+	fn wake_touched_bodies(&mut self) {
+		let is_active = |body: &Body| match body.body_type {
+			BodyType::StaticBody => false,
+			BodyType::KinematicBody => body.velocity.length_squared() > 0.0 || body.angular_velocity != 0.0,
+			BodyType::DynamicBody => body.is_awake()
+		};
+		let mut pairs: Vec<(usize, usize)> = self.contact_pairs.iter().cloned()
+			.filter(|&(a, b)| !self.bodies[a].is_sensor && !self.bodies[b].is_sensor)
+			.collect();
+		pairs.extend(self.joined_pairs());
+		for &(a, b) in pairs.iter() {
+			let a_active = is_active(&self.bodies[a]);
+			let b_active = is_active(&self.bodies[b]);
+			if a_active && !self.bodies[b].is_awake() {
+				self.bodies[b].set_awake(true);
+			} else if b_active && !self.bodies[a].is_awake() {
+				self.bodies[a].set_awake(true);
+			}
+		}
+	}
+
+	// Puts bodies to sleep once they have been still for long enough. Bodies on the same
+	// island only sleep together, otherwise the one still awake would keep waking the rest.
+	fn update_sleep(&mut self, time_step: Scalar) {
+		let mut can_sleep: Vec<bool> = Vec::with_capacity(self.bodies.len());
+		for (i, body) in self.bodies.iter_mut().enumerate() {
+			// Held still by the active region rather than at rest, so left as it is
+			if self.frozen[i] {
+				can_sleep.push(false);
+				continue;
+			}
+			body.update_sleep_time_below(time_step, self.linear_sleep_tolerance, self.angular_sleep_tolerance);
+			// Only dynamic bodies that were still this step, so a time to sleep of zero
+			// neither stops kinematic bodies nor puts moving ones to sleep
+			can_sleep.push(body.body_type == BodyType::DynamicBody && body.sleep_time() > 0.0 &&
+				body.sleep_time() >= self.time_to_sleep);
+		}
+
+		let mut pairs = self.contact_pairs.clone();
+		pairs.extend(self.joined_pairs());
+		let mut islands = self.islands_of(&pairs);
+		let mut island_can_sleep = vec![true; self.bodies.len()];
+		for (i, &body_can_sleep) in can_sleep.iter().enumerate() {
+			let root = islands.find(i);
+			island_can_sleep[root] = island_can_sleep[root] && body_can_sleep;
+		}
+
+		for (i, body) in self.bodies.iter_mut().enumerate() {
+			if island_can_sleep[islands.find(i)] && body.is_awake() {
+				body.set_awake(false);
+			}
+		}
+	}
+
+	// The islands of dynamic bodies joined by the given pairs of body indices.
+	fn islands_of(&self, pairs: &[(usize, usize)]) -> Islands {
+		let mut islands = Islands::new(self.bodies.len());
+		for &(a, b) in pairs.iter() {
+			if self.bodies[a].body_type == BodyType::DynamicBody && self.bodies[b].body_type == BodyType::DynamicBody {
+				islands.join(a, b);
+			}
+		}
+		return islands;
+	}
+
+	// Indices of the bodies of every joint.
+	fn joined_pairs(&self) -> Vec<(usize, usize)> {
+		let mut pairs: Vec<(usize, usize)> = Vec::new();
+		for slot in self.joint_slots.iter() {
+			if let Some(ref joint) = slot.joint {
+				if let (Some(a), Some(b)) = (self.body_index(joint.body_a()), self.body_index(joint.body_b())) {
+					pairs.push((a, b));
+				}
+			}
+		}
+		return pairs;
+	}
+
+	// The narrow phase result for each pair of fixtures, in the same order, given as
+	// indices into skinned and owners. A pair of polygons that has the same shapes and has
+	// barely moved relative to each other since its manifold was found reuses that
+	// manifold, moved along with body a, instead of clipping again.
+	fn cached_narrow_phase(&mut self, indices: &[(usize, usize)], skinned: &[Body], owners: &[(usize, Option<usize>)]) -> Vec<ColliderResult> {
+		let mut results: Vec<Option<ColliderResult>> = Vec::new();
+		let mut manifold_cache: Vec<CachedManifold> = Vec::new();
+		let mut fresh: Vec<usize> = Vec::new();
+		let key = |p: usize, q: usize| (owners[p].0, owners[q].0, owners[p].1, owners[q].1);
+		let cached_key = |cached: &CachedManifold| (cached.a, cached.b, cached.fixture_a, cached.fixture_b);
+		for (i, &(p, q)) in indices.iter().enumerate() {
+			let (points_a, points_b) = match (&skinned[p].shape, &skinned[q].shape) {
+				(Shape::PolygonShape{points: points_a}, Shape::PolygonShape{points: points_b}) => (points_a, points_b),
+				_ => {
+					results.push(None);
+					fresh.push(i);
+					continue;
+				}
+			};
+			let transform_a = skinned[p].transform();
+			let relative_position = transform_a.apply_inverse(skinned[q].position);
+			let relative_angle = skinned[q].angle - skinned[p].angle;
+			let cached = self.manifold_cache.binary_search_by_key(&key(p, q), &cached_key).ok()
+				.map(|index| &self.manifold_cache[index])
+				.filter(|cached| (cached.relative_position - relative_position).length() <= MANIFOLD_CACHE_DISTANCE &&
+					(cached.relative_angle - relative_angle).abs() <= MANIFOLD_CACHE_ANGLE &&
+					&cached.points_a == points_a && &cached.points_b == points_b);
+			match cached {
+				Some(cached) => {
+					let manifold = cached.manifold.as_ref().map(|local| to_frame(local, |point| transform_a.apply(point), |normal| transform_a.rotation.rotate(normal)));
+					results.push(Some(ColliderResult::new(manifold.clone(), manifold.is_some())));
+					manifold_cache.push(cached.clone());
+				},
+				None => {
+					results.push(None);
+					fresh.push(i);
+					let ((a, fixture_a), (b, fixture_b)) = (owners[p], owners[q]);
+					manifold_cache.push(CachedManifold{ a: a, b: b, fixture_a: fixture_a, fixture_b: fixture_b,
+						points_a: points_a.clone(), points_b: points_b.clone(),
+						relative_position: relative_position, relative_angle: relative_angle, manifold: None });
+				}
+			}
+		}
+
+		manifold_cache.sort_by_key(|cached| cached_key(cached));
+
+		let pairs: Vec<(Body, Body)> = fresh.iter()
+			.map(|&i| (skinned[indices[i].0].clone(), skinned[indices[i].1].clone()))
+			.collect();
+		// The narrow phase gives one result per pair, in the same order
+		for (&i, result) in fresh.iter().zip(self.narrow_phase.run(&pairs)) {
+			let (p, q) = indices[i];
+			if let Ok(index) = manifold_cache.binary_search_by_key(&key(p, q), &cached_key) {
+				if result.is_colliding {
+					let transform_a = skinned[p].transform();
+					manifold_cache[index].manifold = result.manifold.as_ref()
+						.map(|manifold| to_frame(manifold, |point| transform_a.apply_inverse(point), |normal| transform_a.rotation.inv_rotate(normal)));
+				}
+			}
+			results[i] = Some(result);
+		}
+		self.manifold_cache = manifold_cache;
+		return results.into_iter().map(|result| result.unwrap_or(ColliderResult::new_empty_false())).collect();
+	}
+
+	// A copy of the body with its polygon grown by the skin, and its bounds to match.
+	// Builds the copies of the static bodies again if one was added, removed, moved with
+	// set_transform, reshaped with set_shape or switched on or off since the last time, or
+	// the skin has changed. Other changes to a static body's fields only show after that.
+	fn refresh_static_proxies(&mut self) {
+		let unchanged = self.static_skin == self.polygon_skin && {
+			let mut stored = self.static_key.iter();
+			self.bodies.iter().enumerate()
+				.filter(|&(_, body)| body.body_type == BodyType::StaticBody)
+				.all(|(i, body)| stored.next() == Some(&(i, body.handle, body.revision()))) && stored.next().is_none()
+		};
+		if unchanged {
+			return;
+		}
+
+		let mut proxies: Vec<Body> = Vec::new();
+		let mut owners: Vec<(usize, Option<usize>)> = Vec::new();
+		let mut key: Vec<(usize, BodyHandle, u32)> = Vec::new();
+		for (i, body) in self.bodies.iter().enumerate().filter(|&(_, body)| body.body_type == BodyType::StaticBody) {
+			key.push((i, body.handle, body.revision()));
+			let fixtures = ::std::iter::once(None).chain((0..body.fixtures.len()).map(Some));
+			for fixture in fixtures {
+				proxies.push(if body.fixtures.is_empty() { self.with_skin(body) } else { self.with_skin(&body.fixture_body(fixture)) });
+				owners.push((i, fixture));
+			}
+		}
+		self.static_proxies = proxies;
+		self.static_owners = owners;
+		self.static_key = key;
+		self.static_skin = self.polygon_skin;
+	}
+
+	fn with_skin(&self, body: &Body) -> Body {
+		let mut body = body.clone();
+		if let Shape::PolygonShape{..} = body.shape {
+			body.shape = body.shape.with_skin(self.polygon_skin);
+			body.update_aabb();
+		}
+		return body;
+	}
+
+	// Copies the impulses the solver ended with onto the contact points it solved.
+	fn record_impulses(&self, contacts: &mut [Contact]) {
+		for contact in contacts.iter_mut() {
+			let key = (contact.body_a, contact.body_b, contact.fixture_a, contact.fixture_b);
+			let cached = match self.contact_cache.binary_search_by_key(&key, |cached| (cached.a, cached.b, cached.fixture_a, cached.fixture_b)) {
+				Ok(index) => &self.contact_cache[index],
+				Err(_) => continue
+			};
+			for point in contact.manifold.points.iter_mut() {
+				if let Some(&(_, normal_impulse, tangent_impulse)) = cached.impulses.iter().find(|cached| cached.0 == point.id) {
+					point.normal_impulse = normal_impulse;
+					point.tangent_impulse = tangent_impulse;
+				}
+			}
+		}
+	}
+
+	// Hands the listener the impulses of every contact the solver ran, which are the ones
+	// it left in the contact cache.
+	fn report_impulses(&mut self, contacts: &[Contact]) {
+		if let Some(ref mut listener) = self.contact_listener {
+			for contact in contacts.iter() {
+				let key = (contact.body_a, contact.body_b, contact.fixture_a, contact.fixture_b);
+				if self.contact_cache.binary_search_by_key(&key, |cached| (cached.a, cached.b, cached.fixture_a, cached.fixture_b)).is_err() {
+					continue;
+				}
+				let impulse = ContactImpulse{
+					normal_impulses: contact.manifold.points.iter().map(|point| point.normal_impulse).collect(),
+					tangent_impulses: contact.manifold.points.iter().map(|point| point.tangent_impulse).collect()
+				};
+				listener.post_solve(contact.body_a, contact.body_b, &impulse);
+			}
+		}
+	}
+
+	// The friction and restitution of the body's own shape or one of its fixtures, and
+	// whether it is a sensor.
+	fn material(&self, body: usize, fixture: Option<usize>) -> (Scalar, Scalar, bool) {
+		let body = &self.bodies[body];
+		match fixture {
+			Some(index) => {
+				let fixture = &body.fixtures[index];
+				return (fixture.friction, fixture.restitution, fixture.is_sensor);
+			},
+			None => return (body.friction, body.restitution, body.is_sensor)
+		}
+	}
+
+	fn joint_prevents_collision(&self, a: usize, b: usize) -> bool {
+		for slot in self.joint_slots.iter() {
+			if let Some(ref joint) = slot.joint {
+				let (handle_a, handle_b) = (self.bodies[a].handle, self.bodies[b].handle);
+				let joins = (joint.body_a() == handle_a && joint.body_b() == handle_b) ||
+					(joint.body_a() == handle_b && joint.body_b() == handle_a);
+				if joins && !joint.collide_connected() {
+					return true;
+				}
+			}
+		}
+		return false;
+	}
+
+	// The slot and body indices of the joints the solver should run this step, skipping
+	// joints whose bodies are both asleep and those holding a body outside the active region.
+	fn active_joints(&self) -> Vec<(usize, usize, usize)> {
+		let mut joints: Vec<(usize, usize, usize)> = Vec::new();
+		for (i, slot) in self.joint_slots.iter().enumerate() {
+			if let Some(ref joint) = slot.joint {
+				if let (Some(a), Some(b)) = (self.body_index(joint.body_a()), self.body_index(joint.body_b())) {
+					let enabled = self.bodies[a].is_enabled() && self.bodies[b].is_enabled();
+					let frozen = self.frozen.get(a).cloned().unwrap_or(false) || self.frozen.get(b).cloned().unwrap_or(false);
+					if enabled && !frozen && (self.bodies[a].is_awake() || self.bodies[b].is_awake()) {
+						joints.push((i, a, b));
+					}
+				}
+			}
+		}
+		return joints;
+	}
+
+	fn prepare_joints(&mut self, time_step: Scalar) {
+		for (i, a, b) in self.active_joints() {
+			if let Some(ref mut joint) = self.joint_slots[i].joint {
+				joint.prepare(&mut self.bodies, a, b, time_step);
+			}
+		}
+	}
+
+	// Compares the pairs touching this step against the last step and reports the
+	// difference to the contact listener. Returns how many pairs began and ended.
+	fn update_contact_pairs(&mut self, contacts: &[Contact]) -> (u32, u32) {
+		let mut pairs: Vec<(usize, usize)> = contacts.iter()
+			.map(|c| (c.body_a.min(c.body_b), c.body_a.max(c.body_b)))
+			.collect();
+		pairs.sort();
+		pairs.dedup();
+
+		let begun: Vec<(usize, usize)> = pairs.iter().cloned()
+			.filter(|pair| self.contact_pairs.binary_search(pair).is_err())
+			.collect();
+		let ended: Vec<(usize, usize)> = self.contact_pairs.iter().cloned()
+			.filter(|pair| pairs.binary_search(pair).is_err())
+			.collect();
+		if let Some(ref mut listener) = self.contact_listener {
+			for &(a, b) in begun.iter() {
+				listener.begin_contact(a, b);
+			}
+			for &(a, b) in ended.iter() {
+				listener.end_contact(a, b);
+			}
+		}
+		self.contact_pairs = pairs;
+		return (begun.len() as u32, ended.len() as u32);
+	}
+}
+
+impl CollisionResolution for World {
+    fn resolve_collisions(&mut self, contacts: &Vec<Contact>) {
+        let mut constraints: Vec<ContactConstraint> = Vec::new();
+        for contact in contacts.iter() {
+            let a = contact.body_a;
+            let b = contact.body_b;
+            let (friction_a, restitution_a, sensor_a) = self.material(a, contact.fixture_a);
+            let (friction_b, restitution_b, sensor_b) = self.material(b, contact.fixture_b);
+            if sensor_a || sensor_b {
+                continue;
+            }
+            if !self.bodies[a].is_awake() && !self.bodies[b].is_awake() {
+                continue;
+            }
+            let inv_mass_a = self.bodies[a].inv_mass();
+            let inv_mass_b = self.bodies[b].inv_mass();
+            if inv_mass_a + inv_mass_b == 0.0 {
+                continue;
+            }
+            let inv_inertia_a = self.bodies[a].inv_inertia();
+            let inv_inertia_b = self.bodies[b].inv_inertia();
+            let normal = contact.manifold.normal;
+            let tangent = Vec2::new(-normal.y, normal.x);
+            let e = self.restitution_mixing.mix(restitution_a, restitution_b);
+
+            let mut points: Vec<ContactConstraintPoint> = Vec::new();
+            for point in contact.manifold.points.iter() {
+                let r_a = point.position - self.bodies[a].position;
+                let r_b = point.position - self.bodies[b].position;
+                let rn_a = r_a.cross(normal);
+                let rn_b = r_b.cross(normal);
+                let rt_a = r_a.cross(tangent);
+                let rt_b = r_b.cross(tangent);
+                let normal_inv_mass = inv_mass_a + inv_mass_b + inv_inertia_a * rn_a * rn_a + inv_inertia_b * rn_b * rn_b;
+                let tangent_inv_mass = inv_mass_a + inv_mass_b + inv_inertia_a * rt_a * rt_a + inv_inertia_b * rt_b * rt_b;
+
+                // Restitution is aimed at the approach speed from before any iteration ran
+                let vel_along_normal = self.relative_velocity(a, b, r_a, r_b).dot(normal);
+                let target_velocity = if vel_along_normal > self.restitution_threshold { -e * vel_along_normal } else { 0.0 };
+
+                points.push(ContactConstraintPoint{
+                    id: point.id,
+                    r_a: r_a,
+                    r_b: r_b,
+                    penetration: point.penetration,
+                    normal_mass: if normal_inv_mass > 0.0 { 1.0 / normal_inv_mass } else { 0.0 },
+                    tangent_mass: if tangent_inv_mass > 0.0 { 1.0 / tangent_inv_mass } else { 0.0 },
+                    target_velocity: target_velocity,
+                    normal_impulse: 0.0,
+                    tangent_impulse: 0.0
+                });
+            }
+
+            constraints.push(ContactConstraint{
+                a: a,
+                b: b,
+                fixture_a: contact.fixture_a,
+                fixture_b: contact.fixture_b,
+                normal: normal,
+                start_position_a: self.bodies[a].position,
+                start_position_b: self.bodies[b].position,
+                start_angle_a: self.bodies[a].angle,
+                start_angle_b: self.bodies[b].angle,
+                inv_mass_a: inv_mass_a,
+                inv_mass_b: inv_mass_b,
+                inv_inertia_a: inv_inertia_a,
+                inv_inertia_b: inv_inertia_b,
+                friction: self.friction_mixing.mix(friction_a, friction_b),
+                points: points
+            });
+        }
+
+        if self.warm_starting {
+            for constraint in constraints.iter_mut() {
+                self.warm_start(constraint);
+            }
+        }
+
+        // Islands share no body the solver can move, so each is solved on its own, all its
+        // velocity passes and then all its position passes
+        let joints = self.active_joints();
+        let mut pairs: Vec<(usize, usize)> = joints.iter().map(|&(_, a, b)| (a, b)).collect();
+        pairs.extend(constraints.iter().map(|constraint| (constraint.a, constraint.b)));
+        let mut islands = self.islands_of(&pairs);
+        let mut slots: Vec<Option<usize>> = vec![None; self.bodies.len()];
+        let mut island_work: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+        for (k, &(a, b)) in pairs.iter().enumerate() {
+            let body = if self.bodies[a].body_type == BodyType::DynamicBody { a } else { b };
+            let root = islands.find(body);
+            let slot = match slots[root] {
+                Some(slot) => slot,
+                None => {
+                    slots[root] = Some(island_work.len());
+                    island_work.push((Vec::new(), Vec::new()));
+                    island_work.len() - 1
+                }
+            };
+            if k < joints.len() {
+                island_work[slot].0.push(k);
+            } else {
+                island_work[slot].1.push(k - joints.len());
+            }
+        }
+
+        let mut passes = 0;
+        for (island_joints, island_constraints) in island_work.iter() {
+            for _ in 0..self.velocity_iterations {
+                for &j in island_joints.iter() {
+                    let (i, a, b) = joints[j];
+                    if let Some(ref mut joint) = self.joint_slots[i].joint {
+                        joint.solve_velocity(&mut self.bodies, a, b);
+                    }
+                }
+                for &c in island_constraints.iter() {
+                    self.solve_velocity(&mut constraints[c]);
+                }
+            }
+            for _ in 0..self.position_iterations {
+                for &j in island_joints.iter() {
+                    let (i, a, b) = joints[j];
+                    if let Some(ref mut joint) = self.joint_slots[i].joint {
+                        joint.solve_position(&mut self.bodies, a, b);
+                    }
+                }
+                for &c in island_constraints.iter() {
+                    self.solve_position(&constraints[c]);
+                }
+            }
+            passes += self.velocity_iterations + self.position_iterations;
+        }
+        self.solver_passes = passes as u32;
+        self.contact_cache = constraints.iter().map(|constraint| CachedContact{
+            a: constraint.a,
+            b: constraint.b,
+            fixture_a: constraint.fixture_a,
+            fixture_b: constraint.fixture_b,
+            impulses: constraint.points.iter().map(|p| (p.id, p.normal_impulse, p.tangent_impulse)).collect()
+        }).collect();
+        self.contact_cache.sort_by_key(|cached| (cached.a, cached.b, cached.fixture_a, cached.fixture_b));
+    }
+}
+
+impl World {
+    // Velocity of b relative to a at the contact point.
+    fn relative_velocity(&self, a: usize, b: usize, r_a: Vec2, r_b: Vec2) -> Vec2 {
+        return self.bodies[b].velocity + Vec2::scalar_cross(self.bodies[b].angular_velocity, r_b) -
+            self.bodies[a].velocity - Vec2::scalar_cross(self.bodies[a].angular_velocity, r_a);
+    }
+
+    // Pushes a along the impulse and b against it, at the contact point.
+    fn apply_contact_impulse(&mut self, constraint: &ContactConstraint, r_a: Vec2, r_b: Vec2, impulse: Vec2) {
+        let (a, b) = (constraint.a, constraint.b);
+        self.bodies[a].velocity += impulse.multiply(constraint.inv_mass_a);
+        self.bodies[a].angular_velocity += constraint.inv_inertia_a * r_a.cross(impulse);
+        self.bodies[b].velocity -= impulse.multiply(constraint.inv_mass_b);
+        self.bodies[b].angular_velocity -= constraint.inv_inertia_b * r_b.cross(impulse);
+    }
+
+    // Picks up the impulses of the points that were already touching last step, matched by
+    // the features that make them, and applies them.
+    fn warm_start(&mut self, constraint: &mut ContactConstraint) {
+        let key = (constraint.a, constraint.b, constraint.fixture_a, constraint.fixture_b);
+        let cached = match self.contact_cache.binary_search_by_key(&key, |cached| (cached.a, cached.b, cached.fixture_a, cached.fixture_b)) {
+            Ok(index) => self.contact_cache[index].impulses.clone(),
+            Err(_) => return
+        };
+        let normal = constraint.normal;
+        let tangent = Vec2::new(-normal.y, normal.x);
+        for i in 0..constraint.points.len() {
+            if let Some(&(_, normal_impulse, tangent_impulse)) = cached.iter().find(|cached| cached.0 == constraint.points[i].id) {
+                constraint.points[i].normal_impulse = normal_impulse;
+                constraint.points[i].tangent_impulse = tangent_impulse;
+                let impulse = normal.multiply(normal_impulse) - tangent.multiply(tangent_impulse);
+                let (r_a, r_b) = (constraint.points[i].r_a, constraint.points[i].r_b);
+                self.apply_contact_impulse(constraint, r_a, r_b, impulse);
+            }
+        }
+    }
+
+    // One sequential impulse pass over a contact. The impulses are accumulated over the
+    // iterations and clamped as totals, so later passes can take back what earlier ones
+    // overshot.
+    fn solve_velocity(&mut self, constraint: &mut ContactConstraint) {
+        let (a, b) = (constraint.a, constraint.b);
+        let normal = constraint.normal;
+        let tangent = Vec2::new(-normal.y, normal.x);
+
+        for i in 0..constraint.points.len() {
+            let (r_a, r_b) = (constraint.points[i].r_a, constraint.points[i].r_b);
+
+            let rv = self.relative_velocity(a, b, r_a, r_b);
+            let point = &mut constraint.points[i];
+            let old_impulse = point.normal_impulse;
+            point.normal_impulse = (old_impulse + (rv.dot(normal) - point.target_velocity) * point.normal_mass).max(0.0);
+            let impulse = normal.multiply(point.normal_impulse - old_impulse);
+            let normal_impulse = point.normal_impulse;
+            let tangent_mass = point.tangent_mass;
+            self.apply_contact_impulse(constraint, r_a, r_b, impulse);
+
+            // Friction opposes the sliding velocity, limited by the normal impulse
+            let rv = self.relative_velocity(a, b, r_a, r_b);
+            let max_friction = constraint.friction * normal_impulse;
+            let point = &mut constraint.points[i];
+            let old_impulse = point.tangent_impulse;
+            point.tangent_impulse = (old_impulse - rv.dot(tangent) * tangent_mass).max(-max_friction).min(max_friction);
+            let friction_impulse = tangent.multiply(old_impulse - point.tangent_impulse);
+            self.apply_contact_impulse(constraint, r_a, r_b, friction_impulse);
+        }
+    }
+
+    // Pushes the bodies apart by the baumgarte share of what is left of the penetration
+    // beyond the linear slop at each point, estimated from how far they have already been
+    // moved apart this step. No point is corrected by more than max_linear_correction in a
+    // step.
+    fn solve_position(&mut self, constraint: &ContactConstraint) {
+        let (a, b) = (constraint.a, constraint.b);
+        let normal = constraint.normal;
+        for point in constraint.points.iter() {
+            let moved_a = self.bodies[a].position - constraint.start_position_a +
+                Vec2::scalar_cross(self.bodies[a].angle - constraint.start_angle_a, point.r_a);
+            let moved_b = self.bodies[b].position - constraint.start_position_b +
+                Vec2::scalar_cross(self.bodies[b].angle - constraint.start_angle_b, point.r_b);
+            let penetration = point.penetration - (moved_a - moved_b).dot(normal);
+
+            let corrected = point.penetration - penetration;
+            let maximum = (penetration - self.linear_slop).max(0.0);
+            let correction = (maximum * self.baumgarte).min(self.max_linear_correction - corrected).max(0.0);
+            let impulse = normal.multiply(correction * point.normal_mass);
+            self.bodies[a].position += impulse.multiply(constraint.inv_mass_a);
+            self.bodies[a].angle += constraint.inv_inertia_a * point.r_a.cross(impulse);
+            self.bodies[b].position -= impulse.multiply(constraint.inv_mass_b);
+            self.bodies[b].angle -= constraint.inv_inertia_b * point.r_b.cross(impulse);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use super::super::joint::revolute_joint::RevoluteJoint;
+    use super::super::fixture::FixtureDef;
+
+    fn dynamic_box(position: Vec2, half_size: Scalar) -> BodyDef {
+        return BodyDef::builder(Shape::box_shape(half_size, half_size)).body_type(BodyType::DynamicBody).position(position).build();
+    }
+
+    fn ground(world: &mut World) -> BodyHandle {
+        return world.add_body(BodyDef::builder(Shape::box_shape(20.0, 0.5)).position(Vec2::new(0.0, 0.5)).build());
+    }
+
+    #[test]
+    fn static_bounds_only_change_with_set_transform() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let floor = ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -2.0), 0.5));
+        world.step(1.0 / 60.0);
+        let bounds = world.get_body(floor).unwrap().aabb();
+        let proxy_bounds = world.static_proxies[0].aabb();
+
+        // Writing the position directly is not seen by stepping
+        world.get_body_mut(floor).unwrap().position = Vec2::new(100.0, 0.5);
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.get_body(floor).unwrap().aabb(), bounds);
+        assert_eq!(world.static_proxies[0].aabb(), proxy_bounds);
+
+        world.get_body_mut(floor).unwrap().set_transform(Vec2::new(100.0, 0.5), 0.0);
+        assert!(world.get_body(floor).unwrap().aabb().center().approx_eq(Vec2::new(100.0, 0.5), 1.0e-5));
+        world.step(1.0 / 60.0);
+        assert!(world.static_proxies[0].aabb().center().approx_eq(Vec2::new(100.0, 0.5), 1.0e-5));
+    }
+
+    #[test]
+    fn step_result_counts_contacts_and_passes() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -0.6), 0.5));
+
+        // Falling with nothing touching leaves the solver nothing to do
+        let first = world.step(1.0 / 60.0);
+        assert_eq!(first.contacts_begun, 0);
+        assert_eq!(first.iterations_used, 0);
+
+        let mut landing = StepResult::default();
+        for _ in 0..60 {
+            landing = world.step(1.0 / 60.0);
+            if landing.contacts_begun > 0 {
+                break;
+            }
+        }
+        assert!(landing.contacts_begun >= 1);
+        assert!(landing.iterations_used >= world.velocity_iterations as u32);
+
+        // A box resting on the ground is one island, solved with every pass
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let resting = world.step(1.0 / 60.0);
+        assert_eq!(resting.iterations_used, (world.velocity_iterations + world.position_iterations) as u32);
+    }
+
+    #[test]
+    fn three_boxes_in_a_row_weld_into_one() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let tiles: Vec<BodyHandle> = (0..3).map(|i| {
+            return world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(i as Scalar, 0.0)).build());
+        }).collect();
+        let crate_box = world.add_body(dynamic_box(Vec2::new(1.0, -1.0), 0.5));
+
+        assert_eq!(world.optimize_static_geometry(), 2);
+        assert_eq!(world.bodies.len(), 2);
+        assert!(world.get_body(tiles[1]).is_none());
+        assert!(world.get_body(tiles[2]).is_none());
+        let aabb = world.get_body(tiles[0]).unwrap().aabb();
+        assert!(aabb.min.approx_eq(Vec2::new(-0.5, -0.5), 1.0e-5));
+        assert!(aabb.max.approx_eq(Vec2::new(2.5, 0.5), 1.0e-5));
+        assert_eq!(world.body_index(crate_box), Some(1));
+        assert_eq!(world.optimize_static_geometry(), 0);
+    }
+
+    #[test]
+    fn overlapping_pairs_lists_each_touching_pair_once() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(dynamic_box(Vec2::new(0.0, 0.0), 0.5));
+        world.add_body(dynamic_box(Vec2::new(5.0, 0.0), 0.5));
+        world.add_body(dynamic_box(Vec2::new(0.8, 0.0), 0.5));
+        assert_eq!(world.overlapping_pairs(), vec![(0, 2)]);
+    }
+
+    // Counts the begin and end contact calls it hears
+    struct ContactCounter {
+        counts: Rc<RefCell<(u32, u32)>>
+    }
+
+    impl ContactListener for ContactCounter {
+        fn begin_contact(&mut self, _a: usize, _b: usize) {
+            self.counts.borrow_mut().0 += 1;
+        }
+
+        fn end_contact(&mut self, _a: usize, _b: usize) {
+            self.counts.borrow_mut().1 += 1;
+        }
+    }
+
+    #[test]
+    fn landing_begins_one_contact() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let counts = Rc::new(RefCell::new((0, 0)));
+        world.set_contact_listener(Box::new(ContactCounter{ counts: counts.clone() }));
+        let floor = ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -2.0), 0.5));
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(*counts.borrow(), (1, 0));
+
+        world.remove_body(floor);
+        assert_eq!(*counts.borrow(), (1, 1));
+    }
+
+    #[test]
+    fn bodies_with_disjoint_masks_pass_through_each_other() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let ghost = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -1.0)).category_bits(0x0002).mask_bits(0xFFFF & !0x0001).build());
+        let solid = world.add_body(dynamic_box(Vec2::new(5.0, -1.0), 0.5));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(ghost).unwrap().position.y > 5.0);
+        assert!(world.get_body(solid).unwrap().position.y < 0.0);
+    }
+
+    #[test]
+    fn ray_cast_returns_the_closest_hit() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(1.0, 1.0)).position(Vec2::new(10.0, 0.0)).build());
+        world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0}).position(Vec2::new(5.0, 0.0)).build());
+
+        let hit = world.ray_cast(Vec2::new_zero(), Vec2::new(1.0, 0.0), 100.0).unwrap();
+        assert_eq!(hit.body, 1);
+        assert!((hit.fraction - 4.0).abs() < 1.0e-4);
+        assert!(hit.point.approx_eq(Vec2::new(4.0, 0.0), 1.0e-4));
+        assert!(hit.normal.approx_eq(Vec2::new(-1.0, 0.0), 1.0e-4));
+
+        let hit = world.ray_cast(Vec2::new(20.0, 0.5), Vec2::new(-1.0, 0.0), 100.0).unwrap();
+        assert_eq!(hit.body, 0);
+        assert!((hit.fraction - 9.0).abs() < 1.0e-4);
+        assert!(hit.normal.approx_eq(Vec2::new(1.0, 0.0), 1.0e-4));
+    }
+
+    #[test]
+    fn ray_cast_misses_and_stops_at_max_fraction() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0}).position(Vec2::new(5.0, 0.0)).build());
+        assert!(world.ray_cast(Vec2::new(0.0, 5.0), Vec2::new(1.0, 0.0), 100.0).is_none());
+        assert!(world.ray_cast(Vec2::new_zero(), Vec2::new(1.0, 0.0), 3.0).is_none());
+    }
+
+    #[test]
+    fn query_point_finds_the_body_under_the_point() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(1.0, 1.0)).position(Vec2::new(10.0, 0.0)).build());
+        world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0}).build());
+        assert_eq!(world.query_point(Vec2::new(10.5, 0.5)), Some(0));
+        assert_eq!(world.query_point(Vec2::new(0.5, -0.5)), Some(1));
+        assert_eq!(world.query_point(Vec2::new(5.0, 0.0)), None);
+    }
+
+    #[test]
+    fn removing_the_middle_body_keeps_the_others() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let first = world.add_body(dynamic_box(Vec2::new(-3.0, -0.49), 0.5));
+        let middle = world.add_body(dynamic_box(Vec2::new(0.0, -0.49), 0.5));
+        let last = world.add_body(dynamic_box(Vec2::new(3.0, -0.49), 0.5));
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contacts().len(), 3);
+
+        assert!(world.remove_body(middle));
+        assert_eq!(world.bodies.len(), 3);
+        assert_eq!(world.contacts().len(), 2);
+        assert!(world.contacts().iter().all(|contact| contact.body_a < 3 && contact.body_b < 3));
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(first).unwrap().position.x + 3.0).abs() < 1.0e-3);
+        assert!((world.get_body(last).unwrap().position.x - 3.0).abs() < 1.0e-3);
+        assert_eq!(world.get_body(last).unwrap().id, 2);
+        assert!(!world.remove_body(middle));
+    }
+
+    #[test]
+    fn handles_of_removed_bodies_stay_dead() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let a = world.add_body(dynamic_box(Vec2::new(0.0, 0.0), 0.5));
+        let b = world.add_body(dynamic_box(Vec2::new(10.0, 0.0), 0.5));
+        assert!(world.remove_body(a));
+        assert!(world.get_body(a).is_none());
+        assert!(world.get_body_mut(a).is_none());
+
+        // The freed slot is reused under a new generation
+        let c = world.add_body(dynamic_box(Vec2::new(20.0, 0.0), 0.5));
+        assert_eq!(c.index, a.index);
+        assert!(c != a);
+        assert!(world.get_body(a).is_none());
+        assert_eq!(world.get_body(b).unwrap().position.x, 10.0);
+        assert_eq!(world.get_body(c).unwrap().position.x, 20.0);
+    }
+
+    #[test]
+    fn resting_box_falls_asleep_and_wakes_on_impulse() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let resting = world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        let awake = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(3.0, -0.5)).allow_sleep(false).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(!world.get_body(resting).unwrap().is_awake());
+        assert!(world.get_body(awake).unwrap().is_awake());
+
+        world.get_body_mut(resting).unwrap().apply_impulse(Vec2::new(0.0, -3.0));
+        assert!(world.get_body(resting).unwrap().is_awake());
+        world.step(1.0 / 60.0);
+        assert!(world.get_body(resting).unwrap().position.y < -0.55);
+    }
+
+    fn slide(friction: Scalar) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(20.0, 0.5)).position(Vec2::new(0.0, 0.5)).friction(friction).build());
+        let block = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -0.5)).friction(friction).build());
+        world.get_body_mut(block).unwrap().apply_impulse(Vec2::new(3.0, 0.0));
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(block).unwrap().velocity.x;
+    }
+
+    #[test]
+    fn friction_slows_a_sliding_box() {
+        assert!(slide(0.5) < 0.1);
+        assert!(slide(0.0) > 2.9);
+    }
+
+    #[test]
+    fn rising_kinematic_platform_lifts_a_box() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let platform = world.add_body(BodyDef::builder(Shape::box_shape(2.0, 0.25)).body_type(BodyType::KinematicBody)
+            .position(Vec2::new(0.0, 0.25)).velocity(Vec2::new(0.0, -1.0)).build());
+        let rider = world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        // Still moving at its own speed, whatever rests on it
+        let platform = world.get_body(platform).unwrap();
+        assert_eq!(platform.velocity, Vec2::new(0.0, -1.0));
+        assert!((platform.position.y + 1.75).abs() < 1.0e-3);
+        assert!(world.get_body(rider).unwrap().position.y < -2.0);
+    }
+
+    #[test]
+    fn flipping_gravity_wakes_bodies_and_turns_them_around() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let sleeper = world.add_body(dynamic_box(Vec2::new(-3.0, -0.5), 0.5));
+        let faller = world.add_body(dynamic_box(Vec2::new(3.0, -10.0), 0.5));
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(faller).unwrap().velocity.y > 0.0);
+        assert!(!world.get_body(sleeper).unwrap().is_awake());
+
+        world.set_gravity(Vec2::new(0.0, -9.8));
+        assert_eq!(world.gravity(), Vec2::new(0.0, -9.8));
+        assert!(world.get_body(sleeper).unwrap().is_awake());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(faller).unwrap().velocity.y < 0.0);
+        assert!(world.get_body(sleeper).unwrap().position.y < -1.0);
+    }
+
+    #[test]
+    fn sensors_report_overlap_without_pushing() {
+        let mut world = World::new(Vec2::new_zero());
+        let counts = Rc::new(RefCell::new((0, 0)));
+        world.set_contact_listener(Box::new(ContactCounter{ counts: counts.clone() }));
+        world.add_body(BodyDef::builder(Shape::box_shape(1.0, 1.0)).position(Vec2::new(3.0, 0.0)).is_sensor(true).build());
+        let mover = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .velocity(Vec2::new(5.0, 0.0)).build());
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(*counts.borrow(), (1, 1));
+        assert_eq!(world.get_body(mover).unwrap().velocity, Vec2::new(5.0, 0.0));
+    }
+
+    #[test]
+    fn circle_comes_to_rest_on_an_edge() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::EdgeShape{vertex1: Vec2::new(-5.0, 0.0), vertex2: Vec2::new(5.0, 0.0), one_sided: true}).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -3.0)).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        let ball = world.get_body(ball).unwrap();
+        assert!((ball.position.y + 0.5).abs() < 0.02);
+        assert!(ball.velocity.length() < 0.05);
+    }
+
+    #[test]
+    fn upright_capsule_rests_on_its_end_cap() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let capsule = world.add_body(BodyDef::builder(Shape::CapsuleShape{center1: Vec2::new(0.0, -0.5), center2: Vec2::new(0.0, 0.5), radius: 0.25})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -2.0)).fixed_rotation(true).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        // The lower segment end sits a radius above the floor
+        assert!((world.get_body(capsule).unwrap().position.y + 0.75).abs() < 0.02);
+    }
+
+    // How far the top of a stack of five boxes has sunk below where it should rest.
+    fn stack_sink(iterations: usize) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.set_velocity_iterations(iterations);
+        world.set_position_iterations(iterations);
+        ground(&mut world);
+        let boxes: Vec<BodyHandle> = (0..5).map(|i| world.add_body(dynamic_box(Vec2::new(0.0, -0.5 - i as Scalar), 0.5))).collect();
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(boxes[4]).unwrap().position.y + 4.5;
+    }
+
+    #[test]
+    fn more_iterations_let_a_stack_sink_less() {
+        assert!(stack_sink(10).abs() < stack_sink(1).abs());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip_keeps_bodies_and_steps_the_same() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -1.0)).build());
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let json = ::serde_json::to_string(&world).unwrap();
+        let mut loaded: World = ::serde_json::from_str(&json).unwrap();
+        for (body, loaded_body) in world.bodies.iter().zip(loaded.bodies.iter()) {
+            assert_eq!(body.position, loaded_body.position);
+            assert_eq!(body.velocity, loaded_body.velocity);
+            assert_eq!(body.aabb(), loaded_body.aabb());
+        }
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            loaded.step(1.0 / 60.0);
+        }
+        assert_eq!(world.bodies[1].position, loaded.bodies[1].position);
+    }
+
+    // Where a small, fast circle ends up after being fired at a thin wall at x = 5
+    fn fire_at_wall(bullet: bool) -> Scalar {
+        let mut world = World::new(Vec2::new_zero());
+        let shot = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.05})
+            .body_type(BodyType::DynamicBody).velocity(Vec2::new(290.0, 0.0)).bullet(bullet).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.05, 1.0)).position(Vec2::new(5.0, 0.0)).build());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(shot).unwrap().position.x;
+    }
+
+    #[test]
+    fn bullets_stop_at_thin_walls_they_would_tunnel_through() {
+        assert!(fire_at_wall(false) > 5.0);
+        assert!(fire_at_wall(true) < 5.0);
+    }
+
+    #[test]
+    fn query_aabb_finds_each_body_in_the_region_once() {
+        let mut world = World::new(Vec2::new_zero());
+        for i in 0..10 {
+            for j in 0..10 {
+                world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+                    .position(Vec2::new(i as Scalar, j as Scalar)).build());
+            }
+        }
+        let mut found = world.query_aabb(AABB::new(Vec2::new(2.5, 2.9), Vec2::new(4.5, 4.1)));
+        found.sort();
+        assert_eq!(found, vec![33, 34, 43, 44]);
+        assert!(world.query_aabb(AABB::new(Vec2::new(20.0, 20.0), Vec2::new(21.0, 21.0))).is_empty());
+    }
+
+    #[test]
+    fn cloned_worlds_step_identically() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        for i in 0..6 {
+            world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.3})
+                .body_type(BodyType::DynamicBody).position(Vec2::new(i as Scalar * 0.37, -1.0 - i as Scalar * 0.7)).build());
+        }
+        for i in 0..4 {
+            world.add_body(dynamic_box(Vec2::new(-2.0 + i as Scalar * 0.1, -1.0 - i as Scalar * 0.7), 0.3));
+        }
+        let anchor = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).position(Vec2::new(5.0, -4.0)).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(3.0, -4.0)).build());
+        let joint = RevoluteJoint::new(world.get_body(anchor).unwrap(), world.get_body(ball).unwrap(), Vec2::new(5.0, -4.0));
+        world.add_joint(joint);
+        for _ in 0..20 {
+            world.step(1.0 / 60.0);
+        }
+
+        let mut copy = world.clone();
+        for _ in 0..100 {
+            world.step(1.0 / 60.0);
+            copy.step(1.0 / 60.0);
+        }
+        for (body, copied) in world.bodies.iter().zip(copy.bodies.iter()) {
+            assert_eq!(body.position, copied.position);
+            assert_eq!(body.velocity, copied.velocity);
+            assert_eq!(body.angle, copied.angle);
+        }
+    }
+
+    // Drops a perfectly elastic ball a short way onto elastic ground and returns its top
+    // speed over the last second
+    fn bounce(restitution_threshold: Scalar) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.set_restitution_threshold(restitution_threshold);
+        world.add_body(BodyDef::builder(Shape::box_shape(20.0, 0.5)).position(Vec2::new(0.0, 0.5)).restitution(1.0).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -0.4)).restitution(1.0).build());
+        let mut top_speed: Scalar = 0.0;
+        for i in 0..600 {
+            world.step(1.0 / 60.0);
+            if i >= 540 {
+                top_speed = top_speed.max(world.get_body(ball).unwrap().velocity.length());
+            }
+        }
+        return top_speed;
+    }
+
+    #[test]
+    fn elastic_ball_settles_below_the_restitution_threshold() {
+        assert!(bounce(DEFAULT_RESTITUTION_THRESHOLD) < 0.01);
+        assert!(bounce(0.0) > 0.05);
+    }
+
+    #[test]
+    fn profile_splits_the_step_into_its_phases() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        for i in 0..100 {
+            world.add_body(dynamic_box(Vec2::new((i % 10) as Scalar * 1.05, -1.0 - (i / 10) as Scalar * 1.05), 0.5));
+        }
+        world.step(1.0 / 60.0);
+        let profile = world.last_profile();
+        let phases = profile.integrate + profile.broad_phase + profile.narrow_phase + profile.solve;
+        assert!(profile.step > 0.0);
+        assert!(phases <= profile.step * 1.01);
+        assert_eq!(profile.body_count, 101);
+    }
+
+    #[test]
+    fn gravity_override_replaces_world_gravity_for_one_body() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let rising = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .gravity_override(Vec2::new(0.0, -9.8)).build());
+        let falling = world.add_body(dynamic_box(Vec2::new(5.0, 0.0), 0.5));
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(rising).unwrap().position.y < -1.0);
+        assert!(world.get_body(falling).unwrap().position.y > 1.0);
+    }
+
+    // Steps until a stack of six boxes falls asleep, or gives up after 2000 steps
+    fn steps_to_settle(warm_starting: bool) -> usize {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.set_warm_starting(warm_starting);
+        ground(&mut world);
+        for i in 0..6 {
+            world.add_body(dynamic_box(Vec2::new(0.0, -0.5 - i as Scalar), 0.5));
+        }
+        for step in 0..2000 {
+            world.step(1.0 / 60.0);
+            if world.bodies.iter().skip(1).all(|body| !body.is_awake()) {
+                return step;
+            }
+        }
+        return 2000;
+    }
+
+    #[test]
+    fn warm_starting_settles_a_stack_sooner() {
+        let warm = steps_to_settle(true);
+        assert!(warm < 2000);
+        assert!(warm < steps_to_settle(false));
+    }
+
+    #[test]
+    fn resting_box_exposes_its_floor_contact() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let crate_box = world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        ground(&mut world);
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let contacts = world.contacts();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].manifold.points.len(), 2);
+        let crate_index = world.body_index(crate_box).unwrap();
+        let up = if contacts[0].body_a == crate_index { contacts[0].manifold.normal } else { -contacts[0].manifold.normal };
+        assert!(up.y < -0.99);
+        for point in contacts[0].manifold.points.iter() {
+            assert!(point.position.y.abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn try_add_body_turns_down_massless_and_non_finite_bodies() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let point = Shape::LineShape{point1: Vec2::new_zero(), point2: Vec2::new_zero()};
+        let massless = BodyDef::builder(point.clone()).body_type(BodyType::DynamicBody).build();
+        assert_eq!(world.try_add_body(massless).err(), Some(BodyDefError::ZeroMass));
+        let lost = dynamic_box(Vec2::new(0.0, Scalar::NAN), 0.5);
+        assert_eq!(world.try_add_body(lost).err(), Some(BodyDefError::NonFinite));
+        assert!(world.bodies.is_empty());
+        // Static bodies need no mass
+        assert!(world.try_add_body(BodyDef::builder(point).build()).is_ok());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "dynamic body needs a mass")]
+    fn add_body_panics_on_a_massless_dynamic_body() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let line = Shape::LineShape{point1: Vec2::new_zero(), point2: Vec2::new(1.0, 0.0)};
+        world.add_body(BodyDef::builder(line).body_type(BodyType::DynamicBody).build());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "non finite")]
+    fn step_panics_on_a_non_finite_velocity() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let falling = world.add_body(dynamic_box(Vec2::new_zero(), 0.5));
+        world.get_body_mut(falling).unwrap().velocity.x = Scalar::NAN;
+        world.step(1.0 / 60.0);
+    }
+
+    #[test]
+    fn half_plane_holds_up_bodies_however_far_out_they_fall() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        // Everything below y = 2 is solid
+        world.add_body(BodyDef::builder(Shape::HalfPlaneShape{normal: Vec2::new(0.0, -1.0), offset: -2.0}).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.25})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(30.0, 0.0)).build());
+        let far_box = world.add_body(dynamic_box(Vec2::new(-100.0, 0.0), 0.5));
+        let capsule = world.add_body(BodyDef::builder(Shape::CapsuleShape{center1: Vec2::new(-0.5, 0.0), center2: Vec2::new(0.5, 0.0), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(5.0, 0.0)).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(ball).unwrap().position.y - 1.75).abs() < 0.02);
+        assert!((world.get_body(far_box).unwrap().position.y - 1.5).abs() < 0.02);
+        assert!((world.get_body(capsule).unwrap().position.y - 1.8).abs() < 0.02);
+
+        let hit = world.ray_cast(Vec2::new(50.0, -5.0), Vec2::new(0.0, 1.0), 100.0).unwrap();
+        assert!((hit.fraction - 7.0).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn half_plane_turns_and_moves_with_its_body() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::HalfPlaneShape{normal: Vec2::new(1.0, 0.0), offset: 0.0})
+            .position(Vec2::new(0.0, 3.0)).angle(-consts::FRAC_PI_2).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.25})
+            .body_type(BodyType::DynamicBody).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(ball).unwrap().position.y - 2.75).abs() < 0.02);
+    }
+
+    #[test]
+    fn clear_ends_contacts_and_keeps_settings() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let counts = Rc::new(RefCell::new((0, 0)));
+        world.set_contact_listener(Box::new(ContactCounter{ counts: counts.clone() }));
+        world.set_velocity_iterations(5);
+        ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        world.clear();
+        assert!(world.bodies.is_empty());
+        assert!(world.contacts().is_empty());
+        assert_eq!(*counts.borrow(), (1, 1));
+        assert_eq!(world.gravity(), Vec2::new(0.0, 9.8));
+        assert_eq!(world.velocity_iterations, 5);
+
+        // The listener stays and hears the next world's contacts
+        ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        world.step(1.0 / 60.0);
+        assert_eq!(*counts.borrow(), (2, 1));
+    }
+
+    #[test]
+    fn box_rests_on_the_skin_without_jitter() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let resting = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -0.5)).allow_sleep(false).build());
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let (mut lowest, mut highest): (Scalar, Scalar) = (1.0e9, -1.0e9);
+        for _ in 0..200 {
+            world.step(1.0 / 60.0);
+            let y = world.get_body(resting).unwrap().position.y;
+            lowest = lowest.min(y);
+            highest = highest.max(y);
+        }
+        assert!(highest - lowest < world.polygon_skin());
+        // It sits on the skin of both boxes, sunk in by the slop
+        let expected = -0.5 - 2.0 * DEFAULT_POLYGON_SKIN + DEFAULT_LINEAR_SLOP;
+        assert!((lowest - expected).abs() < 0.003);
+    }
+
+    #[test]
+    fn body_iterators_filter_by_type_and_region() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        for i in 0..3 {
+            world.add_body(dynamic_box(Vec2::new(i as Scalar * 2.0, 0.0), 0.5));
+        }
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(0.0, 5.0)).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::KinematicBody).position(Vec2::new(0.0, 9.0)).build());
+        assert_eq!(world.dynamic_bodies().count(), 3);
+        assert_eq!(world.static_bodies().count(), 1);
+        let region = AABB::new(Vec2::new(1.0, -1.0), Vec2::new(10.0, 1.0));
+        assert_eq!(world.bodies_in(region).map(|body| body.id).collect::<Vec<usize>>(), vec![1, 2]);
+    }
+
+    fn sub_step_scene() -> World {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        world.add_body(dynamic_box(Vec2::new(0.0, -0.3), 0.25));
+        world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.2})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(3.0, -2.0)).build());
+        return world;
+    }
+
+    #[test]
+    fn one_long_step_split_into_sub_steps_matches_many_short_ones() {
+        let mut split = sub_step_scene();
+        split.set_max_sub_step(1.0 / 60.0);
+        split.step(0.25);
+        let mut manual = sub_step_scene();
+        for _ in 0..15 {
+            manual.step(1.0 / 60.0);
+        }
+        for (body, manual_body) in split.bodies.iter().zip(manual.bodies.iter()) {
+            assert!(body.position.approx_eq(manual_body.position, 1.0e-4));
+        }
+    }
+
+    #[test]
+    fn group_index_overrides_the_masks() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        // A shared negative group never collides
+        world.add_body(BodyDef::builder(Shape::box_shape(20.0, 0.5)).position(Vec2::new(0.0, 0.5)).group_index(-1).build());
+        let ghost = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -0.6)).group_index(-1).build());
+        // A shared positive group always does, even with no mask bits
+        world.add_body(BodyDef::builder(Shape::box_shape(1.0, 0.5)).position(Vec2::new(30.0, 0.5)).group_index(3).build());
+        let grouped = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(30.0, -0.6)).mask_bits(0).group_index(3).build());
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(ghost).unwrap().position.y > 2.0);
+        assert!((world.get_body(grouped).unwrap().position.y + 0.5).abs() < 0.05);
+    }
+
+    // How fast two balls of restitution 0.8 and 0.5 part after meeting head on at 10
+    fn parting_speed(rule: MixingRule) -> Scalar {
+        let mut world = World::new(Vec2::new_zero());
+        world.set_restitution_mixing(rule);
+        let ball = |x: Scalar, speed: Scalar, restitution: Scalar| {
+            return BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5}).body_type(BodyType::DynamicBody)
+                .position(Vec2::new(x, 0.0)).velocity(Vec2::new(speed, 0.0)).restitution(restitution).build();
+        };
+        let a = world.add_body(ball(-1.0, 5.0, 0.8));
+        let b = world.add_body(ball(1.0, -5.0, 0.5));
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(b).unwrap().velocity.x - world.get_body(a).unwrap().velocity.x;
+    }
+
+    #[test]
+    fn restitution_mixing_rule_sets_the_bounce() {
+        assert!((parting_speed(MixingRule::Max) - 8.0).abs() < 0.2);
+        assert!((parting_speed(MixingRule::Multiply) - 4.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn box_teleported_into_the_floor_is_pushed_out_gently() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let teleported = world.add_body(dynamic_box(Vec2::new(0.0, -5.0), 0.5));
+        world.set_max_linear_correction(0.05);
+        // Half of the box is now in the floor
+        world.get_body_mut(teleported).unwrap().set_transform(Vec2::new_zero(), 0.0);
+        let mut fastest: Scalar = 0.0;
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            fastest = fastest.max(world.get_body(teleported).unwrap().velocity.length());
+        }
+        assert!(fastest < 0.2);
+        assert!((world.get_body(teleported).unwrap().position.y + 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn user_data_survives_stepping() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let tagged = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .user_data(0xDEADBEEF).build());
+        let untagged = world.add_body(dynamic_box(Vec2::new(3.0, 0.0), 0.5));
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.get_body(tagged).unwrap().user_data, 0xDEADBEEF);
+        assert_eq!(world.get_body(untagged).unwrap().user_data, 0);
+    }
+
+    #[test]
+    fn line_wall_holds_up_circles_and_boxes() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::LineShape{point1: Vec2::new(-5.0, 0.0), point2: Vec2::new(5.0, 0.0)})
+            .position(Vec2::new(0.0, 2.0)).build());
+        let ball = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.25})
+            .body_type(BodyType::DynamicBody).build());
+        let tilted = world.add_body(BodyDef::builder(Shape::box_shape(0.25, 0.25)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(3.0, 0.0)).angle(0.3).build());
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(ball).unwrap().position.y - 1.75).abs() < 0.03);
+        assert!((world.get_body(tilted).unwrap().position.y - 1.75).abs() < 0.03);
+    }
+
+    #[test]
+    fn advance_runs_whole_fixed_steps_and_keeps_the_rest() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(dynamic_box(Vec2::new_zero(), 0.5));
+        let exact = world.advance(0.05, 1.0 / 60.0);
+        assert_eq!(exact.steps, 3);
+        assert!(exact.leftover.abs() < 1.0e-6);
+
+        let partial = world.advance(0.025, 1.0 / 60.0);
+        assert_eq!(partial.steps, 1);
+        assert!((partial.leftover - (0.025 - 1.0 / 60.0)).abs() < 1.0e-5);
+        assert!((partial.alpha - 0.5).abs() < 1.0e-3);
+
+        // A long frame only counts as MAX_FRAME_TIME
+        assert_eq!(world.advance(10.0, 1.0 / 60.0).steps, 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed step must be positive")]
+    fn advance_panics_on_a_zero_fixed_step() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.advance(1.0 / 60.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed step must be positive")]
+    fn advance_panics_on_a_nan_fixed_step() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.advance(1.0 / 60.0, Scalar::NAN);
+    }
+
+    #[test]
+    fn interpolation_runs_from_the_last_step_to_this_one() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let spinning = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .angular_velocity(1.0).build());
+        for _ in 0..3 {
+            world.step(1.0 / 60.0);
+        }
+        let body = world.get_body(spinning).unwrap();
+        let (start, start_angle) = body.interpolated_transform(0.0);
+        let (end, end_angle) = body.interpolated_transform(1.0);
+        assert_eq!((start, start_angle), (body.previous_position, body.previous_angle));
+        assert_eq!((end, end_angle), (body.position, body.angle));
+        assert!(start != end && start_angle != end_angle);
+        let (middle, _) = body.interpolated_transform(0.5);
+        assert!(middle.approx_eq((start + end).multiply(0.5), 1.0e-6));
+    }
+
+    #[test]
+    fn bodies_touching_reads_the_current_contacts() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let floor = ground(&mut world);
+        let resting = world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        let falling = world.add_body(dynamic_box(Vec2::new(3.0, -4.0), 0.5));
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        let (floor, resting, falling) = (world.body_index(floor).unwrap(), world.body_index(resting).unwrap(), world.body_index(falling).unwrap());
+        assert_eq!(world.bodies_touching(resting), vec![floor]);
+        assert!(world.bodies_touching(falling).is_empty());
+        for _ in 0..90 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.bodies_touching(floor), vec![resting, falling]);
+    }
+
+    #[test]
+    fn ground_normal_leaves_out_slopes_steeper_than_the_limit() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let slope = (30.0 as Scalar).to_radians();
+        let ramp = world.add_body(BodyDef::builder(Shape::box_shape(10.0, 0.5)).position(Vec2::new(0.0, 2.0)).angle(-slope).friction(1.0).build());
+        let standing = world.add_body(BodyDef::builder(Shape::box_shape(0.3, 0.3)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, 1.0)).angle(-slope).friction(1.0).build());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let index = world.body_index(standing).unwrap();
+        let normal = world.ground_normal(index, (45.0 as Scalar).to_radians()).unwrap();
+        assert!((normal.dot(Vec2::new(0.0, -1.0)) - slope.cos()).abs() < 0.01);
+        assert!(world.ground_normal(index, (15.0 as Scalar).to_radians()).is_none());
+        // The ramp is pushed down, not held up
+        assert!(world.ground_normal(world.body_index(ramp).unwrap(), (45.0 as Scalar).to_radians()).is_none());
+    }
+
+    #[test]
+    fn sliding_box_keeps_its_contact_ids_and_impulses() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(20.0, 0.5)).position(Vec2::new(0.0, 0.5)).friction(0.1).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-3.0, -0.5)).velocity(Vec2::new(4.0, 0.0)).friction(0.1).build());
+        for _ in 0..20 {
+            world.step(1.0 / 60.0);
+        }
+        let ids = |world: &World| {
+            let mut ids: Vec<ContactId> = world.contacts()[0].manifold.points.iter().map(|point| point.id).collect();
+            ids.sort_by_key(|id| (id.index_a, id.index_b));
+            return ids;
+        };
+        let first = ids(&world);
+        assert_eq!(first.len(), 2);
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+            assert_eq!(ids(&world), first);
+            // The kept impulses carry the box's weight, and friction at the sliding limit
+            let points = &world.contacts()[0].manifold.points;
+            let normal: Scalar = points.iter().map(|point| point.normal_impulse).sum();
+            let tangent: Scalar = points.iter().map(|point| point.tangent_impulse).sum();
+            assert!((normal - 9.8 / 60.0).abs() < 0.02);
+            assert!((tangent.abs() - 0.1 * 9.8 / 60.0).abs() < 0.005);
+        }
+    }
+
+    #[test]
+    fn disabled_bodies_neither_move_nor_collide() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let held = world.add_body(dynamic_box(Vec2::new(0.0, -4.0), 0.5));
+        let hidden = world.add_body(dynamic_box(Vec2::new(0.0, -1.0), 0.5));
+        world.get_body_mut(held).unwrap().set_enabled(false);
+        world.get_body_mut(hidden).unwrap().set_enabled(false);
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.get_body(held).unwrap().position, Vec2::new(0.0, -4.0));
+        assert!(world.contacts().is_empty());
+
+        // Once enabled it falls through the disabled box onto the ground
+        world.get_body_mut(held).unwrap().set_enabled(true);
+        for _ in 0..150 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(held).unwrap().position.y + 0.5).abs() < 0.05);
+        assert_eq!(world.get_body(hidden).unwrap().position, Vec2::new(0.0, -1.0));
+    }
+
+    #[cfg(feature = "f64")]
+    #[test]
+    fn f64_world_steps_in_double_precision() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let falling = world.add_body(dynamic_box(Vec2::new(0.0, -3.0), 0.5));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let position: f64 = world.get_body(falling).unwrap().position.y;
+        assert!((position + 0.5).abs() < 0.02);
+        // A step far too small for f32 still moves a falling body
+        let mut fine = World::new(Vec2::new(0.0, 9.8));
+        let body = fine.add_body(dynamic_box(Vec2::new(0.0, 1.0e6), 0.5));
+        fine.get_body_mut(body).unwrap().velocity = Vec2::new(0.0, 1.0);
+        fine.step(1.0e-4);
+        assert!(fine.get_body(body).unwrap().position.y > 1.0e6);
+    }
+
+    #[test]
+    fn kinetic_energy_sums_moving_bodies_and_drains_from_a_stack() {
+        let mut world = World::new(Vec2::new_zero());
+        let spinning = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .mass(2.0).velocity(Vec2::new(3.0, 4.0)).angular_velocity(2.0).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(5.0, 5.0)).build());
+        let inertia = world.get_body(spinning).unwrap().inertia;
+        assert!((world.kinetic_energy() - (0.5 * 2.0 * 25.0 + 0.5 * inertia * 4.0)).abs() < 1.0e-4);
+
+        let mut stack = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut stack);
+        for i in 0..3 {
+            stack.add_body(dynamic_box(Vec2::new(0.0, -0.55 - i as Scalar), 0.5));
+        }
+        for _ in 0..300 {
+            stack.step(1.0 / 60.0);
+        }
+        assert!(stack.kinetic_energy() < 1.0e-3);
+    }
+
+    #[test]
+    fn cached_manifold_matches_a_fresh_one_for_an_unmoved_pair() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::KinematicBody)
+            .position(Vec2::new(0.3, -0.95)).angle(0.1).build());
+        world.step(1.0 / 60.0);
+        assert_eq!(world.manifold_cache.len(), 1);
+        assert!(world.manifold_cache[0].manifold.is_some());
+
+        let mut fresh = world.clone();
+        fresh.manifold_cache.clear();
+        world.step(1.0 / 60.0);
+        fresh.step(1.0 / 60.0);
+        assert_eq!(format!("{:?}", world.contacts()[0].manifold), format!("{:?}", fresh.contacts()[0].manifold));
+    }
+
+    #[test]
+    fn fixtures_add_their_mass_and_collide() {
+        let feet = FixtureDef{ density: 2.0, ..FixtureDef::new(Shape::box_shape(0.5, 0.5).translated(Vec2::new(0.0, 1.0))) };
+        let sensor = FixtureDef{ is_sensor: true, ..FixtureDef::new(Shape::box_shape(0.2, 0.1).translated(Vec2::new(0.0, 1.6))) };
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let walker = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -3.0)).fixture(feet).fixture(sensor).build());
+        // One unit of area at density 1, one at density 2 and the sensor's 0.08
+        assert!((world.get_body(walker).unwrap().mass - 3.08).abs() < 1.0e-4);
+        assert!((world.get_body(walker).unwrap().aabb().max.y + 1.3).abs() < 1.0e-4);
+
+        // It stands on its feet, with the sensor reaching into the ground
+        ground(&mut world);
+        for _ in 0..240 {
+            world.step(1.0 / 60.0);
+        }
+        assert!((world.get_body(walker).unwrap().position.y + 1.5).abs() < 0.05);
+        assert!(world.ground_normal(world.body_index(walker).unwrap(), 0.5).is_some());
+        assert_eq!(world.debug_draw_data().len(), 4);
+    }
+
+    #[test]
+    fn radial_impulse_falls_off_with_distance() {
+        let mut world = World::new(Vec2::new_zero());
+        let near = world.add_body(dynamic_box(Vec2::new(1.0, 0.0), 0.1));
+        let far = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.0, -3.0)).mass(0.5).build());
+        let outside = world.add_body(dynamic_box(Vec2::new(5.0, 0.0), 0.1));
+        world.apply_radial_impulse(Vec2::new_zero(), 4.0, 8.0);
+        // 3/4 of the strength on a mass of 0.04, and 1/4 of it on a mass of 0.5
+        assert!(world.get_body(near).unwrap().velocity.approx_eq(Vec2::new(150.0, 0.0), 1.0e-2));
+        assert!(world.get_body(far).unwrap().velocity.approx_eq(Vec2::new(0.0, -4.0), 1.0e-5));
+        assert_eq!(world.get_body(outside).unwrap().velocity, Vec2::new_zero());
+    }
+
+    #[test]
+    fn closest_body_measures_to_the_nearest_surface() {
+        let mut world = World::new(Vec2::new_zero());
+        let square = world.add_body(dynamic_box(Vec2::new(3.0, 0.0), 0.5));
+        let circle = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 1.0})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -3.4)).build());
+        let corner = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(-4.0, 4.0)).build());
+        let index = |handle: BodyHandle| world.body_index(handle);
+        // The circle's surface is 2.4 away, nearer than the square's 2.5
+        assert_eq!(world.closest_body(Vec2::new_zero(), 10.0), index(circle));
+        assert_eq!(world.closest_body(Vec2::new(0.5, 0.0), 10.0), index(square));
+        assert_eq!(world.closest_body(Vec2::new(-3.0, 3.0), 10.0), index(corner));
+        assert_eq!(world.closest_body(Vec2::new_zero(), 2.0), None);
+        // A point inside a shape is zero away
+        assert_eq!(world.closest_body(Vec2::new(3.0, 0.2), 0.0), index(square));
+    }
+
+    // How far a box dropped into the ground still is from its resting height after five steps
+    fn sunk_after_five_steps(baumgarte: Scalar) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.set_baumgarte(baumgarte);
+        world.set_position_iterations(1);
+        ground(&mut world);
+        let sunk = world.add_body(dynamic_box(Vec2::new(0.0, -0.3), 0.5));
+        for _ in 0..5 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(sunk).unwrap().position.y + 0.5;
+    }
+
+    #[test]
+    fn higher_baumgarte_pushes_overlap_out_faster() {
+        assert!(sunk_after_five_steps(0.8) < sunk_after_five_steps(0.1));
+    }
+
+    #[test]
+    fn correction_settings_are_kept_in_range() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.set_linear_slop(-1.0);
+        assert_eq!(world.linear_slop(), 0.0);
+        world.set_baumgarte(3.0);
+        assert_eq!(world.baumgarte(), 1.0);
+    }
+
+    // Whether a body drifting at 0.05 is still awake after half a second
+    fn drifter_awake(linear_threshold: Scalar) -> bool {
+        let mut world = World::new(Vec2::new_zero());
+        world.set_sleep_thresholds(linear_threshold, 0.03, 0.25);
+        let drifter = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .velocity(Vec2::new(0.05, 0.0)).build());
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(drifter).unwrap().is_awake();
+    }
+
+    #[test]
+    fn raising_the_linear_threshold_lets_drifting_bodies_sleep() {
+        assert!(drifter_awake(0.01));
+        assert!(!drifter_awake(0.1));
+    }
+
+    #[test]
+    fn zero_time_to_sleep_keeps_kinematic_platforms_moving() {
+        let mut world = World::new(Vec2::new_zero());
+        world.set_sleep_thresholds(0.05, 0.03, 0.0);
+        let platform = world.add_body(BodyDef::builder(Shape::box_shape(2.0, 0.1)).body_type(BodyType::KinematicBody)
+            .velocity(Vec2::new(1.0, 0.0)).build());
+        let idle = world.add_body(dynamic_box(Vec2::new(20.0, 0.0), 0.5));
+        let thrown = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-20.0, 0.0)).velocity(Vec2::new(3.0, 0.0)).build());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let platform = world.get_body(platform).unwrap();
+        assert_eq!(platform.velocity, Vec2::new(1.0, 0.0));
+        assert!((platform.position.x - 1.0).abs() < 1.0e-4);
+        // Still bodies sleep at once, moving ones stay awake
+        assert!(!world.get_body(idle).unwrap().is_awake());
+        assert!(world.get_body(thrown).unwrap().is_awake());
+    }
+
+    #[test]
+    fn separate_islands_sleep_apart() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let bottom = world.add_body(dynamic_box(Vec2::new(0.0, -0.5), 0.5));
+        let top = world.add_body(dynamic_box(Vec2::new(0.0, -1.5), 0.5));
+        let pin = world.add_body(BodyDef::builder(Shape::box_shape(0.1, 0.1)).position(Vec2::new(50.0, -10.0)).build());
+        let swinging = world.add_body(dynamic_box(Vec2::new(55.0, -10.0), 0.5));
+        let joint = RevoluteJoint::new(world.get_body(pin).unwrap(), world.get_body(swinging).unwrap(), Vec2::new(50.0, -10.0));
+        world.add_joint(joint);
+        for _ in 0..240 {
+            world.step(1.0 / 60.0);
+        }
+        let (bottom, top, swinging) = (world.body_index(bottom).unwrap(), world.body_index(top).unwrap(), world.body_index(swinging).unwrap());
+        assert_eq!(world.islands(), vec![vec![bottom, top], vec![swinging]]);
+        // The stack has gone to sleep while the pendulum keeps swinging
+        assert!(!world.body(bottom).is_awake() && !world.body(top).is_awake());
+        assert!(world.body(swinging).is_awake());
+        assert_eq!(world.constraint_count(bottom), 2);
+        assert_eq!(world.constraint_count(swinging), 1);
+    }
+
+    #[test]
+    fn light_bodies_float_and_dense_ones_sink() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_buoyancy_region(AABB::new(Vec2::new(-10.0, 0.0), Vec2::new(10.0, 50.0)), 1.0, 2.0);
+        let float = |world: &mut World, shape: Shape, x: Scalar, density: Scalar| {
+            return world.add_body(BodyDef::builder(shape).body_type(BodyType::DynamicBody)
+                .position(Vec2::new(x, -2.0)).density(density).build());
+        };
+        let wood = float(&mut world, Shape::box_shape(0.5, 0.5), 0.0, 0.5);
+        let metal = float(&mut world, Shape::box_shape(0.5, 0.5), 5.0, 3.0);
+        let ball = float(&mut world, Shape::CircleShape{center: Vec2::new_zero(), radius: 0.5}, -5.0, 0.5);
+        for _ in 0..900 {
+            world.step(1.0 / 60.0);
+        }
+        // Half as dense as the water, so they float half under
+        let wood = world.get_body(wood).unwrap();
+        assert!(wood.position.y.abs() < 0.01);
+        assert!(wood.velocity.length() < 0.01);
+        assert!(world.get_body(ball).unwrap().position.y.abs() < 0.02);
+        assert!(world.get_body(metal).unwrap().position.y > 10.0);
+    }
+
+    #[test]
+    fn max_linear_speed_caps_only_its_own_body() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let capped = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .max_linear_speed(5.0).build());
+        let free = world.add_body(dynamic_box(Vec2::new(10.0, 0.0), 0.5));
+        world.get_body_mut(capped).unwrap().apply_impulse(Vec2::new(100.0, -50.0));
+        world.get_body_mut(free).unwrap().apply_impulse(Vec2::new(100.0, 0.0));
+        world.step(1.0 / 60.0);
+        let velocity = world.get_body(capped).unwrap().velocity;
+        assert!((velocity.length() - 5.0).abs() < 1.0e-5);
+        // Slowed down without turning
+        assert!(velocity.x > 0.0 && velocity.y < 0.0);
+        assert!(world.get_body(free).unwrap().velocity.length() > 50.0);
+    }
+
+    // Lets bodies through the platform from below and holds them up from above
+    struct OneWayPlatform {
+        platform: usize,
+        passing: Vec<(usize, usize)>
+    }
+
+    impl ContactListener for OneWayPlatform {
+        fn end_contact(&mut self, a: usize, b: usize) {
+            self.passing.retain(|&pair| pair != (a, b));
+        }
+
+        fn pre_solve(&mut self, a: usize, b: usize, manifold: &Manifold) -> bool {
+            if a != self.platform && b != self.platform {
+                return true;
+            }
+            let pair = (a.min(b), a.max(b));
+            if self.passing.contains(&pair) {
+                return false;
+            }
+            // The normal from the platform to the other body
+            let normal = if a == self.platform { manifold.normal.multiply(-1.0) } else { manifold.normal };
+            if normal.y < -0.5 {
+                return true;
+            }
+            self.passing.push(pair);
+            return false;
+        }
+    }
+
+    #[test]
+    fn pre_solve_makes_a_one_way_platform() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let platform = world.add_body(BodyDef::builder(Shape::box_shape(3.0, 0.1)).build());
+        let rising = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(-1.5, 2.0)).velocity(Vec2::new(0.0, -12.0)).build());
+        let falling = world.add_body(dynamic_box(Vec2::new(1.5, -2.0), 0.5));
+        let platform_index = world.body_index(platform).unwrap();
+        world.set_contact_listener(Box::new(OneWayPlatform{ platform: platform_index, passing: Vec::new() }));
+
+        let mut highest: Scalar = 10.0;
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+            highest = highest.min(world.get_body(rising).unwrap().position.y);
+        }
+        // The rising box went up through the platform and landed on top, like the falling one
+        assert!(highest < -0.6);
+        assert!((world.get_body(rising).unwrap().position.y + 0.6).abs() < 0.05);
+        assert!((world.get_body(falling).unwrap().position.y + 0.6).abs() < 0.05);
+    }
+
+    struct ImpactLog {
+        impacts: Rc<RefCell<Vec<(usize, usize, Scalar)>>>
+    }
+
+    impl ContactListener for ImpactLog {
+        fn post_solve(&mut self, a: usize, b: usize, impulse: &ContactImpulse) {
+            self.impacts.borrow_mut().push((a, b, impulse.normal_impulses.iter().sum()));
+        }
+    }
+
+    #[test]
+    fn post_solve_reports_harder_landings_for_heavier_boxes() {
+        let impacts = Rc::new(RefCell::new(Vec::new()));
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        ground(&mut world);
+        let light = world.add_body(dynamic_box(Vec2::new(-5.0, -5.0), 0.5));
+        let heavy = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(5.0, -5.0)).density(10.0).build());
+        let (light, heavy) = (world.body_index(light).unwrap(), world.body_index(heavy).unwrap());
+        world.set_contact_listener(Box::new(ImpactLog{ impacts: impacts.clone() }));
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+
+        let peak = |body: usize| impacts.borrow().iter()
+            .filter(|impact| impact.0 == body || impact.1 == body)
+            .map(|impact| impact.2).fold(0.0, Scalar::max);
+        assert!(peak(light) > 0.0);
+        assert!(peak(heavy) > 5.0 * peak(light));
+    }
+
+    #[test]
+    fn bodies_outside_the_active_region_stay_frozen() {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        let near = world.add_body(dynamic_box(Vec2::new(0.0, 0.0), 0.5));
+        let far = world.add_body(dynamic_box(Vec2::new(100.0, 0.0), 0.5));
+        world.set_active_region(Some(AABB::new(Vec2::new(-10.0, -10.0), Vec2::new(10.0, 10.0))));
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(near).unwrap().position.y > 1.0);
+        assert_eq!(world.get_body(far).unwrap().position, Vec2::new(100.0, 0.0));
+
+        // Moving the region over the far body swaps which one falls
+        world.set_active_region(Some(AABB::new(Vec2::new(90.0, -10.0), Vec2::new(110.0, 10.0))));
+        let frozen_at = world.get_body(near).unwrap().position.y;
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(far).unwrap().position.y > 1.0);
+        assert_eq!(world.get_body(near).unwrap().position.y, frozen_at);
+
+        world.set_active_region(None);
+        world.step(1.0 / 60.0);
+        assert!(world.get_body(near).unwrap().position.y > frozen_at);
+    }
+
+    #[test]
+    fn bullets_sweep_every_fixture_against_every_fixture() {
+        let mut world = World::new(Vec2::new_zero());
+        // Both the shot and the thin wall are the second fixture of their bodies, level
+        // with each other, while the bodies' own shapes are well apart
+        let shot = world.add_body(BodyDef::builder(Shape::CircleShape{center: Vec2::new_zero(), radius: 0.05})
+            .body_type(BodyType::DynamicBody).position(Vec2::new(0.0, -10.0)).velocity(Vec2::new(290.0, 0.0)).bullet(true)
+            .fixture(FixtureDef::new(Shape::CircleShape{center: Vec2::new(0.0, 10.0), radius: 0.05})).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).position(Vec2::new(-20.0, -10.0))
+            .fixture(FixtureDef::new(Shape::PolygonShape{points: vec![Vec2::new(24.95, 9.0), Vec2::new(25.05, 9.0),
+                Vec2::new(25.05, 11.0), Vec2::new(24.95, 11.0)]})).build());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.get_body(shot).unwrap().position.x < 5.0);
+    }
+
+    // The angle a thin bar spinning about its middle has after one step past a post
+    fn spin_past_post(bullet: bool) -> Scalar {
+        let mut world = World::new(Vec2::new_zero());
+        let bar = world.add_body(BodyDef::builder(Shape::box_shape(2.0, 0.02)).body_type(BodyType::DynamicBody)
+            .angle(-1.0).angular_velocity(90.0).bullet(bullet).build());
+        world.add_body(BodyDef::builder(Shape::box_shape(0.05, 0.05)).position(Vec2::new(1.5, 0.0)).build());
+        world.step(1.0 / 60.0);
+        return world.get_body(bar).unwrap().angle;
+    }
+
+    #[test]
+    fn spinning_bullets_stop_at_the_corner_that_hits() {
+        // Both ends of the turn are clear of the post, but the bar sweeps through it
+        assert!((spin_past_post(false) - 0.5).abs() < 1.0e-4);
+        let angle = spin_past_post(true);
+        assert!(angle > -0.1 && angle < 0.0);
+    }
+
+    #[test]
+    fn max_linear_speed_holds_against_contact_pushes() {
+        let mut world = World::new(Vec2::new_zero());
+        let capped = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(1.5, 0.0)).max_linear_speed(5.0).build());
+        let hammer = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .velocity(Vec2::new(30.0, 0.0)).density(50.0).build());
+        let mut fastest: Scalar = 0.0;
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+            fastest = fastest.max(world.get_body(capped).unwrap().velocity.length());
+        }
+        // The hammer hit it hard enough to go well past the cap without one
+        assert!(world.get_body(hammer).unwrap().velocity.x > 20.0);
+        assert!((fastest - 5.0).abs() < 1.0e-4);
+    }
+
+    // The angle of a box dropped with most of it hanging over the edge of a ledge
+    fn tip_off_ledge(fixed_rotation: bool) -> Scalar {
+        let mut world = World::new(Vec2::new(0.0, 9.8));
+        world.add_body(BodyDef::builder(Shape::box_shape(2.0, 0.5)).position(Vec2::new(-2.0, 0.5)).build());
+        let player = world.add_body(BodyDef::builder(Shape::box_shape(0.5, 0.5)).body_type(BodyType::DynamicBody)
+            .position(Vec2::new(0.3, -1.0)).fixed_rotation(fixed_rotation).build());
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        return world.get_body(player).unwrap().angle;
+    }
+
+    #[test]
+    fn fixed_rotation_bodies_stay_level_when_hit_off_center() {
+        assert!(tip_off_ledge(false).abs() > 0.1);
+        assert_eq!(tip_off_ledge(true), 0.0);
     }
 }